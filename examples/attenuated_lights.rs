@@ -0,0 +1,54 @@
+use image::RgbImage;
+
+use std::path::Path;
+
+use tiny_computer_graphics::raytracer::{output, prelude::*};
+
+fn example_scene() -> Scene<DummyBackground> {
+    let ivory = Material::IVORY;
+    let floor = Material {
+        diffuse_color: Color::new(0.3, 0.3, 0.3),
+        albedo: Albedo::new(0.9, 0.1, 0.0, 0.0),
+        specular_exponent: 10.,
+        ..Material::default()
+    };
+
+    // NOTE: a dim lamp right next to the left sphere, and a bright light far above the right one,
+    // both fade with the square of the distance, so each mostly lights its own sphere
+    let lamp = Light::new(Position::new(-3.5, 0.5, -6.), 4.).with_falloff(1.);
+    let far = Light::new(Position::new(3., 20., -8.), 1600.).with_falloff(1.);
+
+    Scene::default()
+        .add_background(DummyBackground)
+        .add_object(Sphere::new(
+            Position::new(-2.5, 0., -8.),
+            1.5,
+            ivory.clone(),
+        ))
+        .add_object(Sphere::new(Position::new(2.5, 0., -8.), 1.5, ivory))
+        .add_object(
+            AABBox::try_build(
+                Position::new(-50., -3., -50.),
+                Position::new(50., -1.5, 50.),
+                floor,
+            )
+            .unwrap(),
+        )
+        .add_light(lamp)
+        .add_light(far)
+        .add_light(Light::ambient(0.05))
+}
+
+fn main() {
+    let mut img = RgbImage::new(800, 450);
+    let scene = example_scene();
+    let camera = CameraBuilder::default().adjust_fov_in_degree(60.).build();
+
+    camera.render(&scene, &mut img);
+
+    let file_path = file!();
+    let file_stem = Path::new(file_path).file_stem().unwrap().to_str().unwrap();
+
+    let ext = output::format_from_args();
+    output::save_image(&img, format!("output/example_{file_stem}.{ext}")).unwrap();
+}
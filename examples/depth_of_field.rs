@@ -0,0 +1,50 @@
+use image::RgbImage;
+
+use std::path::Path;
+
+use tiny_computer_graphics::raytracer::{output, prelude::*};
+
+fn example_scene() -> Scene<DummyBackground> {
+    let red_rubber = Material {
+        diffuse_color: Color::new(0.3, 0.1, 0.1),
+        albedo: Albedo::new(0.9, 0.1, 0.0, 0.0),
+        specular_exponent: 10.,
+        refractive_index: 1.,
+        ..Material::default()
+    };
+
+    let l1 = Light::new(Position::new(-20., 20., 20.), 1.5);
+    let l2 = Light::new(Position::new(30., 50., -25.), 1.8);
+
+    // NOTE: three spheres at different depths, only the middle one is on the focal plane
+    let near = Sphere::new(Position::new(-2.5, 0., -4.), 1., red_rubber.clone());
+    let middle = Sphere::new(Position::new(0., 0., -8.), 1., Material::IVORY);
+    let far = Sphere::new(Position::new(4., 0., -14.), 1., red_rubber.clone());
+
+    Scene::default()
+        .add_background(DummyBackground)
+        .add_object(near)
+        .add_object(middle)
+        .add_object(far)
+        .add_light(l1)
+        .add_light(l2)
+}
+
+fn main() {
+    let mut img = RgbImage::new(800, 450);
+    let scene = example_scene();
+    let camera = CameraBuilder::default()
+        .antialiasing(true)
+        .samples_per_pixel(64)
+        .aperture(0.4)
+        .focus_distance(8.)
+        .build();
+
+    camera.render(&scene, &mut img);
+
+    let file_path = file!();
+    let file_stem = Path::new(file_path).file_stem().unwrap().to_str().unwrap();
+
+    let ext = output::format_from_args();
+    output::save_image(&img, format!("output/example_{file_stem}.{ext}")).unwrap();
+}
@@ -0,0 +1,40 @@
+use image::RgbImage;
+
+use std::path::Path;
+
+use tiny_computer_graphics::raytracer::{output, prelude::*, world::scene::MonteCarlo};
+
+fn example_scene() -> Scene<Sky, MonteCarlo> {
+    let floor = Material {
+        diffuse_color: Color::new(0.5, 0.5, 0.5),
+        albedo: Albedo::new(1.0, 0.0, 0.0, 0.0),
+        specular_exponent: 10.,
+        refractive_index: 1.,
+        ..Material::default()
+    };
+
+    let lamp = Material::default().with_emission(Color::new(4., 3.6, 3.));
+
+    let lamp = Sphere::new(Position::new(0., 1.5, -5.), 0.8, lamp);
+    let floor = Sphere::new(Position::new(0., -1000., 0.), 1000., floor);
+
+    // NOTE: no background, the lamp is the only light source
+    Scene::default().add_object(lamp).add_object(floor)
+}
+
+fn main() {
+    let mut img = RgbImage::new(800, 450);
+    let scene = example_scene();
+    let camera = CameraBuilder::default()
+        .antialiasing(true)
+        .position(Position::new(0., 0.8, 0.))
+        .build();
+
+    camera.render(&scene, &mut img);
+
+    let file_path = file!();
+    let file_stem = Path::new(file_path).file_stem().unwrap().to_str().unwrap();
+
+    let ext = output::format_from_args();
+    output::save_image(&img, format!("output/example_{file_stem}.{ext}")).unwrap();
+}
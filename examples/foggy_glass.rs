@@ -0,0 +1,53 @@
+use image::RgbImage;
+
+use std::path::Path;
+
+use tiny_computer_graphics::raytracer::{output, prelude::*, world::scene::MonteCarlo};
+
+fn example_scene() -> Scene<Sky, MonteCarlo> {
+    let floor = Material {
+        diffuse_color: Color::new(0.5, 0.5, 0.5),
+        ..Material::default()
+    };
+
+    let glass = Material {
+        diffuse_color: Color::WHITE,
+        albedo: Albedo::new(0.0, 0.0, 0.0, 1.0),
+        specular_exponent: 125.,
+        refractive_index: 1.5,
+        ..Material::default()
+    };
+
+    // NOTE: thin mist around the glass sphere, the floor fades away in it
+    let fog = AABBox::try_build(
+        Position::new(-4., 0., -9.),
+        Position::new(4., 2.5, -2.),
+        Material::default(),
+    )
+    .unwrap();
+    let fog = ConstantMedium::new(fog, 0.2, Color::new(0.9, 0.9, 0.9));
+
+    Scene::default()
+        .add_background(Sky)
+        .add_object(Sphere::new(Position::new(0., 1., -5.), 1., glass))
+        .add_object(fog)
+        .add_object(Sphere::new(Position::new(0., -1000., 0.), 1000., floor))
+}
+
+fn main() {
+    let mut img = RgbImage::new(800, 450);
+    let scene = example_scene();
+    let camera = CameraBuilder::default()
+        .antialiasing(true)
+        .samples_per_pixel(50)
+        .position(Position::new(0., 1.2, 0.))
+        .build();
+
+    camera.render(&scene, &mut img);
+
+    let file_path = file!();
+    let file_stem = Path::new(file_path).file_stem().unwrap().to_str().unwrap();
+
+    let ext = output::format_from_args();
+    output::save_image(&img, format!("output/example_{file_stem}.{ext}")).unwrap();
+}
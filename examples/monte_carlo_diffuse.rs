@@ -2,7 +2,7 @@ use image::RgbImage;
 
 use std::path::Path;
 
-use tiny_computer_graphics::raytracer::{prelude::*, world::scene::MonteCarlo};
+use tiny_computer_graphics::raytracer::{output, prelude::*, world::scene::MonteCarlo};
 
 fn example_scene() -> Scene<Sky, MonteCarlo> {
     let floor = Material {
@@ -10,6 +10,7 @@ fn example_scene() -> Scene<Sky, MonteCarlo> {
         albedo: Albedo::new(1.0, 0.0, 0.0, 0.0),
         specular_exponent: 10.,
         refractive_index: 1.,
+        ..Material::default()
     };
 
     let red_rubber = Material {
@@ -17,6 +18,7 @@ fn example_scene() -> Scene<Sky, MonteCarlo> {
         albedo: Albedo::new(0.9, 0.1, 0.0, 0.0),
         specular_exponent: 10.,
         refractive_index: 1.,
+        ..Material::default()
     };
 
     let sp1 = Sphere::new(Position::new(0., 2., -5.), 2., red_rubber.clone());
@@ -41,5 +43,6 @@ fn main() {
     let file_path = file!();
     let file_stem = Path::new(file_path).file_stem().unwrap().to_str().unwrap();
 
-    img.save(format!("output/example_{file_stem}.png")).unwrap();
+    let ext = output::format_from_args();
+    output::save_image(&img, format!("output/example_{file_stem}.{ext}")).unwrap();
 }
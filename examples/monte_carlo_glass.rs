@@ -0,0 +1,70 @@
+use image::RgbImage;
+
+use std::path::Path;
+
+use tiny_computer_graphics::raytracer::{output, prelude::*, world::scene::MonteCarlo};
+
+fn example_scene() -> Scene<Sky, MonteCarlo> {
+    let floor = Material {
+        diffuse_color: Color::new(0.8, 0.8, 0.0),
+        albedo: Albedo::new(1.0, 0.0, 0.0, 0.0),
+        specular_exponent: 10.,
+        refractive_index: 1.,
+        ..Material::default()
+    };
+
+    let diffuse = Material {
+        diffuse_color: Color::new(0.1, 0.2, 0.5),
+        albedo: Albedo::new(1.0, 0.0, 0.0, 0.0),
+        specular_exponent: 10.,
+        refractive_index: 1.,
+        ..Material::default()
+    };
+
+    let glass = Material {
+        diffuse_color: Color::WHITE,
+        albedo: Albedo::new(0.0, 0.0, 0.0, 1.0),
+        specular_exponent: 125.,
+        refractive_index: 1.5,
+        ..Material::default()
+    };
+
+    // NOTE: an air bubble inside the glass makes the sphere hollow,
+    // seen from the glass, the bubble is a 1 / 1.5 interface
+    let bubble = Material {
+        refractive_index: 1. / 1.5,
+        ..glass.clone()
+    };
+
+    let center = Sphere::new(Position::new(0., 1., -5.), 1., diffuse);
+    let outer = Sphere::new(Position::new(-2.2, 1., -5.), 1., glass.clone());
+    let inner = Sphere::new(Position::new(-2.2, 1., -5.), 0.8, bubble);
+    let solid = Sphere::new(Position::new(2.2, 1., -5.), 1., glass);
+    let floor = Sphere::new(Position::new(0., -1000., 0.), 1000., floor);
+
+    Scene::default()
+        .add_background(Sky)
+        .add_object(center)
+        .add_object(outer)
+        .add_object(inner)
+        .add_object(solid)
+        .add_object(floor)
+}
+
+fn main() {
+    let mut img = RgbImage::new(800, 450);
+    let scene = example_scene();
+    let camera = CameraBuilder::default()
+        .antialiasing(true)
+        .samples_per_pixel(50)
+        .position(Position::new(0., 1.2, 0.))
+        .build();
+
+    camera.render(&scene, &mut img);
+
+    let file_path = file!();
+    let file_stem = Path::new(file_path).file_stem().unwrap().to_str().unwrap();
+
+    let ext = output::format_from_args();
+    output::save_image(&img, format!("output/example_{file_stem}.{ext}")).unwrap();
+}
@@ -0,0 +1,68 @@
+use image::RgbImage;
+
+use std::path::Path;
+
+use tiny_computer_graphics::raytracer::{output, prelude::*, world::scene::MonteCarlo};
+
+fn example_scene() -> Scene<Sky, MonteCarlo> {
+    let floor = Material {
+        diffuse_color: Color::new(0.8, 0.8, 0.0),
+        albedo: Albedo::new(1.0, 0.0, 0.0, 0.0),
+        specular_exponent: 10.,
+        refractive_index: 1.,
+        ..Material::default()
+    };
+
+    let diffuse = Material {
+        diffuse_color: Color::new(0.1, 0.2, 0.5),
+        albedo: Albedo::new(1.0, 0.0, 0.0, 0.0),
+        specular_exponent: 10.,
+        refractive_index: 1.,
+        ..Material::default()
+    };
+
+    // NOTE: reflections aren't tinted, the gold color comes from its diffusive share
+    let mirror = Material {
+        diffuse_color: Color::new(0.8, 0.8, 0.8),
+        albedo: Albedo::new(0.0, 0.0, 0.8, 0.0),
+        specular_exponent: 1425.,
+        refractive_index: 1.,
+        ..Material::default()
+    };
+    let brushed_gold = Material {
+        diffuse_color: Color::new(0.8, 0.6, 0.2),
+        albedo: Albedo::new(0.4, 0.0, 0.6, 0.0),
+        ..mirror.clone()
+    }
+    .with_fuzz(0.3);
+
+    let center = Sphere::new(Position::new(0., 1., -5.), 1., diffuse);
+    let left = Sphere::new(Position::new(-2.2, 1., -5.), 1., mirror);
+    let right = Sphere::new(Position::new(2.2, 1., -5.), 1., brushed_gold);
+    let floor = Sphere::new(Position::new(0., -1000., 0.), 1000., floor);
+
+    Scene::default()
+        .add_background(Sky)
+        .add_object(center)
+        .add_object(left)
+        .add_object(right)
+        .add_object(floor)
+}
+
+fn main() {
+    let mut img = RgbImage::new(800, 450);
+    let scene = example_scene();
+    let camera = CameraBuilder::default()
+        .antialiasing(true)
+        .samples_per_pixel(50)
+        .position(Position::new(0., 1.2, 0.))
+        .build();
+
+    camera.render(&scene, &mut img);
+
+    let file_path = file!();
+    let file_stem = Path::new(file_path).file_stem().unwrap().to_str().unwrap();
+
+    let ext = output::format_from_args();
+    output::save_image(&img, format!("output/example_{file_stem}.{ext}")).unwrap();
+}
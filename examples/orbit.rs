@@ -0,0 +1,53 @@
+use std::path::Path;
+
+use tiny_computer_graphics::raytracer::{animation, prelude::*};
+
+fn example_scene() -> Scene<DummyBackground> {
+    let red_rubber = Material {
+        diffuse_color: Color::new(0.3, 0.1, 0.1),
+        albedo: Albedo::new(0.9, 0.1, 0.0, 0.0),
+        specular_exponent: 10.,
+        refractive_index: 1.,
+        ..Material::default()
+    };
+    let mirror = Material {
+        diffuse_color: Color::new(1.0, 1.0, 1.0),
+        albedo: Albedo::new(0.0, 10.0, 0.8, 0.0),
+        specular_exponent: 1425.,
+        refractive_index: 1.,
+        ..Material::default()
+    };
+
+    let l1 = Light::new(Position::new(-20., 20., 20.), 1.5);
+    let l2 = Light::new(Position::new(30., 50., -25.), 1.8);
+    let l3 = Light::new(Position::new(30., 20., 30.), 1.7);
+
+    let sp1 = Sphere::new(Position::new(0., 0., 0.), 2., Material::IVORY);
+    let sp2 = Sphere::new(Position::new(4., 0., 0.), 1., red_rubber);
+    let sp3 = Sphere::new(Position::new(-2., 1., -3.), 1.5, mirror);
+
+    Scene::default()
+        .add_background(DummyBackground)
+        .add_object(sp1)
+        .add_object(sp2)
+        .add_object(sp3)
+        .add_light(l1)
+        .add_light(l2)
+        .add_light(l3)
+}
+
+fn main() {
+    let scene = example_scene();
+    // NOTE: one progress bar per frame would flood the terminal
+    let camera = CameraBuilder::default()
+        .antialiasing(true)
+        .progress(Progress::Quiet)
+        .build();
+    let frames = camera.render_orbit(&scene, (320, 180), Position::new(0., 0., 0.), 12., 24);
+
+    let file_path = file!();
+    let file_stem = Path::new(file_path).file_stem().unwrap().to_str().unwrap();
+
+    animation::save_frames(&frames, format!("output/example_{file_stem}"), "frame").unwrap();
+    animation::save_gif(&frames, format!("output/example_{file_stem}.gif"), 80).unwrap();
+}
@@ -0,0 +1,28 @@
+use image::RgbImage;
+use tiny_computer_graphics::raytracer::{
+    output,
+    prelude::*,
+    scenes,
+    world::{background::Background, Lambertian},
+};
+
+fn main() {
+    // NOTE: 2:1, 360 degrees across and 180 degrees down
+    let mut img = RgbImage::new(1600, 800);
+    let (scene, _) = scenes::first(Lambertian);
+    // NOTE: something to see behind the camera too
+    let scene = scene
+        .add_background(Box::new(Sky) as Box<dyn Background>)
+        .add_object(Sphere::new(
+            Position::new(2., 0., 10.),
+            3.,
+            Material::RED_RUBBER,
+        ));
+    let camera = CameraBuilder::new()
+        .projection(Projection::Equirectangular)
+        .build();
+
+    camera.render(&scene, &mut img);
+    let ext = output::format_from_args();
+    output::save_image(&img, format!("output/example_panorama.{ext}")).unwrap();
+}
@@ -0,0 +1,32 @@
+use image::RgbImage;
+
+use std::path::Path;
+
+use tiny_computer_graphics::raytracer::{output, prelude::*};
+
+fn main() {
+    let scene_path = std::env::args()
+        .nth(1)
+        .filter(|arg| !arg.starts_with("--"))
+        .unwrap_or_else(|| "scenes/first.toml".to_string());
+
+    let (scene, camera) = Scene::from_file(&scene_path).unwrap();
+    let mut img = RgbImage::new(1600, 900);
+
+    camera.render(&scene, &mut img);
+
+    let file_path = file!();
+    let file_stem = Path::new(file_path).file_stem().unwrap().to_str().unwrap();
+    let scene_stem = Path::new(&scene_path)
+        .file_stem()
+        .unwrap()
+        .to_str()
+        .unwrap();
+
+    let ext = output::format_from_args();
+    output::save_image(
+        &img,
+        format!("output/example_{file_stem}_{scene_stem}.{ext}"),
+    )
+    .unwrap();
+}
@@ -3,7 +3,7 @@ use image::RgbImage;
 use std::env;
 use std::path::Path;
 
-use tiny_computer_graphics::raytracer::prelude::*;
+use tiny_computer_graphics::raytracer::{output, prelude::*};
 
 fn example_scene() -> Scene<DummyBackground> {
     let red_rubber = Material {
@@ -11,6 +11,7 @@ fn example_scene() -> Scene<DummyBackground> {
         albedo: Albedo::new(0.9, 0.1, 0.0, 0.0),
         specular_exponent: 10.,
         refractive_index: 1.,
+        ..Material::default()
     };
 
     let l1 = Light::new(Position::new(-20., 20., 20.), 1.5);
@@ -46,6 +47,6 @@ fn main() {
     let file_stem = Path::new(file_path).file_stem().unwrap().to_str().unwrap();
     let suffix = if antialias { "_antialias" } else { "" };
 
-    img.save(format!("output/example_{file_stem}{suffix}.png"))
-        .unwrap();
+    let ext = output::format_from_args();
+    output::save_image(&img, format!("output/example_{file_stem}{suffix}.{ext}")).unwrap();
 }
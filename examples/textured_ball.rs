@@ -0,0 +1,42 @@
+use image::RgbImage;
+
+use std::path::Path;
+
+use tiny_computer_graphics::raytracer::{output, prelude::*};
+
+fn example_scene(texture_path: &str) -> Scene<Sky> {
+    let l1 = Light::new(Position::new(-20., 20., 20.), 1.5);
+    let l2 = Light::new(Position::new(30., 50., -25.), 1.2);
+
+    // NOTE: any equirectangular image works, e.g. an earth map
+    let ball = TexturedSphere::try_load(
+        Position::new(0., 0., -6.),
+        2.5,
+        Material::default(),
+        texture_path,
+    )
+    .unwrap();
+
+    Scene::default()
+        .add_object(ball)
+        .add_light(l1)
+        .add_light(l2)
+}
+
+fn main() {
+    let texture_path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "obj/african_head_diffuse.tga".to_string());
+
+    let mut img = RgbImage::new(800, 450);
+    let scene = example_scene(&texture_path);
+    let camera = CameraBuilder::default().antialiasing(true).build();
+
+    camera.render(&scene, &mut img);
+
+    let file_path = file!();
+    let file_stem = Path::new(file_path).file_stem().unwrap().to_str().unwrap();
+
+    let ext = output::format_from_args();
+    output::save_image(&img, format!("output/example_{file_stem}.{ext}")).unwrap();
+}
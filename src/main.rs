@@ -1,14 +1,20 @@
-use image::{imageops, Rgb, RgbImage};
+use std::env;
+use std::process::ExitCode;
 
-fn main() {
-    let mut img = RgbImage::new(128, 128);
+use tiny_computer_graphics::raytracer::cli::{self, Options, USAGE};
 
-    for x in 0..=10 {
-        for y in 0..=10 {
-            img.put_pixel(x, y, Rgb([255, 255, 255]));
-        }
+fn main() -> ExitCode {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
     }
 
-    imageops::flip_vertical_in_place(&mut img);
-    img.save("output/empty.tga").unwrap();
+    match Options::parse(args).and_then(|options| cli::run(&options)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err:#}\n\n{USAGE}");
+            ExitCode::FAILURE
+        }
+    }
 }
@@ -0,0 +1,133 @@
+use anyhow::Result;
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    Delay, DynamicImage, Frame, RgbImage,
+};
+
+use std::borrow::Borrow;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use super::{
+    camera::Camera,
+    world::{background::Background, scene::RayCastStrategy, Scene},
+};
+
+/// Save every frame as `{stem}_000.png`, `{stem}_001.png`, ... into `dir`, which is created if
+/// missing, return the paths of the frames
+///
+/// Frames can come from an iterator, to save each one as soon as it is rendered
+pub fn save_frames<I, F, P>(frames: I, dir: P, stem: &str) -> Result<Vec<PathBuf>>
+where
+    I: IntoIterator<Item = F>,
+    F: Borrow<RgbImage>,
+    P: AsRef<Path>,
+{
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir)?;
+
+    frames
+        .into_iter()
+        .enumerate()
+        .map(|(i, frame)| {
+            let path = dir.join(format!("{stem}_{i:03}.png"));
+            frame.borrow().save(&path)?;
+            Ok(path)
+        })
+        .collect()
+}
+
+/// Encode the frames into a looping GIF, each frame is shown for `frame_delay_ms` milliseconds
+pub fn save_gif<P: AsRef<Path>>(frames: &[RgbImage], path: P, frame_delay_ms: u32) -> Result<()> {
+    let writer = BufWriter::new(File::create(path)?);
+    let mut encoder = GifEncoder::new(writer);
+    encoder.set_repeat(Repeat::Infinite)?;
+
+    let delay = Delay::from_numer_denom_ms(frame_delay_ms, 1);
+    encoder.encode_frames(frames.iter().map(|frame| {
+        let rgba = DynamicImage::ImageRgb8(frame.clone()).to_rgba8();
+        Frame::from_parts(rgba, 0, 0, delay)
+    }))?;
+
+    Ok(())
+}
+
+/// Render `frames` frames of `size` into `dir` with `save_frames`, as `frame_000.png`, ...
+///
+/// The scene of each frame is built by `scene_fn` from the frame index, so objects can move
+pub fn render_sequence<B, S, F, P>(
+    camera: &Camera,
+    scene_fn: F,
+    frames: usize,
+    (width, height): (u32, u32),
+    dir: P,
+) -> Result<()>
+where
+    B: Background,
+    S: RayCastStrategy,
+    F: Fn(usize) -> Scene<B, S>,
+    P: AsRef<Path>,
+{
+    // NOTE: each frame is saved before the next one is rendered
+    let images = (0..frames).map(|i| {
+        let mut img = RgbImage::new(width, height);
+        camera.render(&scene_fn(i), &mut img);
+        img
+    });
+    save_frames(images, dir, "frame")?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raytracer::{
+        world::{
+            background::DummyBackground,
+            objects::{Material, Sphere},
+        },
+        Position,
+    };
+    use image::{codecs::gif::GifDecoder, AnimationDecoder, Rgb};
+    use std::io::BufReader;
+
+    #[test]
+    fn test_save_gif() {
+        let frames = [Rgb([255, 0, 0]), Rgb([0, 255, 0]), Rgb([0, 0, 255])]
+            .map(|color| RgbImage::from_pixel(8, 8, color));
+        let path = "output/test_save_gif.gif";
+        save_gif(&frames, path, 100).unwrap();
+
+        let decoder = GifDecoder::new(BufReader::new(File::open(path).unwrap())).unwrap();
+        let decoded = decoder.into_frames().collect_frames().unwrap();
+
+        assert_eq!(decoded.len(), 3);
+        assert_eq!(decoded[1].buffer().get_pixel(4, 4).0, [0, 255, 0, 255]);
+    }
+
+    #[test]
+    fn test_render_sequence() {
+        let dir = Path::new("output/test_render_sequence");
+        // NOTE: the sphere moves from left to right
+        let scene_fn = |i: usize| {
+            Scene::with_background(DummyBackground).add_object(Sphere::new(
+                Position::new(i as f64 - 1., 0., -5.),
+                1.,
+                Material::default(),
+            ))
+        };
+        render_sequence(&Camera::default(), scene_fn, 3, (16, 9), dir).unwrap();
+
+        let frames: Vec<RgbImage> = (0..3)
+            .map(|i| {
+                image::open(dir.join(format!("frame_{i:03}.png")))
+                    .unwrap()
+                    .to_rgb8()
+            })
+            .collect();
+        assert_eq!(frames[0].dimensions(), (16, 9));
+        assert_ne!(frames[0], frames[2]);
+    }
+}
@@ -4,7 +4,7 @@ use indicatif::{ProgressState, ProgressStyle};
 use nalgebra::{Matrix3x4, Vector3, Vector4};
 
 use std::fmt::Write;
-use std::ops::{Add, Div, Mul, Range};
+use std::ops::{Add, AddAssign, Div, Mul, Sub};
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Position(Vector3<f64>);
@@ -85,6 +85,23 @@ impl Direction {
         self.0.dot(&other.0) > 0.
     }
 
+    /// Build two directions `u` and `v`, so `(u, v, self)` is a right-handed orthonormal basis
+    pub fn orthonormal_basis(&self) -> (Self, Self) {
+        // NOTE: same u/v/w trick as rotating_box: t is any vector not collinear with w,
+        // replace the smallest component of w by 1 so t can never be parallel to w
+        let w = self.0;
+        let mut t = w;
+        let smallest = (0..3)
+            .min_by(|&i, &j| w[i].abs().total_cmp(&w[j].abs()))
+            .unwrap_or(0);
+        t[smallest] = 1.;
+
+        let u = t.cross(&w).normalize();
+        let v = w.cross(&u);
+
+        (Self::from(u), Self::from(v))
+    }
+
     #[allow(non_snake_case)]
     pub fn reflection(&self, N: &Self) -> Self {
         let I = self;
@@ -93,19 +110,27 @@ impl Direction {
         Self::from(I.0 - 2. * I_proj)
     }
 
+    /// Refract through a surface with normal `N` from medium `n1` into medium `n2`
+    ///
+    /// Return `None` on total internal reflection, when `sin(theta2)` would exceed 1
     #[allow(non_snake_case)]
-    pub fn refraction(&self, N: &Self, n1: f64, n2: f64) -> Self {
+    pub fn refraction(&self, N: &Self, n1: f64, n2: f64) -> Option<Self> {
         let N = *N;
         let I = self;
 
         let cos_theta1 = -I.0.dot(&N.0).clamp(-1., 1.);
         let sin_theta1 = (1. - cos_theta1.powi(2)).sqrt().clamp(-1., 1.);
-        let sin_theta2 = (n1 / n2 * sin_theta1).clamp(-1., 1.);
+        let sin_theta2 = n1 / n2 * sin_theta1;
+        if sin_theta2 > 1. {
+            return None;
+        }
         let cos_theta2 = (1. - sin_theta2.powi(2)).sqrt().clamp(-1., 1.);
 
         // NOTE: snell's law: vector form
         // L' = (n1/n2) * L + ((n1/n2)cos(theta1) - cos(theta2)) * N
-        Self::from((n1 / n2) * I.0 + ((n1 / n2) * cos_theta1 - cos_theta2) * N.0)
+        Some(Self::from(
+            (n1 / n2) * I.0 + ((n1 / n2) * cos_theta1 - cos_theta2) * N.0,
+        ))
     }
 }
 
@@ -121,7 +146,7 @@ impl From<Vector3<f64>> for Direction {
     }
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct Albedo(Vector4<f64>);
 
 impl Albedo {
@@ -146,9 +171,21 @@ impl Albedo {
     }
 }
 
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct Color(Vector3<f64>);
 
+impl AbsDiffEq for Color {
+    type Epsilon = f64;
+
+    fn default_epsilon() -> Self::Epsilon {
+        EPSILON
+    }
+
+    fn abs_diff_eq(&self, other: &Self, epsilon: Self::Epsilon) -> bool {
+        relative_eq!(self.0, other.0, epsilon = epsilon)
+    }
+}
+
 impl From<Vector3<f64>> for Color {
     fn from(v: Vector3<f64>) -> Self {
         Self(v)
@@ -200,6 +237,16 @@ impl Color {
         Self::from(self.0 * intensity)
     }
 
+    /// Perceived brightness, weighted by the Rec. 709 coefficients
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.0.x + 0.7152 * self.0.y + 0.0722 * self.0.z
+    }
+
+    /// `a` at `t = 0`, `b` at `t = 1`, `t` isn't clamped
+    pub fn lerp(a: Color, b: Color, t: f64) -> Self {
+        a * (1. - t) + b * t
+    }
+
     pub fn apply_albedo(
         diffusive: Color,
         specular: Color,
@@ -221,6 +268,20 @@ impl Add for Color {
     }
 }
 
+impl AddAssign for Color {
+    fn add_assign(&mut self, rhs: Self) {
+        self.0 += rhs.0;
+    }
+}
+
+impl Sub for Color {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::from(self.0 - rhs.0)
+    }
+}
+
 impl Mul<f64> for Color {
     type Output = Self;
 
@@ -229,6 +290,14 @@ impl Mul<f64> for Color {
     }
 }
 
+impl Mul for Color {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self::from(self.0.component_mul(&rhs.0))
+    }
+}
+
 impl Mul<Color> for f64 {
     type Output = Color;
 
@@ -245,47 +314,259 @@ impl Div<f64> for Color {
     }
 }
 
-pub struct Interval(pub Range<f64>);
+/// Schlick's approximation of the fresnel reflectance from medium `n1` into medium `n2`
+///
+/// `cos_theta` is the cosine of the incident angle, return 1 on total internal reflection
+pub fn fresnel(cos_theta: f64, n1: f64, n2: f64) -> f64 {
+    let mut cos_theta = cos_theta.clamp(0., 1.);
+
+    // NOTE: from the denser medium, use the transmitted angle, which is the larger one
+    if n1 > n2 {
+        let sin_theta2_sq = (n1 / n2).powi(2) * (1. - cos_theta.powi(2));
+        if sin_theta2_sq > 1. {
+            return 1.;
+        }
+        cos_theta = (1. - sin_theta2_sq).sqrt();
+    }
+
+    let r0 = ((n1 - n2) / (n1 + n2)).powi(2);
+    r0 + (1. - r0) * (1. - cos_theta).powi(5)
+}
+
+/// Distances along a ray from `min`, included, to `max`, excluded
+///
+/// `max <= min` is an empty interval
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Interval {
+    pub min: f64,
+    pub max: f64,
+}
 
 impl Interval {
-    pub const FULL: Self = Self(f64::NEG_INFINITY..f64::INFINITY);
-    pub const POSITIVE: Self = Self(0.0..f64::INFINITY);
+    pub const FULL: Self = Self::new(f64::NEG_INFINITY, f64::INFINITY);
+    pub const POSITIVE: Self = Self::new(0.0, f64::INFINITY);
+    pub const EMPTY: Self = Self::new(f64::INFINITY, f64::NEG_INFINITY);
 
-    pub fn new(start: f64, end: f64) -> Self {
-        Self(start..end)
+    pub const fn new(min: f64, max: f64) -> Self {
+        Self { min, max }
     }
 
-    pub fn start(&self) -> f64 {
-        self.0.start
+    pub fn is_empty(&self) -> bool {
+        self.max <= self.min
     }
 
-    pub fn end(&self) -> f64 {
-        self.0.end
+    /// Length of the interval, 0 when it is empty
+    pub fn size(&self) -> f64 {
+        (self.max - self.min).max(0.)
     }
 
     pub fn contains(&self, x: f64) -> bool {
-        self.0.contains(&x)
+        self.min <= x && x < self.max
+    }
+
+    /// Strictly inside, neither bound is
+    pub fn surrounds(&self, x: f64) -> bool {
+        self.min < x && x < self.max
+    }
+
+    /// The closest value to `x` in the interval, `min` wins when it is empty
+    pub fn clamp(&self, x: f64) -> f64 {
+        x.min(self.max).max(self.min)
+    }
+
+    /// Grow by `delta`, half of it on each side
+    pub fn expand(&self, delta: f64) -> Self {
+        Self::new(self.min - delta / 2., self.max + delta / 2.)
+    }
+
+    /// Where both intervals overlap, `None` when they don't
+    pub fn intersect(&self, other: &Interval) -> Option<Interval> {
+        let overlap = Self::new(self.min.max(other.min), self.max.min(other.max));
+
+        (!overlap.is_empty()).then_some(overlap)
     }
 
-    pub fn surronds(&self, x: f64) -> bool {
-        !self.0.contains(&x)
+    /// The smallest interval holding both, empty ones are left out
+    pub fn union(&self, other: &Interval) -> Interval {
+        match (self.is_empty(), other.is_empty()) {
+            (true, _) => *other,
+            (_, true) => *self,
+            _ => Self::new(self.min.min(other.min), self.max.max(other.max)),
+        }
     }
 }
 
 pub fn progress_bar_style() -> ProgressStyle {
+    progress_bar_style_of("pixels")
+}
+
+/// Same bar as `progress_bar_style`, counting `unit` instead of pixels, e.g. frames
+pub fn progress_bar_style_of(unit: &'static str) -> ProgressStyle {
     let text = [
-        "[{bar:48.cyan/blue}] {percent}% {spinner:.green}",
-        "Elapsed Time     : {elapsed_precise}",
-        "ETA              : {eta}",
-        "Tracing Progress : {pos}/{len} pixels",
-        "Tracing Speed    : {per_sec}",
+        "[{bar:48.cyan/blue}] {percent}% {spinner:.green}".to_string(),
+        "Elapsed Time     : {elapsed_precise}".to_string(),
+        "ETA              : {eta}".to_string(),
+        format!("Tracing Progress : {{pos}}/{{len}} {unit}"),
+        "Tracing Speed    : {per_sec}".to_string(),
     ]
     .join("\n");
 
     ProgressStyle::with_template(&text)
         .unwrap()
-        .with_key("per_sec", |state: &ProgressState, w: &mut dyn Write| {
-            _ = write!(w, "{:.0} pixels/sec", state.per_sec());
-        })
+        .with_key(
+            "per_sec",
+            move |state: &ProgressState, w: &mut dyn Write| {
+                _ = write!(w, "{:.0} {unit}/sec", state.per_sec());
+            },
+        )
         .progress_chars("#>-")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_fresnel() {
+        // NOTE: head-on, the base reflectance ((n1 - n2) / (n1 + n2))^2
+        assert_abs_diff_eq!(fresnel(1., 1., 1.5), 0.04, epsilon = EPSILON);
+        assert_abs_diff_eq!(fresnel(1., 1.5, 1.), 0.04, epsilon = EPSILON);
+
+        // NOTE: towards grazing incidence, everything is reflected
+        assert_abs_diff_eq!(fresnel(0., 1., 1.5), 1., epsilon = EPSILON);
+        assert!(fresnel(0.01, 1., 1.5) > 0.9);
+        assert!(fresnel(0.5, 1., 1.5) < fresnel(0.1, 1., 1.5));
+
+        // NOTE: beyond the critical angle, total internal reflection
+        assert_eq!(fresnel(0.5, 1.5, 1.), 1.);
+        assert!(fresnel(0.8, 1.5, 1.) < 1.);
+    }
+
+    #[test]
+    fn test_total_internal_reflection_threshold() {
+        let n = Direction::new(0., 1., 0.);
+        // NOTE: from glass (1.5) into air, critical angle is asin(1/1.5) ~ 41.81 degrees
+        let critical = (1. / 1.5f64).asin();
+        let incident = |theta: f64| Direction::new(theta.sin(), -theta.cos(), 0.);
+
+        for theta in [0., 0.3, critical - 1e-3] {
+            assert!(incident(theta).refraction(&n, 1.5, 1.).is_some(), "{theta}");
+        }
+        for theta in [critical + 1e-3, 1., 1.5] {
+            assert!(incident(theta).refraction(&n, 1.5, 1.).is_none(), "{theta}");
+        }
+
+        // NOTE: from air into glass there is never total internal reflection
+        let refracted = incident(1.5).refraction(&n, 1., 1.5).unwrap();
+        assert_abs_diff_eq!(refracted.as_ref().x, 1.5f64.sin() / 1.5, epsilon = EPSILON);
+
+        // NOTE: head-on rays go straight through
+        assert_abs_diff_eq!(incident(0.).refraction(&n, 1.5, 1.).unwrap(), n.reverse());
+    }
+
+    #[test]
+    fn test_interval_contains_and_surrounds() {
+        let interval = Interval::new(1., 3.);
+        assert!(interval.contains(1.));
+        assert!(!interval.surrounds(1.));
+        assert!(interval.contains(2.) && interval.surrounds(2.));
+        assert!(!interval.contains(3.) && !interval.surrounds(3.));
+        assert!(!interval.contains(0.) && !interval.surrounds(4.));
+
+        assert!(Interval::FULL.surrounds(1e300));
+        assert!(!Interval::EMPTY.contains(0.));
+    }
+
+    #[test]
+    fn test_interval_size_and_clamp() {
+        let interval = Interval::new(1., 3.);
+        assert_eq!(interval.size(), 2.);
+        assert_eq!(interval.clamp(0.), 1.);
+        assert_eq!(interval.clamp(2.5), 2.5);
+        assert_eq!(interval.clamp(7.), 3.);
+
+        // NOTE: inverted bounds are empty, clamp doesn't panic on them
+        let inverted = Interval::new(3., 1.);
+        assert!(inverted.is_empty());
+        assert_eq!(inverted.size(), 0.);
+        assert_eq!(inverted.clamp(2.), 3.);
+        assert_eq!(Interval::EMPTY.size(), 0.);
+    }
+
+    #[test]
+    fn test_interval_expand() {
+        assert_eq!(Interval::new(1., 3.).expand(1.), Interval::new(0.5, 3.5));
+        // NOTE: a negative delta shrinks, down to nothing
+        assert!(Interval::new(1., 3.).expand(-4.).is_empty());
+    }
+
+    #[test]
+    fn test_interval_intersect_and_union() {
+        let a = Interval::new(0., 2.);
+        let b = Interval::new(1., 5.);
+        assert_eq!(a.intersect(&b), Some(Interval::new(1., 2.)));
+        assert_eq!(a.union(&b), Interval::new(0., 5.));
+
+        // NOTE: touching at 2 isn't overlapping, the union still covers the gap
+        let c = Interval::new(2., 4.);
+        assert_eq!(a.intersect(&c), None);
+        assert_eq!(a.union(&Interval::new(3., 4.)), Interval::new(0., 4.));
+
+        let inverted = Interval::new(3., 1.);
+        assert_eq!(a.intersect(&inverted), None);
+        assert_eq!(a.union(&inverted), a);
+        assert_eq!(Interval::EMPTY.union(&b), b);
+        assert_eq!(Interval::FULL.intersect(&b), Some(b));
+    }
+
+    #[test]
+    fn test_color_operators() {
+        let a = Color::new(0.2, 0.5, 1.);
+        let b = Color::new(0.5, 0.5, 0.25);
+
+        assert_abs_diff_eq!(a + b, Color::new(0.7, 1., 1.25));
+        assert_abs_diff_eq!(a - b, Color::new(-0.3, 0., 0.75));
+        // NOTE: component-wise, a white surface keeps the color, a black one absorbs it
+        assert_abs_diff_eq!(a * b, Color::new(0.1, 0.25, 0.25));
+        assert_abs_diff_eq!(a * Color::WHITE, a);
+        assert_abs_diff_eq!(a * Color::BLACK, Color::BLACK);
+        assert_abs_diff_eq!(a * 2., Color::new(0.4, 1., 2.));
+        assert_abs_diff_eq!(2. * a, a * 2.);
+        assert_abs_diff_eq!(a / 2., Color::new(0.1, 0.25, 0.5));
+
+        let mut sum = Color::BLACK;
+        sum += a;
+        sum += b;
+        assert_abs_diff_eq!(sum, a + b);
+        assert!(!sum.abs_diff_eq(&a, 1e-3));
+    }
+
+    #[test]
+    fn test_color_luminance() {
+        assert_abs_diff_eq!(Color::WHITE.luminance(), 1., epsilon = 1e-9);
+        assert_eq!(Color::BLACK.luminance(), 0.);
+        // NOTE: green looks much brighter than blue
+        assert!(Color::GREEN.luminance() > Color::RED.luminance());
+        assert!(Color::RED.luminance() > Color::BLUE.luminance());
+    }
+
+    #[test]
+    fn test_color_lerp_endpoints() {
+        let colors = [
+            Color::RED,
+            Color::CYAN,
+            Color::new(0.3, 0.7, 0.1),
+            Color::new(2., 0., 5.),
+            Color::new(-1., 0.25, 1e3),
+        ];
+
+        for a in colors {
+            for b in colors {
+                assert_abs_diff_eq!(Color::lerp(a, b, 0.), a);
+                assert_abs_diff_eq!(Color::lerp(a, b, 1.), b);
+                assert_abs_diff_eq!(Color::lerp(a, b, 0.5), (a + b) / 2.);
+            }
+        }
+    }
+}
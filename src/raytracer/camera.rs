@@ -1,39 +1,201 @@
 use image::{Rgb, RgbImage};
 use indicatif::ParallelProgressIterator;
 use nalgebra::{Matrix3, Vector2, Vector3};
-use rand::Rng;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use rayon::{iter::ParallelIterator, prelude::*};
 
-use crate::raytracer::{progress_bar_style, world::Ray, Direction, Position};
+use std::f64::consts::PI;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::raytracer::{output, progress_bar_style, world::Ray, Direction, Position, EPSILON};
 
 use super::{
     world::{background::Background, RayCastStrategy, Scene},
     Color,
 };
 
-const SAMPLES_PER_PIXEL: usize = 10;
+/// How the samples of one pixel are spread when antialiasing is enabled
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SamplingPattern {
+    /// Every sample is jittered uniformly over the whole pixel, they may clump and leave gaps
+    Uniform,
+    /// The pixel is divided into an `n x n` grid, `n = floor(sqrt(samples))`,
+    /// and each sample is jittered inside one cell
+    #[default]
+    Stratified,
+}
+
+/// Sample a pixel in batches until its color is known well enough
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AdaptiveSampling {
+    /// Samples of one batch, the error is checked after each batch
+    pub min_samples: usize,
+    pub max_samples: usize,
+    /// Stop once the standard error of the pixel luminance is below it
+    pub tolerance: f64,
+}
+
+/// Rectangle of pixels rendered together by `Camera::render_tiled`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Tile {
+    /// Cover a `width x height` image with tiles of `size`, the last row and column may be smaller
+    fn split(width: u32, height: u32, size: u32) -> Vec<Tile> {
+        let size = size.max(1);
+
+        (0..height)
+            .step_by(size as usize)
+            .flat_map(|y| {
+                (0..width).step_by(size as usize).map(move |x| Tile {
+                    x,
+                    y,
+                    width: size.min(width - x),
+                    height: size.min(height - y),
+                })
+            })
+            .collect()
+    }
+
+    /// Coordinates of the pixels in the tile, row by row
+    fn pixels(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        (self.y..self.y + self.height)
+            .flat_map(move |y| (self.x..self.x + self.width).map(move |x| (x, y)))
+    }
+}
+
+/// Operator mapping the linear radiance of a pixel into displayable range
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ToneMap {
+    /// Keep the color as is, the `Rgb` conversion clamps it
+    #[default]
+    Clamp,
+    /// `c / (1 + c)` per channel
+    Reinhard,
+    /// Narkowicz's fit of the ACES filmic curve
+    AcesFilmic,
+}
+
+impl ToneMap {
+    pub fn apply(&self, color: Color) -> Color {
+        let c = color.as_ref();
+
+        match self {
+            ToneMap::Clamp => color,
+            ToneMap::Reinhard => Color::from(c.map(|x| x / (1. + x))),
+            ToneMap::AcesFilmic => {
+                let (a, b, c2, d, e) = (2.51, 0.03, 2.43, 0.59, 0.14);
+                Color::from(c.map(|x| ((x * (a * x + b)) / (x * (c2 * x + d) + e)).clamp(0., 1.)))
+            }
+        }
+    }
+}
+
+/// How the film in front of the camera turns into rays
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+    /// Rays start at the camera and go through the film, `fov` is the vertical one in degrees
+    Perspective { fov: f64, film_distance: f64 },
+    /// Rays start on the film and all go along forward, `width` is the width of the film
+    ///
+    /// Sizes don't shrink with distance, and there is no depth of field
+    Orthographic { width: f64 },
+    /// 360 degrees panorama, x of the image is the longitude in `[-pi, pi]`, y the latitude
+    /// in `[-pi/2, pi/2]`, best with a 2:1 image
+    Equirectangular,
+    /// Equidistant fisheye, the angle to forward grows linearly with the distance to the center
+    ///
+    /// `angle_of_view` in degrees, up to 180, spans the image height
+    Fisheye { angle_of_view: f64 },
+}
+
+impl Default for Projection {
+    fn default() -> Self {
+        Self::Perspective {
+            fov: 90.,
+            film_distance: 1.,
+        }
+    }
+}
+
+impl Projection {
+    /// `fov` and `film_distance`, the default ones when it isn't perspective
+    fn perspective(&self) -> (f64, f64) {
+        match *self {
+            Self::Perspective { fov, film_distance } => (fov, film_distance),
+            _ => Self::default().perspective(),
+        }
+    }
+}
+
+/// How rendering reports its progress
+#[derive(Clone, Default)]
+pub enum Progress {
+    /// Report nothing
+    Quiet,
+    /// Draw an indicatif progress bar in the terminal
+    #[default]
+    Bar,
+    /// Call `f(done, total)` with the number of finished pixels, about every 1% of the image
+    Callback(Arc<dyn Fn(u64, u64) + Send + Sync>),
+}
+
+impl fmt::Debug for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Progress::Quiet => write!(f, "Quiet"),
+            Progress::Bar => write!(f, "Bar"),
+            Progress::Callback(_) => write!(f, "Callback(..)"),
+        }
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct Camera {
-    film_distance: f64,
-    fov: f64,
+    projection: Projection,
     position: Position,
     forward: Direction,
     right: Direction,
     up: Direction,
     enable_antialiasing: bool,
+    samples_per_pixel: usize,
+    sampling_pattern: SamplingPattern,
+    adaptive_sampling: Option<AdaptiveSampling>,
+    tile_size: u32,
+    // NOTE: thin lens, aperture is the lens diameter, zero means a pinhole camera
+    aperture: f64,
+    focus_distance: f64,
+    tone_map: ToneMap,
+    progress: Progress,
+    // NOTE: random numbers of every pixel derive from it, a fresh one is drawn per render if none
+    seed: Option<u64>,
 }
 
 impl Default for Camera {
     fn default() -> Self {
         Self {
-            film_distance: 1.,
-            fov: 90.,
+            projection: Projection::default(),
             position: Position::new(0., 0., 0.),
             forward: Direction::new(0., 0., -1.),
             right: Direction::new(1., 0., 0.),
             up: Direction::new(0., 1., 0.),
             enable_antialiasing: false,
+            samples_per_pixel: 10,
+            sampling_pattern: SamplingPattern::default(),
+            adaptive_sampling: None,
+            tile_size: 32,
+            aperture: 0.,
+            focus_distance: 1.,
+            tone_map: ToneMap::Clamp,
+            progress: Progress::Bar,
+            seed: None,
         }
     }
 }
@@ -41,6 +203,13 @@ impl Default for Camera {
 #[derive(Default)]
 pub struct CameraBuilder(Camera);
 
+/// Tweak a camera which is already built, e.g. the one of a scene file
+impl From<Camera> for CameraBuilder {
+    fn from(camera: Camera) -> Self {
+        Self(camera)
+    }
+}
+
 impl CameraBuilder {
     pub fn new() -> Self {
         Self::default()
@@ -56,29 +225,71 @@ impl CameraBuilder {
         self
     }
 
+    /// World up of the camera, `build` keeps only its part orthogonal to `forward`
     pub fn up_to(&mut self, up: Direction) -> &mut Self {
         self.0.up = up;
         self
     }
 
+    /// Overwritten by `build`, which derives `right` from `forward` and `up`
     pub fn right_to(&mut self, right: Direction) -> &mut Self {
         self.0.right = right;
         self
     }
 
-    pub fn adjust_screen(&mut self, dist: f64) -> &mut Self {
-        self.0.film_distance = dist;
+    /// Aim the camera at `target` from the current position, +Y is used as world up
+    pub fn look_at(&mut self, target: Position) -> &mut Self {
+        let forward = Direction::a_to_b(&self.0.position, &target);
+        self.look_dir(forward, Direction::new(0., 1., 0.))
+    }
+
+    /// Put the camera at `eye` looking at `target`, tilted so `world_up` is up on the image
+    pub fn look_from(&mut self, eye: Position, target: Position, world_up: Direction) -> &mut Self {
+        self.0.position = eye;
+        self.look_dir(Direction::a_to_b(&eye, &target), world_up)
+    }
+
+    /// Aim the camera along `forward`, `right` and `up` are derived from `world_up`
+    pub fn look_dir(&mut self, forward: Direction, world_up: Direction) -> &mut Self {
+        self.0.forward = forward;
+        self.0.up = world_up;
+        self.0.orthonormalize_basis();
         self
     }
 
-    pub fn adjust_fov_in_degree(&mut self, degree: f64) -> &mut Self {
-        self.0.fov = degree;
+    /// How rays leave the camera, perspective with a 90 degrees fov by default
+    ///
+    /// Only perspective supports a thin lens, see `aperture`
+    pub fn projection(&mut self, projection: Projection) -> &mut Self {
+        self.0.projection = match projection {
+            Projection::Fisheye { angle_of_view } => Projection::Fisheye {
+                angle_of_view: angle_of_view.clamp(0., 180.),
+            },
+            p => p,
+        };
         self
     }
 
+    /// Switch to perspective if needed, keeping its fov
+    pub fn adjust_screen(&mut self, dist: f64) -> &mut Self {
+        let (fov, _) = self.0.projection.perspective();
+        self.projection(Projection::Perspective {
+            fov,
+            film_distance: dist,
+        })
+    }
+
+    /// Switch to perspective if needed, keeping its film distance
+    pub fn adjust_fov_in_degree(&mut self, degree: f64) -> &mut Self {
+        let (_, film_distance) = self.0.projection.perspective();
+        self.projection(Projection::Perspective {
+            fov: degree,
+            film_distance,
+        })
+    }
+
     pub fn adjust_fov_in_radian(&mut self, radian: f64) -> &mut Self {
-        self.0.fov = radian.to_degrees();
-        self
+        self.adjust_fov_in_degree(radian.to_degrees())
     }
 
     pub fn antialiasing(&mut self, enable: bool) -> &mut Self {
@@ -86,8 +297,81 @@ impl CameraBuilder {
         self
     }
 
+    /// Number of jittered samples per pixel when antialiasing is enabled
+    ///
+    /// Higher counts reduce aliasing and Monte Carlo noise, at linear cost of render time
+    pub fn samples_per_pixel(&mut self, n: usize) -> &mut Self {
+        self.0.samples_per_pixel = n.max(1);
+        self
+    }
+
+    /// How samples are spread over the pixel, stratified sampling converges faster than uniform
+    pub fn sampling_pattern(&mut self, pattern: SamplingPattern) -> &mut Self {
+        self.0.sampling_pattern = pattern;
+        self
+    }
+
+    /// Sample each pixel in batches of `min_samples`, until the standard error of its
+    /// luminance is below `tolerance` or `max_samples` are taken
+    ///
+    /// Only used when antialiasing is enabled, it replaces `samples_per_pixel`
+    pub fn adaptive_sampling(
+        &mut self,
+        min_samples: usize,
+        max_samples: usize,
+        tolerance: f64,
+    ) -> &mut Self {
+        // NOTE: the variance needs two samples at least
+        let min_samples = min_samples.max(2);
+        self.0.adaptive_sampling = Some(AdaptiveSampling {
+            min_samples,
+            max_samples: max_samples.max(min_samples),
+            tolerance,
+        });
+        self
+    }
+
+    /// Side of the square tiles of `render_tiled`, in pixels
+    pub fn tile_size(&mut self, size: u32) -> &mut Self {
+        self.0.tile_size = size.max(1);
+        self
+    }
+
+    /// Diameter of the thin lens, objects off the focal plane get blurred when it's positive
+    pub fn aperture(&mut self, aperture: f64) -> &mut Self {
+        self.0.aperture = aperture.max(0.);
+        self
+    }
+
+    /// Distance along `forward` of the plane which is in perfect focus
+    pub fn focus_distance(&mut self, dist: f64) -> &mut Self {
+        self.0.focus_distance = dist;
+        self
+    }
+
+    /// Tone mapping applied to every pixel before it's written to the image
+    pub fn tone_map(&mut self, tone_map: ToneMap) -> &mut Self {
+        self.0.tone_map = tone_map;
+        self
+    }
+
+    /// How `render` reports its progress, a terminal bar by default
+    pub fn progress(&mut self, progress: Progress) -> &mut Self {
+        self.0.progress = progress;
+        self
+    }
+
+    /// Seed of the random sampling, the same seed renders the same image
+    pub fn seed(&mut self, seed: u64) -> &mut Self {
+        self.0.seed = Some(seed);
+        self
+    }
+
+    /// Build the camera, `right` and `up` are re-derived from `forward` so the frame is orthonormal
     pub fn build(&mut self) -> Camera {
-        self.0.clone()
+        let mut camera = self.0.clone();
+        camera.orthonormalize_basis();
+        camera
     }
 }
 
@@ -96,15 +380,84 @@ impl Camera {
         Self::default()
     }
 
+    /// Keep `forward`, then derive `right = forward x up` and `up = right x forward`
+    fn orthonormalize_basis(&mut self) {
+        let forward = *self.forward.as_ref();
+        let mut right = forward.cross(self.up.as_ref());
+
+        // NOTE: forward is parallel to up, fallback to any axis perpendicular to forward
+        if right.magnitude() < EPSILON {
+            right = *self.forward.orthonormal_basis().0.as_ref();
+        }
+
+        self.right = Direction::from(right);
+        self.up = Direction::from(self.right.as_ref().cross(&forward));
+    }
+
     /// Get the ray: start from camera to the pixel on film
+    ///
+    /// With a positive aperture, the ray starts from a random point on the lens
+    /// and passes the point on the focal plane the pinhole ray would hit
     pub fn ray_to_pixel(&self, x: f64, y: f64) -> Ray {
-        let pixel_pos = Vector3::new(x, y, self.film_distance);
+        self.sample_ray_to_pixel(x, y, &mut rand::rng())
+    }
+
+    fn sample_ray_to_pixel<R: Rng>(&self, x: f64, y: f64, rng: &mut R) -> Ray {
         let mat = Matrix3::from_columns(&[
             *self.right.as_ref(),
             *self.up.as_ref(),
             *self.forward.as_ref(),
         ]);
-        Ray::new(self.position, Direction::from(mat * pixel_pos))
+        // NOTE: the direction in the camera frame, x right, y up and z forward
+        let local = match self.projection {
+            Projection::Perspective { film_distance, .. } => Vector3::new(x, y, film_distance),
+            Projection::Orthographic { .. } => {
+                let origin = Position::from(
+                    self.position.as_ref() + x * self.right.as_ref() + y * self.up.as_ref(),
+                );
+                return Ray::new(origin, self.forward);
+            }
+            Projection::Equirectangular => {
+                let (lon, lat) = (x, y);
+                // NOTE: sin and cos wrap around, so the seam at +-pi and the poles are seamless
+                Vector3::new(lat.cos() * lon.sin(), lat.sin(), lat.cos() * lon.cos())
+            }
+            Projection::Fisheye { angle_of_view } => {
+                let r = x.hypot(y);
+                let theta = r * (angle_of_view / 2.).to_radians();
+                // NOTE: the center looks along forward whatever the way to it
+                let (dx, dy) = if r < EPSILON {
+                    (0., 0.)
+                } else {
+                    (x / r, y / r)
+                };
+                Vector3::new(theta.sin() * dx, theta.sin() * dy, theta.cos())
+            }
+        };
+        let dir = Direction::from(mat * local);
+
+        let is_perspective = matches!(self.projection, Projection::Perspective { .. });
+        if self.aperture <= 0. || !is_perspective {
+            return Ray::new(self.position, dir);
+        }
+
+        let focus_point = self
+            .position
+            .move_forward(self.focus_distance / dir.dot(&self.forward), &dir);
+        let lens = self.sample_lens(rng);
+        let origin = Position::from(
+            self.position.as_ref() + lens.x * self.right.as_ref() + lens.y * self.up.as_ref(),
+        );
+
+        Ray::new(origin, Direction::a_to_b(&origin, &focus_point))
+    }
+
+    /// Uniform random point on the lens disk, in the right/up plane
+    fn sample_lens<R: Rng>(&self, rng: &mut R) -> Vector2<f64> {
+        let r = self.aperture / 2. * rng.random::<f64>().sqrt();
+        let theta = 2. * PI * rng.random::<f64>();
+
+        Vector2::new(r * theta.cos(), r * theta.sin())
     }
 
     /// Mapping the pixel on canvas to the pixel on the film in front of camera
@@ -120,18 +473,48 @@ impl Camera {
         self.world_coordinate(u, v, w, h)
     }
 
-    fn to_sample_film_pixel(&self, idx: usize, img_width: u32, img_height: u32) -> Vector2<f64> {
+    fn to_sample_film_pixel<R: Rng>(
+        &self,
+        idx: usize,
+        (sample_idx, count): (usize, usize),
+        (img_width, img_height): (u32, u32),
+        rng: &mut R,
+    ) -> Vector2<f64> {
         let idx = idx as u32;
         let u = (idx % img_width) as f64;
         let v = (idx / img_width) as f64;
         let w = img_width as f64;
         let h = img_height as f64;
 
-        let mut rng = rand::rng();
-        let u = rng.random_range(u - 0.5..u + 0.5);
-        let v = rng.random_range(v - 0.5..v + 0.5);
+        let offset = self.sample_offset(sample_idx, count, rng);
 
-        self.world_coordinate(u, v, w, h)
+        self.world_coordinate(u + offset.x, v + offset.y, w, h)
+    }
+
+    /// Offset of the `sample_idx`th of `count` samples from the pixel center, each component
+    /// in [-0.5, 0.5)
+    fn sample_offset<R: Rng>(&self, sample_idx: usize, count: usize, rng: &mut R) -> Vector2<f64> {
+        let uniform =
+            |rng: &mut R| Vector2::new(rng.random_range(-0.5..0.5), rng.random_range(-0.5..0.5));
+
+        match self.sampling_pattern {
+            SamplingPattern::Uniform => uniform(rng),
+            SamplingPattern::Stratified => {
+                // NOTE: samples go round the n * n grid as many whole times as they can,
+                // the ones left over are uniform, so no cell gets more samples than another
+                let n = ((count as f64).sqrt().floor() as usize).max(1);
+                if sample_idx >= count / (n * n) * (n * n) {
+                    return uniform(rng);
+                }
+
+                let cell = sample_idx % (n * n);
+                let cell_size = 1. / n as f64;
+                let x = (cell % n) as f64 + rng.random::<f64>();
+                let y = (cell / n) as f64 + rng.random::<f64>();
+
+                Vector2::new(x * cell_size - 0.5, y * cell_size - 0.5)
+            }
+        }
     }
 
     fn world_coordinate(&self, u: f64, v: f64, w: f64, h: f64) -> Vector2<f64> {
@@ -142,65 +525,1065 @@ impl Camera {
         let x_ndc = (2. * u - w) / h;
         let y_ndc = (h - 2. * v) / h;
 
-        let tan_fov = (self.fov / 2.).to_radians().tan();
+        // NOTE: half of the film height, the film is w/h times wider
+        let half_height = match self.projection {
+            Projection::Perspective { fov, film_distance } => {
+                (fov / 2.).to_radians().tan() * film_distance
+            }
+            Projection::Orthographic { width } => width / 2. * h / w,
+            // NOTE: angles, the whole width is the full circle whatever the aspect ratio
+            Projection::Equirectangular => {
+                return Vector2::new((2. * u - w) / w * PI, (h - 2. * v) / h * PI / 2.)
+            }
+            Projection::Fisheye { .. } => 1.,
+        };
 
-        Vector2::new(
-            x_ndc * tan_fov * self.film_distance,
-            y_ndc * tan_fov * self.film_distance,
-        )
+        Vector2::new(x_ndc * half_height, y_ndc * half_height)
     }
 
-    fn pixel_color<B: Background, S: RayCastStrategy>(
+    /// Random numbers for the `pass`th render of the pixel at `idx`
+    ///
+    /// Each pixel gets its own stream, so the image doesn't depend on how threads pick pixels
+    fn pixel_rng(seed: u64, idx: usize, pass: usize) -> StdRng {
+        let stream = ((idx as u64) << 24) ^ pass as u64;
+        StdRng::seed_from_u64(seed ^ stream.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+    }
+
+    /// The seed of this render, the configured one or a fresh random one
+    fn render_seed(&self) -> u64 {
+        self.seed.unwrap_or_else(|| rand::rng().random())
+    }
+
+    /// Color seen through the point `pxl` of the film
+    fn trace<B: Background, S: RayCastStrategy, R: Rng>(
+        &self,
+        scene: &Scene<B, S>,
+        pxl: &Vector2<f64>,
+        rng: &mut R,
+    ) -> Color {
+        let ray = self.sample_ray_to_pixel(pxl.x, pxl.y, rng);
+
+        // NOTE: a fisheye images a circle, outside of it only the background shows
+        if matches!(self.projection, Projection::Fisheye { .. }) && pxl.norm() > 1. {
+            return scene.background_color(&ray);
+        }
+
+        scene.cast_ray_with_rng(&ray, rng)
+    }
+
+    fn pixel_color<B: Background, S: RayCastStrategy, R: Rng>(
         &self,
         scene: &Scene<B, S>,
         idx: usize,
-        width: u32,
-        height: u32,
-    ) -> Rgb<u8> {
+        (width, height): (u32, u32),
+        rng: &mut R,
+    ) -> Color {
         let pxl = self.to_film_pixel(idx, width, height);
-        let ray = self.ray_to_pixel(pxl.x, pxl.y);
-        let color = scene.cast_ray(&ray);
 
-        Rgb::from(color)
+        self.trace(scene, &pxl, rng)
+    }
+
+    fn pixel_color_by_sampling<B: Background, S: RayCastStrategy, R: Rng>(
+        &self,
+        scene: &Scene<B, S>,
+        idx: usize,
+        size: (u32, u32),
+        rng: &mut R,
+    ) -> Color {
+        let mut color = Color::new(0., 0., 0.);
+
+        for i in 0..self.samples_per_pixel {
+            let pxl = self.to_sample_film_pixel(idx, (i, self.samples_per_pixel), size, rng);
+            color += self.trace(scene, &pxl, rng);
+        }
+
+        color / self.samples_per_pixel as f64
     }
 
-    fn pixel_color_by_sampling<B: Background, S: RayCastStrategy>(
+    /// Average color of the pixel, and how many samples it took
+    fn pixel_color_adaptive<B: Background, S: RayCastStrategy, R: Rng>(
         &self,
         scene: &Scene<B, S>,
         idx: usize,
+        size: (u32, u32),
+        adaptive: &AdaptiveSampling,
+        rng: &mut R,
+    ) -> (Color, usize) {
+        let mut color = Color::BLACK;
+        // NOTE: running sums of the luminance and its square, for the variance
+        let (mut sum, mut sum_sq) = (0., 0.);
+        let mut n = 0;
+
+        loop {
+            // NOTE: each batch is stratified on its own, so the pixel may stop after any batch
+            for i in 0..adaptive.min_samples {
+                let pxl = self.to_sample_film_pixel(idx, (i, adaptive.min_samples), size, rng);
+                let sample = self.trace(scene, &pxl, rng);
+                let lum = sample.luminance();
+
+                color += sample;
+                sum += lum;
+                sum_sq += lum * lum;
+                n += 1;
+            }
+
+            let count = n as f64;
+            let mean = sum / count;
+            let variance = ((sum_sq / count - mean * mean) * count / (count - 1.)).max(0.);
+            let std_error = (variance / count).sqrt();
+
+            if std_error <= adaptive.tolerance || n >= adaptive.max_samples {
+                return (color / count, n);
+            }
+        }
+    }
+
+    /// The `sample_idx`th of `count` samples of the pixel, jittered only when antialiasing
+    /// is enabled
+    fn sample_color<B: Background, S: RayCastStrategy, R: Rng>(
+        &self,
+        scene: &Scene<B, S>,
+        idx: usize,
+        (sample_idx, count): (usize, usize),
+        (width, height): (u32, u32),
+        rng: &mut R,
+    ) -> Color {
+        let pxl = if self.enable_antialiasing {
+            self.to_sample_film_pixel(idx, (sample_idx, count), (width, height), rng)
+        } else {
+            self.to_film_pixel(idx, width, height)
+        };
+
+        self.trace(scene, &pxl, rng)
+    }
+
+    /// Render the scene into raw linear colors, one per pixel in row-major order
+    ///
+    /// No tone mapping is applied, so buffers from repeated calls can be summed up
+    /// for progressive rendering or fed to a denoiser
+    pub fn render_to_buffer<B: Background, S: RayCastStrategy>(
+        &self,
+        scene: &Scene<B, S>,
         width: u32,
         height: u32,
-    ) -> Rgb<u8> {
-        let mut color = Color::new(0., 0., 0.);
+    ) -> Vec<Color> {
+        self.render_pixels(scene, width, height)
+            .into_iter()
+            .map(|(color, _)| color)
+            .collect()
+    }
+
+    /// Render the scene into `img`, and return how many samples each pixel took
+    ///
+    /// Mostly useful with adaptive sampling, see `output::sample_count_heatmap`
+    pub fn render_with_sample_counts<B: Background, S: RayCastStrategy>(
+        &self,
+        scene: &Scene<B, S>,
+        img: &mut RgbImage,
+    ) -> Vec<usize> {
+        let (width, height) = img.dimensions();
+        let (buffer, counts): (Vec<_>, Vec<_>) =
+            self.render_pixels(scene, width, height).into_iter().unzip();
 
-        for _i in 0..SAMPLES_PER_PIXEL {
-            let pxl = self.to_sample_film_pixel(idx, width, height);
-            let ray = self.ray_to_pixel(pxl.x, pxl.y);
-            color = color + scene.cast_ray(&ray);
+        // NOTE: the buffer is rendered at the size of the image, it can't be rejected
+        *img = output::to_rgb_image(&buffer, width, height, self.tone_map).unwrap();
+        counts
+    }
+
+    /// Color and number of samples of the pixel at `idx`, with the sampling set up for the camera
+    fn render_pixel<B: Background, S: RayCastStrategy>(
+        &self,
+        scene: &Scene<B, S>,
+        idx: usize,
+        size: (u32, u32),
+        seed: u64,
+    ) -> (Color, usize) {
+        let rng = &mut Self::pixel_rng(seed, idx, 0);
+
+        match (self.enable_antialiasing, &self.adaptive_sampling) {
+            (true, Some(adaptive)) => self.pixel_color_adaptive(scene, idx, size, adaptive, rng),
+            (true, None) => (
+                self.pixel_color_by_sampling(scene, idx, size, rng),
+                self.samples_per_pixel,
+            ),
+            (false, _) => (self.pixel_color(scene, idx, size, rng), 1),
         }
+    }
+
+    /// Render the image tile by tile, each finished tile is written into `img` right away
+    ///
+    /// `cancel` is checked before every tile, once it's set the remaining tiles are skipped
+    /// and `img` keeps what was rendered so far. `on_tile` is called after each tile is written.
+    /// Return whether every tile was rendered
+    pub fn render_tiled<B: Background, S: RayCastStrategy>(
+        &self,
+        scene: &Scene<B, S>,
+        img: &mut RgbImage,
+        cancel: Option<&AtomicBool>,
+        on_tile: impl Fn(&Tile) + Sync,
+    ) -> bool {
+        let (width, height) = img.dimensions();
+        let seed = self.render_seed();
+        let tiles = Tile::split(width, height, self.tile_size);
+        let total = tiles.len();
+        let done = AtomicUsize::new(0);
+        let img = Mutex::new(img);
+
+        tiles.into_par_iter().for_each(|tile| {
+            if cancel.is_some_and(|c| c.load(Ordering::Relaxed)) {
+                return;
+            }
+
+            let colors: Vec<Color> = tile
+                .pixels()
+                .map(|(x, y)| {
+                    let idx = (y * width + x) as usize;
+                    self.render_pixel(scene, idx, (width, height), seed).0
+                })
+                .collect();
+
+            {
+                let mut img = img.lock().unwrap();
+                for ((x, y), color) in tile.pixels().zip(colors) {
+                    img.put_pixel(x, y, Rgb::from(self.tone_map.apply(color)));
+                }
+            }
+            done.fetch_add(1, Ordering::Relaxed);
+            on_tile(&tile);
+        });
 
-        color = color / SAMPLES_PER_PIXEL as f64;
+        done.into_inner() == total
+    }
+
+    /// Color and number of samples of every pixel, in row-major order
+    fn render_pixels<B: Background, S: RayCastStrategy>(
+        &self,
+        scene: &Scene<B, S>,
+        width: u32,
+        height: u32,
+    ) -> Vec<(Color, usize)> {
+        let total = (width * height) as u64;
+        let pixels = (0..total as usize).into_par_iter();
+        let seed = self.render_seed();
+        let color_of = |idx| self.render_pixel(scene, idx, (width, height), seed);
 
-        Rgb::from(color)
+        match &self.progress {
+            Progress::Quiet => pixels.map(color_of).collect(),
+            Progress::Bar => pixels
+                .progress_with_style(progress_bar_style())
+                .map(color_of)
+                .collect(),
+            Progress::Callback(report) => {
+                // NOTE: throttle the callback, only report every `step` pixels and at the end
+                let step = (total / 100).max(1);
+                let done = AtomicU64::new(0);
+
+                pixels
+                    .map(|idx| {
+                        let color = color_of(idx);
+                        let n = done.fetch_add(1, Ordering::Relaxed) + 1;
+                        if n.is_multiple_of(step) || n == total {
+                            report(n, total);
+                        }
+                        color
+                    })
+                    .collect()
+            }
+        }
     }
 
     pub fn render<B: Background, S: RayCastStrategy>(
         &self,
         scene: &Scene<B, S>,
         img: &mut RgbImage,
+    ) {
+        let (width, height) = img.dimensions();
+        let buffer = self.render_to_buffer(scene, width, height);
+
+        // NOTE: the buffer is rendered at the size of the image, it can't be rejected
+        *img = output::to_rgb_image(&buffer, width, height, self.tone_map).unwrap();
+    }
+
+    /// Render `frames` frames while the camera orbits around `center`
+    ///
+    /// The orbit is a horizontal circle of `radius` at the height of `center`, starting from +Z,
+    /// the camera always looks at `center` with +Y as world up
+    pub fn render_orbit<B: Background, S: RayCastStrategy>(
+        &self,
+        scene: &Scene<B, S>,
+        (width, height): (u32, u32),
+        center: Position,
+        radius: f64,
+        frames: usize,
+    ) -> Vec<RgbImage> {
+        (0..frames)
+            .map(|i| {
+                let theta = 2. * PI * i as f64 / frames as f64;
+                let offset = Vector3::new(theta.sin(), 0., theta.cos()) * radius;
+
+                let mut camera = self.clone();
+                camera.position = Position::from(center.as_ref() + offset);
+                camera.forward = Direction::a_to_b(&camera.position, &center);
+                camera.up = Direction::new(0., 1., 0.);
+                camera.orthonormalize_basis();
+
+                let mut img = RgbImage::new(width, height);
+                camera.render(scene, &mut img);
+                img
+            })
+            .collect()
+    }
+
+    /// Render the scene in `passes` passes, each pass traces one more sample per pixel
+    ///
+    /// After every pass `img` holds the average of all samples so far, and `on_pass`
+    /// is called with the number of finished passes, e.g. to save or show a preview
+    pub fn render_progressive<B: Background, S: RayCastStrategy>(
+        &self,
+        scene: &Scene<B, S>,
+        img: &mut RgbImage,
+        passes: usize,
+        mut on_pass: impl FnMut(usize, &RgbImage),
     ) {
         let width = img.width();
         let height = img.height();
+        let mut accumulated = vec![Color::BLACK; (width * height) as usize];
+        let seed = self.render_seed();
 
-        img.par_pixels_mut()
-            .progress_with_style(progress_bar_style())
-            .enumerate()
-            .for_each(|(idx, pixel)| {
-                if self.enable_antialiasing {
-                    *pixel = self.pixel_color_by_sampling(scene, idx, width, height);
-                } else {
-                    *pixel = self.pixel_color(scene, idx, width, height);
-                }
-            });
+        for pass in 0..passes {
+            accumulated
+                .par_iter_mut()
+                .enumerate()
+                .for_each(|(idx, sum)| {
+                    let rng = &mut Self::pixel_rng(seed, idx, pass);
+                    *sum += self.sample_color(scene, idx, (pass, passes), (width, height), rng);
+                });
+
+            let finished = pass + 1;
+            img.pixels_mut()
+                .zip(&accumulated)
+                .for_each(|(pixel, &sum)| {
+                    *pixel = Rgb::from(self.tone_map.apply(sum / finished as f64));
+                });
+
+            on_pass(finished, img);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raytracer::world::scene::{MonteCarlo, SceneData};
+    use crate::raytracer::world::Visible;
+    use crate::raytracer::world::{
+        background::{Sky, SolidColor},
+        objects::{ConstantMedium, Material, Sphere},
+        Light,
+    };
+    use crate::raytracer::{Albedo, Interval};
+    use approx::assert_abs_diff_eq;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Mutex;
+
+    fn assert_orthonormal(camera: &Camera) {
+        let (r, u, f) = (camera.right, camera.up, camera.forward);
+
+        assert_abs_diff_eq!(r.dot(&u), 0., epsilon = EPSILON);
+        assert_abs_diff_eq!(u.dot(&f), 0., epsilon = EPSILON);
+        assert_abs_diff_eq!(f.dot(&r), 0., epsilon = EPSILON);
+
+        for d in [r, u, f] {
+            assert_abs_diff_eq!(d.as_ref().magnitude(), 1., epsilon = EPSILON);
+        }
+
+        // NOTE: right-handed, camera looks along -z of its frame
+        assert_abs_diff_eq!(Direction::from(r.as_ref().cross(u.as_ref())), f.reverse());
+        assert_abs_diff_eq!(camera.ray_to_pixel(0., 0.).dir, f);
+    }
+
+    #[test]
+    fn test_render_with_samples_per_pixel() {
+        let scene: Scene<Sky> = Scene::default()
+            .add_background(Sky)
+            .add_object(Sphere::new(
+                Position::new(0., 0., -3.),
+                1.,
+                Material::default(),
+            ))
+            .add_light(Light::new(Position::new(-20., 20., 20.), 1.5));
+
+        for spp in [1, 4] {
+            let camera = CameraBuilder::new()
+                .antialiasing(true)
+                .samples_per_pixel(spp)
+                .build();
+            assert_eq!(camera.samples_per_pixel, spp);
+
+            let mut img = RgbImage::new(8, 6);
+            camera.render(&scene, &mut img);
+        }
+    }
+
+    struct CountingCaster(AtomicUsize);
+
+    impl RayCastStrategy for CountingCaster {
+        fn cast_ray<B: Background, R: Rng>(
+            &self,
+            _scene: &SceneData<B>,
+            _ray: &Ray,
+            _depth: usize,
+            _rng: &mut R,
+        ) -> Color {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            Color::BLACK
+        }
+    }
+
+    #[test]
+    fn test_sample_count_is_respected() {
+        for (antialiasing, pattern, spp) in [
+            (false, SamplingPattern::Uniform, 7),
+            (true, SamplingPattern::Uniform, 7),
+            (true, SamplingPattern::Stratified, 9),
+            (true, SamplingPattern::Stratified, 10),
+        ] {
+            let scene: Scene<Sky, CountingCaster> = Scene::new(CountingCaster(AtomicUsize::new(0)));
+            let camera = CameraBuilder::new()
+                .antialiasing(antialiasing)
+                .samples_per_pixel(spp)
+                .sampling_pattern(pattern)
+                .build();
+
+            let mut img = RgbImage::new(4, 3);
+            camera.render(&scene, &mut img);
+
+            let expected = if antialiasing { 12 * spp } else { 12 };
+            assert_eq!(scene.ray_caster().0.load(Ordering::Relaxed), expected);
+        }
+    }
+
+    #[test]
+    fn test_stratified_offsets_stay_inside_pixel() {
+        let mut rng = rand::rng();
+
+        for spp in [1, 4, 9, 10, 16] {
+            let camera = CameraBuilder::new()
+                .samples_per_pixel(spp)
+                .sampling_pattern(SamplingPattern::Stratified)
+                .build();
+
+            for i in 0..spp * 20 {
+                let offset = camera.sample_offset(i, spp, &mut rng);
+                assert!((-0.5..0.5).contains(&offset.x), "{offset:?}");
+                assert!((-0.5..0.5).contains(&offset.y), "{offset:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_stratified_samples_cover_every_cell_once() {
+        let mut rng = rand::rng();
+        assert_eq!(
+            Camera::default().sampling_pattern,
+            SamplingPattern::Stratified
+        );
+
+        for n in [1, 2, 3, 4] {
+            let camera = CameraBuilder::new().samples_per_pixel(n * n).build();
+            let mut hits = vec![0; n * n];
+
+            for i in 0..n * n {
+                let offset = camera.sample_offset(i, n * n, &mut rng);
+                let col = ((offset.x + 0.5) * n as f64) as usize;
+                let row = ((offset.y + 0.5) * n as f64) as usize;
+                hits[row * n + col] += 1;
+            }
+
+            assert!(hits.iter().all(|&h| h == 1), "{n}x{n}: {hits:?}");
+        }
+    }
+
+    #[test]
+    fn test_stratified_leftover_samples_are_uniform() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let camera = CameraBuilder::new()
+            .samples_per_pixel(10)
+            .sampling_pattern(SamplingPattern::Stratified)
+            .build();
+
+        // NOTE: 9 of the 10 samples fill the 3x3 grid, the 10th may land in any cell
+        let outside_first_cell = (0..100)
+            .map(|_| camera.sample_offset(9, 10, &mut rng))
+            .filter(|offset| offset.x >= 1. / 3. - 0.5 || offset.y >= 1. / 3. - 0.5)
+            .count();
+        assert!(outside_first_cell > 50, "{outside_first_cell}");
+    }
+
+    #[test]
+    fn test_thin_lens_ray_origin() {
+        let position = Position::new(1., 2., 3.);
+        let pinhole = CameraBuilder::new().position(position).build();
+        let camera = CameraBuilder::new()
+            .position(position)
+            .aperture(0.5)
+            .focus_distance(4.)
+            .build();
+
+        for (x, y) in [(0., 0.), (0.3, -0.2), (-0.8, 0.5)] {
+            let ray = pinhole.ray_to_pixel(x, y);
+            assert_abs_diff_eq!(ray.position, position);
+
+            // NOTE: every lens ray starts on the lens and passes the same point on focal plane
+            let expected = ray.at(4. / ray.dir.dot(&pinhole.forward));
+            for _ in 0..20 {
+                let lens_ray = camera.ray_to_pixel(x, y);
+                assert!(lens_ray.position.distance_to(&position) <= 0.25 + EPSILON);
+
+                let t = expected.distance_to(&lens_ray.position);
+                assert_abs_diff_eq!(lens_ray.at(t), expected, epsilon = 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn test_orthographic_ray() {
+        let position = Position::new(1., 2., 3.);
+        let camera = CameraBuilder::new()
+            .position(position)
+            .projection(Projection::Orthographic { width: 4. })
+            .build();
+
+        // NOTE: every ray is parallel to forward, starting from the film around the camera
+        let ray = camera.ray_to_pixel(0.5, -0.25);
+        assert_abs_diff_eq!(ray.dir, camera.forward);
+        assert_abs_diff_eq!(ray.position, Position::new(1.5, 1.75, 3.));
+
+        // NOTE: the film is 4 wide, the left edge of the image is 2 left of the camera
+        let left = camera.to_film_pixel(0, 40, 20);
+        assert_abs_diff_eq!(left, Vector2::new(-2., 1.), epsilon = 1e-9);
+
+        // NOTE: setting the fov switches back to perspective
+        let camera = CameraBuilder::new()
+            .projection(Projection::Orthographic { width: 4. })
+            .adjust_fov_in_degree(60.)
+            .build();
+        assert_eq!(
+            camera.projection,
+            Projection::Perspective {
+                fov: 60.,
+                film_distance: 1.
+            }
+        );
+    }
+
+    #[test]
+    fn test_orthographic_keeps_sizes() {
+        let background = Color::new(0.2, 0.2, 0.2);
+        // NOTE: the same spheres, one twice as far, centers between pixels so no hit is a tie
+        let scene: Scene<SolidColor> = Scene::default()
+            .add_background(SolidColor(background))
+            .add_object(Sphere::new(
+                Position::new(-1.55, 0.05, -4.),
+                1.,
+                Material::default(),
+            ))
+            .add_object(Sphere::new(
+                Position::new(2.45, 0.05, -8.),
+                1.,
+                Material::default(),
+            ));
+
+        let covered = |projection: Projection| {
+            let camera = CameraBuilder::new()
+                .projection(projection)
+                .progress(Progress::Quiet)
+                .build();
+            let buffer = camera.render_to_buffer(&scene, 80, 40);
+            let hit = |idx: &usize| buffer[*idx] != background;
+
+            // NOTE: the near sphere is on the left half, the far one on the right half
+            let left = (0..buffer.len())
+                .filter(|i| i % 80 < 40)
+                .filter(hit)
+                .count();
+            let right = (0..buffer.len())
+                .filter(|i| i % 80 >= 40)
+                .filter(hit)
+                .count();
+            (left, right)
+        };
+
+        let (near, far) = covered(Projection::Orthographic { width: 8. });
+        // NOTE: 10 pixels per unit, a disk of radius 10 pixels
+        assert!((300..330).contains(&near), "{near}");
+        assert_eq!(near, far);
+
+        let (near, far) = covered(Projection::default());
+        assert!(near > 3 * far, "{near} vs {far}");
+    }
+
+    #[test]
+    fn test_equirectangular_directions() {
+        let camera = CameraBuilder::new()
+            .look_dir(Direction::new(1., 0., 0.), Direction::new(0., 1., 0.))
+            .projection(Projection::Equirectangular)
+            .build();
+        let (w, h) = (40, 20);
+        let dir = |u: usize, v: usize| {
+            let pxl = camera.to_film_pixel(v * w as usize + u, w, h);
+            camera.ray_to_pixel(pxl.x, pxl.y).dir
+        };
+
+        assert_abs_diff_eq!(dir(20, 10), camera.forward, epsilon = 1e-9);
+        assert_abs_diff_eq!(dir(30, 10), camera.right, epsilon = 1e-9);
+        assert_abs_diff_eq!(dir(20, 0), camera.up, epsilon = 1e-9);
+
+        // NOTE: both edges look backward, and are next to each other across the seam
+        let (left, right) = (dir(0, 10), dir(39, 10));
+        assert_abs_diff_eq!(left, camera.forward.reverse(), epsilon = 1e-9);
+        assert!(right.dot(&camera.forward) < -0.98);
+        assert!(left.dot(&right) > (2. * PI / 40.).cos() - 1e-9);
+    }
+
+    #[test]
+    fn test_fisheye_directions() {
+        let camera = CameraBuilder::new()
+            .projection(Projection::Fisheye {
+                angle_of_view: 270.,
+            })
+            .build();
+        assert_eq!(
+            camera.projection,
+            Projection::Fisheye {
+                angle_of_view: 180.
+            }
+        );
+
+        // NOTE: equidistant, the angle to forward is linear in the distance to the center
+        assert_abs_diff_eq!(camera.ray_to_pixel(0., 0.).dir, camera.forward);
+        assert_abs_diff_eq!(camera.ray_to_pixel(0., 1.).dir, camera.up, epsilon = 1e-9);
+        let half = camera.ray_to_pixel(-0.5, 0.).dir;
+        assert_abs_diff_eq!(half.dot(&camera.forward), (PI / 4.).cos(), epsilon = 1e-9);
+        assert!(half.dot(&camera.right) < 0.);
+    }
+
+    #[test]
+    fn test_fisheye_masks_outside_the_circle() {
+        let background = Color::new(0.2, 0.2, 0.2);
+        // NOTE: the camera is inside the sphere, every ray in the circle hits it
+        let scene: Scene<SolidColor> = Scene::default()
+            .add_background(SolidColor(background))
+            .add_object(Sphere::new(
+                Position::new(0., 0., 0.),
+                10.,
+                Material::default(),
+            ));
+        let camera = CameraBuilder::new()
+            .projection(Projection::Fisheye {
+                angle_of_view: 180.,
+            })
+            .progress(Progress::Quiet)
+            .build();
+        let (w, h) = (40, 20);
+        let buffer = camera.render_to_buffer(&scene, w, h);
+
+        for (idx, color) in buffer.iter().enumerate() {
+            let pxl = camera.to_film_pixel(idx, w, h);
+            assert_eq!(*color == background, pxl.norm() > 1., "{pxl}");
+        }
+    }
+
+    #[test]
+    fn test_look_at_basis_is_orthonormal() {
+        let eye = Position::new(-1., 1.5, 3.);
+        let targets = [
+            Position::new(0., 0., -10.),
+            Position::new(5., 1.5, 3.),
+            Position::new(-1., -3., 3.5),
+            Position::new(10., 20., -30.),
+        ];
+
+        for target in targets {
+            let camera = CameraBuilder::new().position(eye).look_at(target).build();
+
+            assert_orthonormal(&camera);
+            assert_abs_diff_eq!(camera.forward, Direction::a_to_b(&eye, &target));
+            assert!(camera.up.as_ref().y >= 0.);
+        }
+    }
+
+    #[test]
+    fn test_look_from_with_tilted_world_up() {
+        let eye = Position::new(2., 1., 4.);
+        let target = Position::new(0., 0., -1.);
+        let world_up = Direction::new(1., 1., 0.);
+        let camera = CameraBuilder::new()
+            .look_from(eye, target, world_up)
+            .build();
+
+        assert_orthonormal(&camera);
+        assert_abs_diff_eq!(camera.position, eye);
+        assert_abs_diff_eq!(camera.forward, Direction::a_to_b(&eye, &target));
+        // NOTE: up is the part of world up orthogonal to forward
+        assert!(camera.up.dot(&world_up) > 0.);
+        assert_abs_diff_eq!(camera.right.dot(&world_up), 0., epsilon = EPSILON);
+    }
+
+    #[test]
+    fn test_look_dir_parallel_to_world_up() {
+        let up = Direction::new(0., 1., 0.);
+
+        for forward in [up, up.reverse()] {
+            let camera = CameraBuilder::new().look_dir(forward, up).build();
+
+            assert_orthonormal(&camera);
+            assert_abs_diff_eq!(camera.forward, forward);
+        }
+    }
+
+    #[test]
+    fn test_build_orthonormalizes_forward_only_camera() {
+        let camera = CameraBuilder::new()
+            .forward_to(Direction::new(0., -1., -3.))
+            .build();
+
+        assert_orthonormal(&camera);
+        assert_abs_diff_eq!(camera.forward, Direction::new(0., -1., -3.));
+    }
+
+    #[test]
+    fn test_build_orthonormalizes_skewed_basis() {
+        let forward = Direction::new(0.2, -0.3, -1.);
+        let camera = CameraBuilder::new()
+            .forward_to(forward)
+            .up_to(Direction::new(0.3, 1., 0.4))
+            .right_to(Direction::new(1., 0.5, 0.5))
+            .build();
+
+        assert_orthonormal(&camera);
+        assert_abs_diff_eq!(camera.forward, forward);
+        // NOTE: up stays on the side of the one asked for
+        assert!(camera.up.dot(&Direction::new(0.3, 1., 0.4)) > 0.);
+    }
+
+    #[test]
+    fn test_tone_map_bright_color() {
+        let bright = Color::new(100., 20., 0.5);
+
+        // NOTE: clamp keeps the hue by normalizing the max channel
+        assert_eq!(Rgb::from(ToneMap::Clamp.apply(bright)), Rgb([255, 51, 1]));
+
+        let reinhard = ToneMap::Reinhard.apply(bright);
+        assert_abs_diff_eq!(
+            *reinhard.as_ref(),
+            Vector3::new(100. / 101., 20. / 21., 0.5 / 1.5),
+            epsilon = EPSILON
+        );
+
+        let aces = ToneMap::AcesFilmic.apply(bright);
+        assert_abs_diff_eq!(aces.as_ref().x, 1., epsilon = 0.01);
+        assert!(aces.as_ref().y > aces.as_ref().z);
+        assert!(aces.as_ref().z < 1.);
+
+        // NOTE: every operator keeps black black and never exceeds 1
+        for tone_map in [ToneMap::Reinhard, ToneMap::AcesFilmic] {
+            let c = tone_map.apply(bright);
+            assert!(c.as_ref().iter().all(|&x| (0.0..=1.0).contains(&x)));
+            assert_abs_diff_eq!(*tone_map.apply(Color::BLACK).as_ref(), Vector3::zeros());
+        }
+    }
+
+    #[test]
+    fn test_render_with_tone_map() {
+        // NOTE: a strong light right behind the camera saturates the center of the sphere
+        let scene: Scene<Sky> = Scene::default()
+            .add_object(Sphere::new(Position::new(0., 0., -3.), 1., Material::IVORY))
+            .add_light(Light::new(Position::new(0., 0., 0.), 50.));
+
+        let mut clamp = RgbImage::new(4, 4);
+        let mut reinhard = RgbImage::new(4, 4);
+        CameraBuilder::new().build().render(&scene, &mut clamp);
+        CameraBuilder::new()
+            .tone_map(ToneMap::Reinhard)
+            .build()
+            .render(&scene, &mut reinhard);
+
+        // NOTE: the center of sphere is blown out by clamping but not by reinhard
+        assert!(clamp.get_pixel(2, 2).0.contains(&255));
+        assert!(reinhard
+            .get_pixel(2, 2)
+            .0
+            .iter()
+            .all(|&c| (200..255).contains(&c)));
+    }
+
+    #[test]
+    fn test_render_to_buffer_solid_background() {
+        let background = Color::new(0.2, 1.5, 0.7);
+        let scene: Scene<SolidColor> = Scene::default().add_background(SolidColor(background));
+
+        for antialiasing in [false, true] {
+            let camera = CameraBuilder::new().antialiasing(antialiasing).build();
+            let buffer = camera.render_to_buffer(&scene, 5, 3);
+
+            assert_eq!(buffer.len(), 15);
+            for color in buffer {
+                assert_abs_diff_eq!(*color.as_ref(), *background.as_ref(), epsilon = EPSILON);
+            }
+        }
+    }
+
+    #[test]
+    fn test_seeded_render_is_reproducible() {
+        let floor = Material {
+            diffuse_color: Color::new(0.5, 0.5, 0.5),
+            ..Material::default()
+        };
+        let red_rubber = Material {
+            diffuse_color: Color::new(0.3, 0.1, 0.1),
+            albedo: Albedo::new(0.9, 0.1, 0.0, 0.0),
+            specular_exponent: 10.,
+            ..Material::default()
+        };
+        let scene: Scene<Sky, MonteCarlo> = Scene::default()
+            .add_background(Sky)
+            .add_object(Sphere::new(Position::new(0., 2., -5.), 2., red_rubber))
+            .add_object(Sphere::new(Position::new(0., -1000., 0.), 1000., floor))
+            .add_object(ConstantMedium::new(
+                Sphere::new(Position::new(2., 1., -4.), 1., Material::default()),
+                1.,
+                Color::WHITE,
+            ));
+
+        let render = |seed| {
+            let camera = CameraBuilder::new()
+                .antialiasing(true)
+                .samples_per_pixel(4)
+                .position(Position::new(0., 0.8, 0.))
+                .progress(Progress::Quiet)
+                .seed(seed)
+                .build();
+            let mut img = RgbImage::new(32, 18);
+            camera.render(&scene, &mut img);
+            img
+        };
+
+        assert_eq!(render(7).as_raw(), render(7).as_raw());
+        assert_ne!(render(7).as_raw(), render(8).as_raw());
+    }
+
+    #[test]
+    fn test_adaptive_sampling_spends_samples_on_glass() {
+        let glass = Material {
+            albedo: Albedo::new(0.0, 0.0, 0.0, 1.0),
+            refractive_index: 1.5,
+            ..Material::default()
+        };
+        let floor = Material {
+            diffuse_color: Color::new(0.5, 0.5, 0.5),
+            ..Material::default()
+        };
+        let ball = Sphere::new(Position::new(0., 0., -3.), 1., glass);
+        let ground = Sphere::new(Position::new(0., -1001., -3.), 1000., floor);
+        let scene: Scene<Sky, MonteCarlo> = Scene::default()
+            .add_background(Sky)
+            .add_object(ball.clone())
+            .add_object(ground.clone());
+
+        let camera = CameraBuilder::new()
+            .antialiasing(true)
+            .adaptive_sampling(4, 64, 0.01)
+            .progress(Progress::Quiet)
+            .seed(1)
+            .build();
+        let (width, height) = (24, 16);
+        let mut img = RgbImage::new(width, height);
+        let counts = camera.render_with_sample_counts(&scene, &mut img);
+
+        // NOTE: split the pixels by what the ray through their center hits first
+        let (mut on_sky, mut on_ball) = (vec![], vec![]);
+        for (idx, &count) in counts.iter().enumerate() {
+            let pxl = camera.to_film_pixel(idx, width, height);
+            let ray = camera.ray_to_pixel(pxl.x, pxl.y);
+            if ball.hit_by_ray(&ray, &Interval::POSITIVE).is_some() {
+                on_ball.push(count);
+            } else if ground.hit_by_ray(&ray, &Interval::POSITIVE).is_none() {
+                on_sky.push(count);
+            }
+        }
+        let average = |v: &[usize]| v.iter().sum::<usize>() as f64 / v.len() as f64;
+
+        assert!(!on_sky.is_empty() && !on_ball.is_empty());
+        assert!(counts.iter().all(|c| (4..=64).contains(c)));
+        assert!(
+            average(&on_sky) * 3. < average(&on_ball),
+            "sky: {}, ball: {}",
+            average(&on_sky),
+            average(&on_ball)
+        );
+
+        let heatmap = output::sample_count_heatmap(&counts, width, height);
+        assert_eq!(heatmap.dimensions(), (width, height));
+    }
+
+    #[test]
+    fn test_tiles_cover_image() {
+        let tiles = Tile::split(10, 7, 4);
+        assert_eq!(tiles.len(), 6);
+        assert_eq!(
+            tiles[5],
+            Tile {
+                x: 8,
+                y: 4,
+                width: 2,
+                height: 3
+            }
+        );
+
+        let mut seen = vec![0; 70];
+        for (x, y) in tiles.iter().flat_map(|t| t.pixels()) {
+            seen[(y * 10 + x) as usize] += 1;
+        }
+        assert!(seen.iter().all(|&n| n == 1));
+    }
+
+    #[test]
+    fn test_render_tiled_matches_render() {
+        let scene: Scene<Sky> = Scene::default()
+            .add_background(Sky)
+            .add_object(Sphere::new(
+                Position::new(0., 0., -3.),
+                1.,
+                Material::default(),
+            ))
+            .add_light(Light::new(Position::new(-20., 20., 20.), 1.5));
+        let camera = CameraBuilder::new()
+            .antialiasing(true)
+            .samples_per_pixel(4)
+            .tile_size(8)
+            .progress(Progress::Quiet)
+            .seed(3)
+            .build();
+
+        let mut expected = RgbImage::new(37, 23);
+        camera.render(&scene, &mut expected);
+
+        let finished = AtomicUsize::new(0);
+        let mut tiled = RgbImage::new(37, 23);
+        let complete = camera.render_tiled(&scene, &mut tiled, None, |tile| {
+            assert!(tile.width <= 8 && tile.height <= 8);
+            finished.fetch_add(1, Ordering::Relaxed);
+        });
+
+        assert!(complete);
+        assert_eq!(finished.into_inner(), 5 * 3);
+        assert_eq!(tiled, expected);
+    }
+
+    #[test]
+    fn test_render_tiled_cancel() {
+        let scene: Scene<Sky> = Scene::default().add_background(Sky);
+        let camera = CameraBuilder::new()
+            .tile_size(4)
+            .progress(Progress::Quiet)
+            .build();
+
+        // NOTE: cancelled before starting, nothing is drawn
+        let cancel = AtomicBool::new(true);
+        let mut img = RgbImage::new(16, 16);
+        assert!(!camera.render_tiled(&scene, &mut img, Some(&cancel), |_| {}));
+        assert!(img.pixels().all(|p| p.0 == [0, 0, 0]));
+
+        // NOTE: cancelled from the first finished tile, the image is partial,
+        // tiles already started on other threads still finish
+        let cancel = AtomicBool::new(false);
+        let mut img = RgbImage::new(64, 64);
+        let complete = camera.render_tiled(&scene, &mut img, Some(&cancel), |_| {
+            cancel.store(true, Ordering::Relaxed);
+        });
+        assert!(!complete);
+        let drawn = img.pixels().filter(|p| p.0 != [0, 0, 0]).count();
+        assert!((16..64 * 64).contains(&drawn), "{drawn}");
+    }
+
+    #[test]
+    fn test_render_progressive() {
+        let scene: Scene<Sky> = Scene::default().add_background(Sky);
+        let camera = CameraBuilder::new()
+            .antialiasing(true)
+            .samples_per_pixel(3)
+            .build();
+
+        let mut progressive = RgbImage::new(32, 24);
+        let mut passes = Vec::new();
+        camera.render_progressive(&scene, &mut progressive, 3, |pass, img| {
+            assert_eq!(img.dimensions(), (32, 24));
+            passes.push(pass);
+        });
+        assert_eq!(passes, vec![1, 2, 3]);
+
+        let mut direct = RgbImage::new(32, 24);
+        camera.render(&scene, &mut direct);
+
+        // NOTE: both are averages of 3 jittered samples, so they only differ by noise
+        for (p, d) in progressive.pixels().zip(direct.pixels()) {
+            for (a, b) in p.0.iter().zip(d.0) {
+                assert!(a.abs_diff(b) <= 8, "{p:?} vs {d:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_render_orbit() {
+        // NOTE: two spheres, so the view changes while the camera moves around
+        let scene: Scene<Sky> = Scene::default()
+            .add_background(Sky)
+            .add_object(Sphere::new(
+                Position::new(0., 0., 0.),
+                1.,
+                Material::default(),
+            ))
+            .add_object(Sphere::new(
+                Position::new(1.5, 0., 0.),
+                0.5,
+                Material::IVORY,
+            ))
+            .add_light(Light::new(Position::new(-20., 20., 20.), 1.5));
+
+        let camera = CameraBuilder::new().build();
+        let frames = camera.render_orbit(&scene, (16, 12), Position::new(0., 0., 0.), 5., 4);
+
+        assert_eq!(frames.len(), 4);
+        for pair in frames.windows(2) {
+            assert_eq!(pair[0].dimensions(), (16, 12));
+            assert_ne!(pair[0], pair[1]);
+        }
+    }
+
+    #[test]
+    fn test_progress_callback() {
+        let scene: Scene<Sky> = Scene::default().add_background(Sky);
+        let reports = Arc::new(Mutex::new(Vec::new()));
+
+        for (width, height, expected_calls) in [(10, 10, 100), (40, 30, 100)] {
+            reports.lock().unwrap().clear();
+            let sink = reports.clone();
+            let camera = CameraBuilder::new()
+                .progress(Progress::Callback(Arc::new(move |done, total| {
+                    sink.lock().unwrap().push((done, total));
+                })))
+                .build();
+
+            let mut img = RgbImage::new(width, height);
+            camera.render(&scene, &mut img);
+
+            let total = (width * height) as u64;
+            let mut reports = reports.lock().unwrap().clone();
+            reports.sort();
+            assert_eq!(reports.len(), expected_calls);
+            assert!(reports.iter().all(|&(done, t)| t == total && done <= total));
+            assert_eq!(reports.last(), Some(&(total, total)));
+        }
     }
 }
@@ -0,0 +1,264 @@
+//! Command line renderer, see `USAGE`
+
+use anyhow::{bail, ensure, Context, Result};
+use image::{ImageFormat, RgbImage};
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::raytracer::{
+    camera::CameraBuilder,
+    output,
+    scenes::{self, SceneWithCamera},
+    world::{scene::SceneDescription, Lambertian, MonteCarlo, RayCastStrategy},
+};
+
+pub const USAGE: &str = "\
+Usage: tiny-computer-graphics [OPTIONS]
+
+Options:
+  --scene <NAME|FILE>    builtin scene (first, random_ball) or TOML/JSON scene file [default: first]
+  --width <PIXELS>       [default: 800]
+  --height <PIXELS>      [default: 450]
+  --samples <N>          samples per pixel, turns antialiasing on
+  --strategy <STRATEGY>  lambertian or montecarlo [default: lambertian]
+  --output <PATH>        png, jpg, ppm, ... by the extension [default: output/render.png]
+  --seed <SEED>          seed of the random sampling, the same seed renders the same image
+  -h, --help             print this help";
+
+/// How rays are cast, see `RayCastStrategy`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Strategy {
+    #[default]
+    Lambertian,
+    MonteCarlo,
+}
+
+impl FromStr for Strategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "lambertian" => Ok(Self::Lambertian),
+            "montecarlo" => Ok(Self::MonteCarlo),
+            _ => bail!("unknown strategy `{s}`, expected lambertian or montecarlo"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Options {
+    /// Name of a builtin scene, or path of a scene file
+    pub scene: String,
+    pub width: u32,
+    pub height: u32,
+    /// Samples per pixel, the camera of the scene decides if `None`
+    pub samples: Option<usize>,
+    pub strategy: Strategy,
+    pub output: PathBuf,
+    pub seed: Option<u64>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            scene: "first".to_string(),
+            width: 800,
+            height: 450,
+            samples: None,
+            strategy: Strategy::default(),
+            output: PathBuf::from("output/render.png"),
+            seed: None,
+        }
+    }
+}
+
+impl Options {
+    /// Parse the arguments, without the program name, and check them before anything is rendered
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self> {
+        let mut options = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(flag) = args.next() {
+            let mut value = || {
+                args.next()
+                    .with_context(|| format!("missing value for {flag}"))
+            };
+            match flag.as_str() {
+                "--scene" => options.scene = value()?,
+                "--width" => options.width = parse_value(&flag, value()?)?,
+                "--height" => options.height = parse_value(&flag, value()?)?,
+                "--samples" => options.samples = Some(parse_value(&flag, value()?)?),
+                "--strategy" => options.strategy = value()?.parse()?,
+                "--output" => options.output = PathBuf::from(value()?),
+                "--seed" => options.seed = Some(parse_value(&flag, value()?)?),
+                _ => bail!("unknown argument `{flag}`"),
+            }
+        }
+
+        options.check()?;
+        Ok(options)
+    }
+
+    fn check(&self) -> Result<()> {
+        ensure!(
+            self.width > 0 && self.height > 0,
+            "image size {}x{} has a zero dimension",
+            self.width,
+            self.height
+        );
+        ensure!(self.samples != Some(0), "--samples must be at least 1");
+
+        let ext = self
+            .output
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default();
+        // NOTE: PPM is written by hand, see `output::save_image`
+        let writable = ext == "ppm"
+            || ImageFormat::from_extension(ext).is_some_and(|format| format.writing_enabled());
+        ensure!(
+            writable,
+            "unsupported extension `{ext}` of output {}",
+            self.output.display()
+        );
+
+        Ok(())
+    }
+}
+
+fn parse_value<T>(flag: &str, value: String) -> Result<T>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    value
+        .parse()
+        .with_context(|| format!("invalid value `{value}` for {flag}"))
+}
+
+/// The builtin scene called `name`, or the scene file at `name`, and its camera
+pub fn load_scene<S: RayCastStrategy>(name: &str, ray_caster: S) -> Result<SceneWithCamera<S>> {
+    if scenes::BUILTIN.contains(&name) {
+        return scenes::builtin(name, ray_caster);
+    }
+
+    let path = Path::new(name);
+    ensure!(
+        path.is_file(),
+        "`{name}` is neither a builtin scene ({}) nor a scene file",
+        scenes::BUILTIN.join(", ")
+    );
+    let desc = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => SceneDescription::from_json_file(path)?,
+        _ => SceneDescription::from_file(path)?,
+    };
+
+    Ok((desc.scene_with(ray_caster)?, desc.camera()))
+}
+
+/// Render the scene of `options` in memory
+pub fn render(options: &Options) -> Result<RgbImage> {
+    match options.strategy {
+        Strategy::Lambertian => render_with(options, Lambertian),
+        Strategy::MonteCarlo => render_with(options, MonteCarlo::default()),
+    }
+}
+
+fn render_with<S: RayCastStrategy>(options: &Options, ray_caster: S) -> Result<RgbImage> {
+    let (scene, camera) = load_scene(&options.scene, ray_caster)?;
+
+    let mut builder = CameraBuilder::from(camera);
+    if let Some(n) = options.samples {
+        builder.antialiasing(true).samples_per_pixel(n);
+    }
+    if let Some(seed) = options.seed {
+        builder.seed(seed);
+    }
+
+    let mut img = RgbImage::new(options.width, options.height);
+    builder.build().render(&scene, &mut img);
+
+    Ok(img)
+}
+
+/// Render and save to `options.output`, its directory is created if missing
+pub fn run(options: &Options) -> Result<()> {
+    let img = render(options)?;
+
+    if let Some(dir) = options.output.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    output::save_image(&img, &options.output)
+        .with_context(|| format!("failed to save {}", options.output.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_options() {
+        assert_eq!(parse(&[]).unwrap(), Options::default());
+
+        let options = parse(&[
+            "--scene",
+            "scenes/first.toml",
+            "--width",
+            "16",
+            "--height",
+            "9",
+            "--samples",
+            "4",
+            "--strategy",
+            "montecarlo",
+            "--output",
+            "out.ppm",
+            "--seed",
+            "7",
+        ])
+        .unwrap();
+        assert_eq!(
+            options,
+            Options {
+                scene: "scenes/first.toml".to_string(),
+                width: 16,
+                height: 9,
+                samples: Some(4),
+                strategy: Strategy::MonteCarlo,
+                output: PathBuf::from("out.ppm"),
+                seed: Some(7),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |args: &[&str]| format!("{:#}", parse(args).unwrap_err());
+
+        assert!(error(&["--width", "0"]).contains("zero dimension"));
+        assert!(error(&["--height", "-3"]).contains("invalid value `-3` for --height"));
+        assert!(error(&["--samples"]).contains("missing value for --samples"));
+        assert!(error(&["--samples", "0"]).contains("at least 1"));
+        assert!(error(&["--strategy", "whitted"]).contains("unknown strategy"));
+        assert!(error(&["--output", "render.xyz"]).contains("unsupported extension `xyz`"));
+        assert!(error(&["--output", "render"]).contains("unsupported extension"));
+        assert!(error(&["--fast"]).contains("unknown argument `--fast`"));
+    }
+
+    #[test]
+    fn test_missing_scene_file() {
+        let options = Options {
+            scene: "scenes/missing.toml".to_string(),
+            ..Options::default()
+        };
+
+        let error = render(&options).unwrap_err().to_string();
+        assert!(error.contains("neither a builtin scene"), "{error}");
+    }
+}
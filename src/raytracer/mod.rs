@@ -1,5 +1,9 @@
+pub mod animation;
 pub mod basics;
 pub mod camera;
+pub mod cli;
+pub mod output;
+pub mod scenes;
 pub mod world;
 
 pub use basics::*;
@@ -7,9 +11,9 @@ pub use basics::*;
 pub mod prelude {
     pub use super::{
         basics::*,
-        camera::{Camera, CameraBuilder},
+        camera::{Camera, CameraBuilder, Progress, Projection, SamplingPattern, ToneMap},
         world::{
-            background::{DummyBackground, Sky},
+            background::{DummyBackground, EnvironmentMap, Sky, SolidColor},
             objects::*,
             scene::Scene,
         },
@@ -0,0 +1,185 @@
+use anyhow::{ensure, Result};
+use image::{codecs::hdr::HdrEncoder, Rgb, Rgb32FImage, RgbImage};
+
+use std::env;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::raytracer::{camera::ToneMap, Color};
+
+fn check_size(buffer: &[Color], width: u32, height: u32) -> Result<()> {
+    ensure!(
+        buffer.len() == (width * height) as usize,
+        "buffer has {} pixels, expected {width}x{height}",
+        buffer.len()
+    );
+
+    Ok(())
+}
+
+/// Quantize a linear float buffer into 8 bits, with `tone_map` then the clamp of `Rgb` conversion
+pub fn to_rgb_image(
+    buffer: &[Color],
+    width: u32,
+    height: u32,
+    tone_map: ToneMap,
+) -> Result<RgbImage> {
+    check_size(buffer, width, height)?;
+
+    Ok(RgbImage::from_fn(width, height, |x, y| {
+        Rgb::from(tone_map.apply(buffer[(y * width + x) as usize]))
+    }))
+}
+
+/// Color the pixels by their number of samples, from blue for the fewest to red for the most
+pub fn sample_count_heatmap(counts: &[usize], width: u32, height: u32) -> RgbImage {
+    let min = counts.iter().copied().min().unwrap_or(0);
+    let max = counts.iter().copied().max().unwrap_or(0);
+    let range = (max - min).max(1) as f64;
+
+    RgbImage::from_fn(width, height, |x, y| {
+        let t = (counts[(y * width + x) as usize] - min) as f64 / range;
+        Rgb([(255. * t) as u8, 0, (255. * (1. - t)) as u8])
+    })
+}
+
+/// Keep the linear radiance as 32 bits floats
+pub fn to_rgb32f_image(buffer: &[Color], width: u32, height: u32) -> Result<Rgb32FImage> {
+    check_size(buffer, width, height)?;
+
+    Ok(Rgb32FImage::from_fn(width, height, |x, y| {
+        let c = buffer[(y * width + x) as usize];
+        let c = c.as_ref();
+        Rgb([c.x as f32, c.y as f32, c.z as f32])
+    }))
+}
+
+/// Save the buffer as Radiance HDR (`.hdr`)
+pub fn save_hdr<P: AsRef<Path>>(buffer: &[Color], width: u32, height: u32, path: P) -> Result<()> {
+    let img = to_rgb32f_image(buffer, width, height)?;
+    let writer = BufWriter::new(File::create(path)?);
+    let pixels = img.pixels().copied().collect::<Vec<_>>();
+    HdrEncoder::new(writer).encode(&pixels, width as usize, height as usize)?;
+
+    Ok(())
+}
+
+/// Save the buffer as portable float map (`.pfm`), little endian, rows stored bottom to top
+pub fn save_pfm<P: AsRef<Path>>(buffer: &[Color], width: u32, height: u32, path: P) -> Result<()> {
+    check_size(buffer, width, height)?;
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    // NOTE: a negative scale means little endian
+    write!(writer, "PF\n{width} {height}\n-1.0\n")?;
+
+    for row in buffer.chunks(width as usize).rev() {
+        for color in row {
+            for c in color.as_ref().iter() {
+                writer.write_all(&(*c as f32).to_le_bytes())?;
+            }
+        }
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Save the image as binary PPM (`P6`), the output format of tinyraytracer
+pub fn write_ppm<P: AsRef<Path>>(path: P, img: &RgbImage) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write!(writer, "P6\n{} {}\n255\n", img.width(), img.height())?;
+    writer.write_all(img.as_raw())?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Save the image, as PPM for a `.ppm` path, in the format of the extension otherwise
+pub fn save_image<P: AsRef<Path>>(img: &RgbImage, path: P) -> Result<()> {
+    let path = path.as_ref();
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("ppm") => write_ppm(path, img),
+        _ => Ok(img.save(path)?),
+    }
+}
+
+/// Image extension asked for with `--format <ext>` on the command line, `png` if none
+///
+/// For the examples, e.g. `cargo run --example first -- --format ppm`
+pub fn format_from_args() -> String {
+    let args: Vec<String> = env::args().collect();
+
+    args.windows(2)
+        .find(|w| w[0] == "--format")
+        .map_or_else(|| "png".to_string(), |w| w[1].clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raytracer::{
+        camera::Camera,
+        world::{background::SolidColor, Scene},
+    };
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_buffer_keeps_radiance_above_one() {
+        let scene: Scene<SolidColor> =
+            Scene::default().add_background(SolidColor(Color::new(4., 4., 4.)));
+        let buffer = Camera::default().render_to_buffer(&scene, 4, 3);
+
+        assert!(buffer.iter().all(|c| c.as_ref().x > 1.));
+
+        let img = to_rgb_image(&buffer, 4, 3, ToneMap::Clamp).unwrap();
+        assert!(img.pixels().all(|p| p.0 == [255, 255, 255]));
+
+        let hdr = to_rgb32f_image(&buffer, 4, 3).unwrap();
+        assert!(hdr.pixels().all(|p| p.0 == [4., 4., 4.]));
+
+        // NOTE: a buffer of the wrong size is an error, not a panic
+        let err = to_rgb_image(&buffer, 4, 4, ToneMap::Clamp).unwrap_err();
+        assert!(err.to_string().contains("expected 4x4"), "{err}");
+        assert!(to_rgb32f_image(&buffer[..5], 4, 3).is_err());
+    }
+
+    #[test]
+    fn test_save_float_images() {
+        let buffer = (0..6)
+            .map(|i| Color::new(i as f64, 0.5, 10.))
+            .collect::<Vec<_>>();
+
+        save_pfm(&buffer, 3, 2, "output/test_save_pfm.pfm").unwrap();
+        let bytes = std::fs::read("output/test_save_pfm.pfm").unwrap();
+        let header = b"PF\n3 2\n-1.0\n";
+        assert_eq!(&bytes[..header.len()], header);
+        assert_eq!(bytes.len(), header.len() + 6 * 3 * 4);
+        // NOTE: the first stored pixel is the bottom left one
+        let first = f32::from_le_bytes(bytes[header.len()..header.len() + 4].try_into().unwrap());
+        assert_eq!(first, 3.);
+
+        save_hdr(&buffer, 3, 2, "output/test_save_hdr.hdr").unwrap();
+        let hdr = image::open("output/test_save_hdr.hdr").unwrap().to_rgb32f();
+        assert_eq!(hdr.dimensions(), (3, 2));
+        // NOTE: radiance HDR has 8 bits mantissa shared by channels
+        assert_abs_diff_eq!(hdr.get_pixel(2, 1).0[2], 10., epsilon = 0.1);
+
+        assert!(save_pfm(&buffer, 4, 2, "output/test_save_pfm.pfm").is_err());
+    }
+
+    #[test]
+    fn test_write_ppm() {
+        let img = RgbImage::from_fn(2, 2, |x, y| Rgb([x as u8 * 255, y as u8 * 255, 7]));
+        let path = "output/test_write_ppm.ppm";
+        save_image(&img, path).unwrap();
+
+        let bytes = std::fs::read(path).unwrap();
+        let header = b"P6\n2 2\n255\n";
+        assert_eq!(&bytes[..header.len()], header);
+        assert_eq!(
+            &bytes[header.len()..],
+            &[0, 0, 7, 255, 0, 7, 0, 255, 7, 255, 255, 7]
+        );
+    }
+}
@@ -0,0 +1,269 @@
+//! Ready made scenes, shared by the examples and the command line renderer
+
+use anyhow::{bail, Result};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::raytracer::{
+    camera::{Camera, CameraBuilder},
+    world::{
+        background::{Background, DummyBackground},
+        objects::{AABBox, GradientSphere, Material, Sphere},
+        Light, RayCastStrategy, Scene,
+    },
+    Albedo, Color, Direction, Position,
+};
+
+/// A scene with any background, and the camera to look at it
+pub type SceneWithCamera<S> = (Scene<Box<dyn Background>, S>, Camera);
+
+/// Names accepted by `builtin`
+pub const BUILTIN: [&str; 2] = ["first", "random_ball"];
+
+/// The builtin scene called `name` and its camera, the scene casts rays with `ray_caster`
+pub fn builtin<S>(name: &str, ray_caster: S) -> Result<SceneWithCamera<S>>
+where
+    S: RayCastStrategy,
+{
+    match name {
+        "first" => Ok(first(ray_caster)),
+        "random_ball" => Ok(random_ball(ray_caster)),
+        _ => bail!(
+            "unknown builtin scene `{name}`, expected one of {}",
+            BUILTIN.join(", ")
+        ),
+    }
+}
+
+/// Spheres of every preset material and a box, on a mirror floor
+///
+/// Objects are named after their material, the floor is `floor`
+pub fn first<S: RayCastStrategy>(ray_caster: S) -> SceneWithCamera<S> {
+    let l1 = Light::new(Position::new(-20., 20., 20.), 1.5);
+    let l2 = Light::new(Position::new(30., 50., -25.), 1.8);
+    let l3 = Light::new(Position::new(30., 20., 30.), 1.7);
+
+    let sp1 = Sphere::new(Position::new(-3., 0., -16.), 2., Material::IVORY);
+    let sp2 = Sphere::new(Position::new(-1., -1.5, -12.), 2., Material::GLASS);
+    let sp3 = Sphere::new(Position::new(1.5, -0.5, -18.), 3., Material::RED_RUBBER);
+    let sp4 = Sphere::new(Position::new(5., 8., -18.), 4., Material::MIRROR);
+    let sp5 = Sphere::new(Position::new(-3., 2.5, -8.), 2., Material::GOLD);
+    let gradient_sp = GradientSphere::new(Position::new(7., 0.5, -10.), 2.);
+    let box1 = AABBox::try_build(
+        Position::new(4.5, -3.5, -18.),
+        Position::new(10., -1.5, -8.),
+        Material::MAGENTA_PLASTIC,
+    )
+    .unwrap();
+
+    let floor = AABBox::try_build(
+        Position::new(-100., -20., -100.),
+        Position::new(100., -3.5, 100.),
+        Material::DARK_MIRROR,
+    )
+    .unwrap();
+
+    let scene = Scene::new(ray_caster)
+        .add_background(Box::new(DummyBackground) as Box<dyn Background>)
+        .add_named_object("ivory", sp1)
+        .add_named_object("glass", sp2)
+        .add_named_object("red_rubber", sp3)
+        .add_named_object("mirror", sp4)
+        .add_named_object("gold", sp5)
+        .add_named_object("gradient", gradient_sp)
+        .add_named_object("floor", floor)
+        .add_named_object("box", box1)
+        .add_light(l1)
+        .add_light(l2)
+        .add_light(l3);
+
+    (scene, Camera::default())
+}
+
+const SMALL_RADIUS: f64 = 0.2;
+const BIG_RADIUS: f64 = 1.5;
+
+fn random_ball_around(rng: &mut StdRng, x: i32, z: i32, big_balls: &[Position]) -> Option<Sphere> {
+    let x_min = x as f64;
+    let x_max = (x + 1) as f64;
+    let y_min = z as f64;
+    let y_max = (z + 1) as f64;
+
+    let r = rng.random_range(0.0..1.0);
+    let g = rng.random_range(0.0..1.0);
+    let b = rng.random_range(0.0..1.0);
+
+    let rubber = Material {
+        diffuse_color: Color::new(r, g, b),
+        ..Material::RED_RUBBER
+    };
+
+    let glass = Material {
+        diffuse_color: Color::BLACK,
+        ..Material::GLASS
+    };
+
+    let pos = Position::new(
+        rng.random_range(x_min + SMALL_RADIUS..x_max - SMALL_RADIUS),
+        0.2,
+        rng.random_range(y_min + SMALL_RADIUS..y_max - SMALL_RADIUS),
+    );
+
+    for big_ball in big_balls {
+        if pos.distance_to(big_ball) < BIG_RADIUS + SMALL_RADIUS {
+            return None;
+        }
+    }
+
+    let mat = match rng.random_range(0..10) {
+        0..8 => rubber,
+        8..10 => glass,
+        _ => unreachable!(),
+    };
+
+    Some(Sphere::new(pos, SMALL_RADIUS, mat))
+}
+
+/// Three big balls among small ones scattered at random, always the same ones
+///
+/// The big balls are named after their material, the floor is `floor`
+pub fn random_ball<S: RayCastStrategy>(ray_caster: S) -> SceneWithCamera<S> {
+    let seed = [42u8; 32]; // 32-byte seed for StdRng
+    let mut rng = StdRng::from_seed(seed);
+
+    let floor = AABBox::try_build(
+        Position::new(-100., -20., -100.),
+        Position::new(100., 0., 100.),
+        Material::DARK_MIRROR,
+    )
+    .unwrap();
+
+    // NOTE: duller than the presets
+    let gold = Material {
+        albedo: Albedo::new(0.8, 0.2, 0.0, 0.0),
+        ..Material::GOLD
+    };
+    let glass = Material {
+        albedo: Albedo::new(0.0, 0.2, 0.0, 0.8),
+        refractive_index: 5.0,
+        ..Material::GLASS
+    };
+
+    let mut big_ball_pos = vec![Position::new(3., BIG_RADIUS, -4.)];
+    let next_pos =
+        big_ball_pos[0].move_forward(BIG_RADIUS * 2. + 0.05, &Direction::new(-1., 0., 0.));
+    big_ball_pos.push(next_pos);
+    let next_pos =
+        big_ball_pos[1].move_forward(BIG_RADIUS * 2. + 0.05, &Direction::new(-1., 0., 0.));
+    big_ball_pos.push(next_pos);
+
+    let sp_mirror = Sphere::new(big_ball_pos[0], BIG_RADIUS, glass.clone());
+    let sp_glass = Sphere::new(big_ball_pos[1], BIG_RADIUS, Material::MIRROR);
+    let sp_gold = Sphere::new(big_ball_pos[2], BIG_RADIUS, gold.clone());
+
+    let l1 = Light::new(Position::new(-20., 20., 20.), 1.5);
+    let l2 = Light::new(Position::new(30., 50., -25.), 1.8);
+    let l3 = Light::new(Position::new(30., 20., 30.), 1.7);
+
+    let mut scene = Scene::new(ray_caster)
+        .add_background(Box::new(DummyBackground) as Box<dyn Background>)
+        .add_named_object("floor", floor)
+        .add_named_object("glass", sp_mirror)
+        .add_named_object("mirror", sp_glass)
+        .add_named_object("gold", sp_gold)
+        .add_light(l1)
+        .add_light(l2)
+        .add_light(l3);
+
+    for i in -8..8 {
+        for j in -10..1 {
+            if let Some(ball) = random_ball_around(&mut rng, i, j, &big_ball_pos) {
+                scene = scene.add_object(ball);
+            }
+        }
+    }
+
+    let camera = CameraBuilder::new()
+        .adjust_fov_in_degree(60.)
+        .look_from(
+            Position::new(-1., 1.5, 3.),
+            Position::new(-1., 0.5, 0.),
+            Direction::new(0., 1., 0.),
+        )
+        .build();
+
+    (scene, camera)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raytracer::{
+        camera::Progress,
+        world::{objects::Sphere, Lambertian},
+    };
+    use image::RgbImage;
+
+    fn render(scene: &Scene<Box<dyn Background>, Lambertian>, camera: &Camera) -> RgbImage {
+        let camera = CameraBuilder::from(camera.clone())
+            .progress(Progress::Quiet)
+            .seed(1)
+            .build();
+        let mut img = RgbImage::new(64, 36);
+        camera.render(scene, &mut img);
+        img
+    }
+
+    #[test]
+    fn test_edit_named_objects_of_first() {
+        let (mut scene, camera) = first(Lambertian);
+        assert_eq!(
+            scene.object_ids().collect::<Vec<_>>(),
+            [
+                "ivory",
+                "glass",
+                "red_rubber",
+                "mirror",
+                "gold",
+                "gradient",
+                "floor",
+                "box"
+            ]
+        );
+        let before = render(&scene, &camera);
+
+        // NOTE: without the floor, the bottom of the image sees the background instead
+        let (mut scene_without_floor, _) = first(Lambertian);
+        assert!(scene_without_floor.remove_object("floor").is_some());
+        assert_ne!(render(&scene_without_floor, &camera), before);
+        assert_eq!(
+            scene_without_floor.object_ids().collect::<Vec<_>>(),
+            [
+                "ivory",
+                "glass",
+                "red_rubber",
+                "mirror",
+                "gold",
+                "gradient",
+                "box"
+            ]
+        );
+
+        // NOTE: the ivory ball turned red, the left of the image and the reflections change,
+        // the gradient ball and the box on the right don't
+        scene
+            .replace_object(
+                "ivory",
+                Sphere::new(Position::new(-3., 0., -16.), 2., Material::RED_RUBBER),
+            )
+            .unwrap();
+        let recolored = render(&scene, &camera);
+        let changed: Vec<_> = before
+            .enumerate_pixels()
+            .filter(|&(x, y, p)| recolored.get_pixel(x, y) != p)
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        assert!(!changed.is_empty());
+        assert!(changed.iter().all(|&(x, _)| x < 40), "{changed:?}");
+        assert_eq!(scene.object_ids().count(), 8);
+    }
+}
@@ -1,10 +1,21 @@
-use super::Ray;
+use anyhow::Result;
+
+use std::f64::consts::PI;
+use std::path::Path;
+
+use super::{Ray, Texture};
 use crate::raytracer::Color;
 
 pub trait Background: Send + Sync {
     fn get_color(&self, ray: &Ray) -> Color;
 }
 
+impl Background for Box<dyn Background> {
+    fn get_color(&self, ray: &Ray) -> Color {
+        self.as_ref().get_color(ray)
+    }
+}
+
 pub struct DummyBackground;
 
 impl Background for DummyBackground {
@@ -24,3 +35,112 @@ impl Background for Sky {
         (1. - a) * Color::new(1.0, 1.0, 1.0) + a * Color::new(0.5, 0.7, 1.0)
     }
 }
+
+/// The same color in every direction
+pub struct SolidColor(pub Color);
+
+impl Background for SolidColor {
+    fn get_color(&self, _ray: &Ray) -> Color {
+        self.0
+    }
+}
+
+/// Equirectangular image wrapped around the whole scene
+///
+/// The center of the image is seen looking down +Z, the top row is straight up
+pub struct EnvironmentMap(Texture);
+
+impl EnvironmentMap {
+    pub fn new(texture: Texture) -> Self {
+        Self(texture)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(Self(Texture::load(path)?))
+    }
+}
+
+impl Background for EnvironmentMap {
+    fn get_color(&self, ray: &Ray) -> Color {
+        let d = ray.dir.as_ref();
+        let longitude = d.x.atan2(d.z);
+        let latitude = d.y.clamp(-1., 1.).asin();
+
+        self.0
+            .sample(0.5 + longitude / (2. * PI), 0.5 + latitude / PI)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raytracer::{Direction, Position};
+    use approx::assert_abs_diff_eq;
+    use image::{DynamicImage, Rgb, RgbImage};
+    use nalgebra::Vector3;
+
+    #[test]
+    fn test_environment_map() {
+        let img = RgbImage::from_fn(4, 2, |x, y| Rgb([x as u8 * 50, y as u8 * 200, 100]));
+        let env = EnvironmentMap::new(Texture::new(DynamicImage::ImageRgb8(img.clone())));
+
+        // NOTE: look at the center of every texel
+        for x in 0..4 {
+            for y in 0..2 {
+                let longitude = ((x as f64 + 0.5) / 4. - 0.5) * 2. * PI;
+                let latitude = (0.5 - (y as f64 + 0.5) / 2.) * PI;
+                let dir = Direction::new(
+                    latitude.cos() * longitude.sin(),
+                    latitude.sin(),
+                    latitude.cos() * longitude.cos(),
+                );
+                let color = env.get_color(&Ray::new(Position::new(0., 0., 0.), dir));
+
+                let [r, g, b] = img.get_pixel(x, y).0.map(|c| c as f64 / 255.);
+                assert_abs_diff_eq!(*color.as_ref(), Vector3::new(r, g, b), epsilon = 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn test_environment_map_looking_down() {
+        // NOTE: only the bottom row is green
+        let img = RgbImage::from_fn(3, 3, |_, y| match y {
+            2 => Rgb([0, 255, 0]),
+            _ => Rgb([255, 0, 0]),
+        });
+        let path = "output/test_environment_map_looking_down.tga";
+        img.save(path).unwrap();
+        let env = EnvironmentMap::load(path).unwrap();
+
+        for dir in [
+            Direction::new(0., -1., 0.),
+            Direction::new(0.1, -1., 0.05),
+            Direction::new(-0.1, -1., -0.1),
+        ] {
+            let color = env.get_color(&Ray::new(Position::new(0., 0., 0.), dir));
+            assert_abs_diff_eq!(*color.as_ref(), *Color::GREEN.as_ref());
+        }
+
+        let up = env.get_color(&Ray::new(
+            Position::new(0., 0., 0.),
+            Direction::new(0., 1., 0.),
+        ));
+        assert_abs_diff_eq!(*up.as_ref(), *Color::RED.as_ref());
+    }
+
+    #[test]
+    fn test_solid_color() {
+        for dir in [
+            Direction::new(1., 2., 3.),
+            Direction::new(0., -1., 0.),
+            Direction::new(-5., 0.1, 2.),
+        ] {
+            let ray = Ray::new(Position::new(1., -2., 3.), dir);
+            assert_abs_diff_eq!(
+                *SolidColor(Color::RED).get_color(&ray).as_ref(),
+                *Color::RED.as_ref()
+            );
+        }
+    }
+}
@@ -2,7 +2,9 @@ pub mod background;
 pub mod objects;
 pub mod ray;
 pub mod scene;
+pub mod texture;
 
 pub use objects::{Light, Visible};
 pub use ray::{HitPoint, Ray};
 pub use scene::{Lambertian, MonteCarlo, RayCastStrategy, Scene};
+pub use texture::Texture;
@@ -1,4 +1,4 @@
-use crate::raytracer::{Direction, Interval, Position, EPSILON};
+use crate::raytracer::{Direction, Interval, Position};
 use anyhow::{bail, Result};
 
 use std::borrow::Cow;
@@ -70,36 +70,26 @@ impl Visible for AABBox {
     }
 
     fn surface_norm(&self, pos: &Position) -> Direction {
-        // test if on slab perpendicular to x axis
+        // NOTE: pick the closest face instead of requiring an exact match,
+        // hit points drift off the faces with floating point error on large boxes
         let pos = pos.as_ref();
         let low = self.low.as_ref();
         let high = self.high.as_ref();
 
-        if (pos.x - low.x).abs() < EPSILON {
-            return Direction::new(-1.0, 0.0, 0.0);
-        }
-
-        if (pos.x - high.x).abs() < EPSILON {
-            return Direction::new(1.0, 0.0, 0.0);
-        }
-
-        if (pos.y - low.y).abs() < EPSILON {
-            return Direction::new(0.0, -1.0, 0.0);
-        }
+        let (axis, sign, _) = (0..3)
+            .flat_map(|i| {
+                [
+                    (i, -1., (pos[i] - low[i]).abs()),
+                    (i, 1., (pos[i] - high[i]).abs()),
+                ]
+            })
+            .min_by(|(.., d1), (.., d2)| d1.total_cmp(d2))
+            .unwrap();
 
-        if (pos.y - high.y).abs() < EPSILON {
-            return Direction::new(0.0, 1.0, 0.0);
-        }
-
-        if (pos.z - low.z).abs() < EPSILON {
-            return Direction::new(0.0, 0.0, -1.0);
-        }
-
-        if (pos.z - high.z).abs() < EPSILON {
-            return Direction::new(0.0, 0.0, 1.0);
-        }
+        let mut norm = nalgebra::Vector3::zeros();
+        norm[axis] = sign;
 
-        Direction::new(0.0, 0.0, 0.0)
+        Direction::from(norm)
     }
 }
 
@@ -186,4 +176,54 @@ mod test {
             );
         }
     }
+
+    #[test]
+    fn test_aabb_ray_from_inside() {
+        let bbox = AABBox::try_build(
+            Position::new(-1., -1., -1.),
+            Position::new(1., 1., 1.),
+            Material::default(),
+        )
+        .unwrap();
+        let ray = Ray::new(Position::new(0., 0., 0.), Direction::new(1., 0., 0.));
+
+        let t = bbox.hit_by_ray(&ray, &Interval::POSITIVE).unwrap();
+        assert_abs_diff_eq!(t, 1.);
+        assert_eq!(bbox.surface_norm(&ray.at(t)), Direction::new(1., 0., 0.));
+    }
+
+    #[test]
+    fn test_aabb_norm_off_face() {
+        let bbox = AABBox::try_build(
+            Position::new(-100., -20., -100.),
+            Position::new(100., -3.5, 100.),
+            Material::default(),
+        )
+        .unwrap();
+
+        let cases = [
+            (
+                Position::new(10., -3.5 + 1e-7, 20.),
+                Direction::new(0., 1., 0.),
+            ),
+            (
+                Position::new(10., -3.5 - 1e-7, 20.),
+                Direction::new(0., 1., 0.),
+            ),
+            (
+                Position::new(100. + 1e-7, -10., 20.),
+                Direction::new(1., 0., 0.),
+            ),
+            (
+                Position::new(10., -10., -100. - 1e-7),
+                Direction::new(0., 0., -1.),
+            ),
+        ];
+
+        for (p, expected) in cases {
+            let output = bbox.surface_norm(&p);
+            assert!(output.as_ref().iter().all(|c| c.is_finite()));
+            assert_eq!(output, expected, "Input: {p:#?}");
+        }
+    }
 }
@@ -0,0 +1,273 @@
+use std::borrow::Cow;
+
+use super::{Material, Ray, Visible};
+use crate::raytracer::{Direction, Interval, Position};
+
+/// How the insides of the two children are combined
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CsgOp {
+    Union,
+    Intersection,
+    /// Inside the first child, but not the second one
+    Difference,
+}
+
+impl CsgOp {
+    fn inside(&self, in_left: bool, in_right: bool) -> bool {
+        match self {
+            CsgOp::Union => in_left || in_right,
+            CsgOp::Intersection => in_left && in_right,
+            CsgOp::Difference => in_left && !in_right,
+        }
+    }
+}
+
+/// Constructive solid geometry, e.g. a lens is the intersection of two spheres
+///
+/// The children must be closed objects, the inside of each is found with `hit_intervals`
+pub struct Csg {
+    op: CsgOp,
+    left: Box<dyn Visible>,
+    right: Box<dyn Visible>,
+}
+
+// NOTE: how far from a point the surface of a child is still considered under it
+const ON_SURFACE_EPS: f64 = 1e-6;
+
+impl Csg {
+    pub fn new<L, R>(op: CsgOp, left: L, right: R) -> Self
+    where
+        L: Visible + 'static,
+        R: Visible + 'static,
+    {
+        Self {
+            op,
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    pub fn union<L: Visible + 'static, R: Visible + 'static>(left: L, right: R) -> Self {
+        Self::new(CsgOp::Union, left, right)
+    }
+
+    pub fn intersection<L: Visible + 'static, R: Visible + 'static>(left: L, right: R) -> Self {
+        Self::new(CsgOp::Intersection, left, right)
+    }
+
+    pub fn difference<L: Visible + 'static, R: Visible + 'static>(left: L, right: R) -> Self {
+        Self::new(CsgOp::Difference, left, right)
+    }
+
+    /// Whether `pos` is on the surface of the left child, otherwise it's on the right one
+    ///
+    /// Checked by shooting a short ray back at `pos` along the child's normal
+    fn on_left(&self, pos: &Position) -> bool {
+        let norm = self.left.surface_norm(pos);
+        let ray = Ray::new(pos.move_forward(ON_SURFACE_EPS, &norm), norm.reverse());
+
+        self.left
+            .hit_by_ray(&ray, &Interval::new(0., 2. * ON_SURFACE_EPS))
+            .is_some()
+    }
+}
+
+impl Visible for Csg {
+    fn hit_by_ray(&self, ray: &Ray, interval: &Interval) -> Option<f64> {
+        self.hit_intervals(ray)
+            .into_iter()
+            .flat_map(|(enter, exit)| [enter, exit])
+            .find(|t| interval.contains(*t))
+    }
+
+    fn material_of(&self, pos: &Position) -> Cow<'_, Material> {
+        if self.on_left(pos) {
+            self.left.material_of(pos)
+        } else {
+            self.right.material_of(pos)
+        }
+    }
+
+    fn surface_norm(&self, pos: &Position) -> Direction {
+        if self.on_left(pos) {
+            return self.left.surface_norm(pos);
+        }
+
+        let norm = self.right.surface_norm(pos);
+        // NOTE: the surface of the carved out part faces into the second child
+        match self.op {
+            CsgOp::Difference => norm.reverse(),
+            _ => norm,
+        }
+    }
+
+    fn hit_intervals(&self, ray: &Ray) -> Vec<(f64, f64)> {
+        // NOTE: walk through every boundary along the ray, keeping track of
+        // which children we are in, a span starts when the combination becomes inside
+        let mut events: Vec<(f64, bool)> = self
+            .left
+            .hit_intervals(ray)
+            .into_iter()
+            .flat_map(|(enter, exit)| [(enter, true), (exit, true)])
+            .chain(
+                self.right
+                    .hit_intervals(ray)
+                    .into_iter()
+                    .flat_map(|(enter, exit)| [(enter, false), (exit, false)]),
+            )
+            .collect();
+        events.sort_by(|e1, e2| e1.0.total_cmp(&e2.0));
+
+        let (mut in_left, mut in_right) = (false, false);
+        let mut spans = Vec::new();
+        let mut enter = None;
+
+        for (t, is_left) in events {
+            if is_left {
+                in_left = !in_left;
+            } else {
+                in_right = !in_right;
+            }
+
+            match (enter, self.op.inside(in_left, in_right)) {
+                (None, true) => enter = Some(t),
+                (Some(start), false) => {
+                    spans.push((start, t));
+                    enter = None;
+                }
+                _ => {}
+            }
+        }
+
+        spans
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raytracer::world::objects::{AABBox, Sphere};
+    use crate::raytracer::{Albedo, Color};
+    use approx::assert_abs_diff_eq;
+
+    fn red() -> Material {
+        Material::new(Color::new(1., 0., 0.), Albedo::default(), 1., 1.)
+    }
+
+    fn blue() -> Material {
+        Material::new(Color::new(0., 0., 1.), Albedo::default(), 1., 1.)
+    }
+
+    fn along_z() -> Ray {
+        Ray::new(Position::new(0., 0., 0.), Direction::new(0., 0., -1.))
+    }
+
+    #[test]
+    fn test_sphere_sphere_difference() {
+        // NOTE: along -z, the big sphere spans [8, 12] and the small one [7, 9]
+        let bitten = Csg::difference(
+            Sphere::new(Position::new(0., 0., -10.), 2., red()),
+            Sphere::new(Position::new(0., 0., -8.), 1., blue()),
+        );
+
+        let ray = along_z();
+        assert_eq!(bitten.hit_intervals(&ray), vec![(9., 12.)]);
+
+        // NOTE: the first hit is the bottom of the bite, facing the ray
+        let t = bitten.hit_by_ray(&ray, &Interval::POSITIVE).unwrap();
+        assert_abs_diff_eq!(t, 9., epsilon = 1e-9);
+        let hit = ray.at(t);
+        assert_abs_diff_eq!(bitten.surface_norm(&hit), Direction::new(0., 0., 1.));
+        assert_eq!(
+            bitten.material_of(&hit).diffuse_color.as_ref(),
+            Color::new(0., 0., 1.).as_ref()
+        );
+
+        let t = bitten.hit_by_ray(&ray, &Interval::new(9.5, 100.)).unwrap();
+        assert_abs_diff_eq!(t, 12., epsilon = 1e-9);
+        let hit = ray.at(t);
+        assert_abs_diff_eq!(bitten.surface_norm(&hit), Direction::new(0., 0., -1.));
+        assert_eq!(
+            bitten.material_of(&hit).diffuse_color.as_ref(),
+            Color::new(1., 0., 0.).as_ref()
+        );
+
+        // NOTE: off the axis by 1.5, the small sphere is missed
+        let ray = Ray::new(Position::new(0., 1.5, 0.), Direction::new(0., 0., -1.));
+        let t = bitten.hit_by_ray(&ray, &Interval::POSITIVE).unwrap();
+        assert_abs_diff_eq!(t, 10. - 1.75f64.sqrt(), epsilon = 1e-9);
+        assert_eq!(
+            bitten.material_of(&ray.at(t)).diffuse_color.as_ref(),
+            Color::new(1., 0., 0.).as_ref()
+        );
+
+        // NOTE: the bite goes through the front of the big sphere
+        let ray = Ray::new(Position::new(0., 0., -20.), Direction::new(0., 0., 1.));
+        let t = bitten.hit_by_ray(&ray, &Interval::POSITIVE).unwrap();
+        assert_abs_diff_eq!(t, 8., epsilon = 1e-9);
+        let t = bitten
+            .hit_by_ray(&ray, &Interval::new(t + 1e-3, 100.))
+            .unwrap();
+        assert_abs_diff_eq!(t, 11., epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_lens_and_union() {
+        // NOTE: along -z, the spheres span [8, 12] and [9, 13]
+        let left = || Sphere::new(Position::new(0., 0., -10.), 2., red());
+        let right = || Sphere::new(Position::new(0., 0., -11.), 2., blue());
+        let ray = along_z();
+
+        let lens = Csg::intersection(left(), right());
+        assert_eq!(lens.hit_intervals(&ray), vec![(9., 12.)]);
+        let t = lens.hit_by_ray(&ray, &Interval::POSITIVE).unwrap();
+        assert_abs_diff_eq!(lens.surface_norm(&ray.at(t)), Direction::new(0., 0., 1.));
+        assert_eq!(
+            lens.material_of(&ray.at(t)).diffuse_color.as_ref(),
+            Color::new(0., 0., 1.).as_ref()
+        );
+
+        let union = Csg::union(left(), right());
+        assert_eq!(union.hit_intervals(&ray), vec![(8., 13.)]);
+        // NOTE: from inside the union, only the far end is hit
+        let inside = Ray::new(Position::new(0., 0., -10.5), Direction::new(0., 0., -1.));
+        let t = union.hit_by_ray(&inside, &Interval::POSITIVE).unwrap();
+        assert_abs_diff_eq!(t, 2.5, epsilon = 1e-9);
+        assert_abs_diff_eq!(
+            union.surface_norm(&inside.at(t)),
+            Direction::new(0., 0., -1.)
+        );
+
+        // NOTE: disjoint spheres give two spans
+        let apart = Csg::union(left(), Sphere::new(Position::new(0., 0., -20.), 1., blue()));
+        assert_eq!(apart.hit_intervals(&ray), vec![(8., 12.), (19., 21.)]);
+    }
+
+    #[test]
+    fn test_box_with_a_bite() {
+        let bitten = Csg::difference(
+            AABBox::try_build(
+                Position::new(-1., -1., -6.),
+                Position::new(1., 1., -4.),
+                red(),
+            )
+            .unwrap(),
+            Sphere::new(Position::new(0., 0., -4.), 0.5, blue()),
+        );
+
+        let ray = along_z();
+        let t = bitten.hit_by_ray(&ray, &Interval::POSITIVE).unwrap();
+        assert_abs_diff_eq!(t, 4.5, epsilon = 1e-9);
+        assert_abs_diff_eq!(bitten.surface_norm(&ray.at(t)), Direction::new(0., 0., 1.));
+
+        // NOTE: outside the bite, the front face of the box
+        let ray = Ray::new(Position::new(0.8, 0.8, 0.), Direction::new(0., 0., -1.));
+        let t = bitten.hit_by_ray(&ray, &Interval::POSITIVE).unwrap();
+        assert_abs_diff_eq!(t, 4., epsilon = 1e-9);
+        assert_abs_diff_eq!(bitten.surface_norm(&ray.at(t)), Direction::new(0., 0., 1.));
+        assert_eq!(
+            bitten.material_of(&ray.at(t)).diffuse_color.as_ref(),
+            Color::new(1., 0., 0.).as_ref()
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use nalgebra::Vector3;
+
+use std::borrow::Cow;
+
+use super::{Material, Ray, Visible};
+use crate::raytracer::{Direction, Interval, Position};
+
+/// Cylinder closed by two caps, going from `base` along `axis` for `height`
+#[derive(Clone, Debug)]
+pub struct Cylinder {
+    base: Position,
+    axis: Direction,
+    height: f64,
+    radius: f64,
+    material: Material,
+}
+
+impl Cylinder {
+    pub fn new(
+        base: Position,
+        axis: Direction,
+        height: f64,
+        radius: f64,
+        material: Material,
+    ) -> Self {
+        Self {
+            base,
+            axis,
+            height,
+            radius,
+            material,
+        }
+    }
+
+    /// Distance to the side hits whose height along the axis is within `[0, height]`
+    fn hit_side(&self, ray: &Ray) -> [Option<f64>; 2] {
+        let a = self.axis.as_ref();
+        let d = ray.dir.as_ref();
+        let cq = ray.position.as_ref() - self.base.as_ref();
+
+        // NOTE: drop the axis component, what is left is a circle in the plane orthogonal to it
+        let d_perp = d - d.dot(a) * a;
+        let cq_perp = cq - cq.dot(a) * a;
+
+        let qa = d_perp.dot(&d_perp);
+        // NOTE: parallel to the axis, only the caps can be hit
+        if qa < f64::EPSILON {
+            return [None, None];
+        }
+        let qb = 2. * d_perp.dot(&cq_perp);
+        let qc = cq_perp.dot(&cq_perp) - self.radius.powi(2);
+
+        let descriminant = qb.powi(2) - 4. * qa * qc;
+        if descriminant < 0. {
+            return [None, None];
+        }
+
+        let near = (-qb - descriminant.sqrt()) / (2. * qa);
+        let far = (-qb + descriminant.sqrt()) / (2. * qa);
+
+        [near, far].map(|t| {
+            let h = (cq + t * d).dot(a);
+            (0.0..=self.height).contains(&h).then_some(t)
+        })
+    }
+
+    /// Distance to the cap at `h` along the axis, if the hit is within the radius
+    fn hit_cap(&self, ray: &Ray, h: f64) -> Option<f64> {
+        let denom = ray.dir.dot(&self.axis);
+        if denom.abs() < f64::EPSILON {
+            return None;
+        }
+
+        let center = self.base.move_forward(h, &self.axis);
+        let t = (center.as_ref() - ray.position.as_ref()).dot(self.axis.as_ref()) / denom;
+        let p = ray.at(t);
+
+        (p.distance_to(&center) <= self.radius).then_some(t)
+    }
+}
+
+impl Visible for Cylinder {
+    fn hit_by_ray(&self, ray: &Ray, interval: &Interval) -> Option<f64> {
+        let [near, far] = self.hit_side(ray);
+
+        [
+            near,
+            far,
+            self.hit_cap(ray, 0.),
+            self.hit_cap(ray, self.height),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|t| interval.contains(*t))
+        .min_by(|t1, t2| t1.total_cmp(t2))
+    }
+
+    fn material_of(&self, _pos: &Position) -> Cow<'_, Material> {
+        Cow::Borrowed(&self.material)
+    }
+
+    fn surface_norm(&self, pos: &Position) -> Direction {
+        let a = self.axis.as_ref();
+        let cp = pos.as_ref() - self.base.as_ref();
+        let h = cp.dot(a);
+        let radial: Vector3<f64> = cp - h * a;
+
+        // NOTE: pick the closest surface, so points on the rim still get a normal
+        let to_side = (radial.norm() - self.radius).abs();
+        let to_bottom = h.abs();
+        let to_top = (h - self.height).abs();
+
+        if to_side <= to_bottom && to_side <= to_top {
+            Direction::from(radial)
+        } else if to_bottom < to_top {
+            self.axis.reverse()
+        } else {
+            self.axis
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    fn cylinder() -> Cylinder {
+        Cylinder::new(
+            Position::new(0., 0., -5.),
+            Direction::new(0., 1., 0.),
+            2.,
+            1.,
+            Material::default(),
+        )
+    }
+
+    #[test]
+    fn test_cylinder_hit_side() {
+        let cylinder = cylinder();
+        let ray = Ray::new(Position::new(0., 1., 0.), Direction::new(0., 0., -1.));
+
+        let t = cylinder.hit_by_ray(&ray, &Interval::POSITIVE).unwrap();
+        assert_abs_diff_eq!(t, 4., epsilon = 1e-9);
+        assert_abs_diff_eq!(
+            cylinder.surface_norm(&ray.at(t)),
+            Direction::new(0., 0., 1.)
+        );
+
+        // NOTE: the infinite cylinder would be hit, but above the top cap
+        let ray = Ray::new(Position::new(0., 3., 0.), Direction::new(0., 0., -1.));
+        assert!(cylinder.hit_by_ray(&ray, &Interval::POSITIVE).is_none());
+    }
+
+    #[test]
+    fn test_cylinder_hit_caps() {
+        let cylinder = cylinder();
+
+        let ray = Ray::new(Position::new(0.5, 5., -5.), Direction::new(0., -1., 0.));
+        let t = cylinder.hit_by_ray(&ray, &Interval::POSITIVE).unwrap();
+        assert_abs_diff_eq!(t, 3., epsilon = 1e-9);
+        assert_abs_diff_eq!(
+            cylinder.surface_norm(&ray.at(t)),
+            Direction::new(0., 1., 0.)
+        );
+
+        let ray = Ray::new(Position::new(0., -3., -5.), Direction::new(0., 1., 0.));
+        let t = cylinder.hit_by_ray(&ray, &Interval::POSITIVE).unwrap();
+        assert_abs_diff_eq!(t, 3., epsilon = 1e-9);
+        assert_abs_diff_eq!(
+            cylinder.surface_norm(&ray.at(t)),
+            Direction::new(0., -1., 0.)
+        );
+
+        // NOTE: from inside, the far cap is hit
+        let ray = Ray::new(Position::new(0., 1., -5.), Direction::new(0., 1., 0.));
+        let t = cylinder.hit_by_ray(&ray, &Interval::POSITIVE).unwrap();
+        assert_abs_diff_eq!(t, 1., epsilon = 1e-9);
+    }
+}
@@ -1,16 +1,295 @@
-use crate::raytracer::Position;
+use rand::Rng;
+
+use std::f64::consts::PI;
+
+use crate::raytracer::{Color, Direction, Position};
+
+/// How a point light fades with the distance `d`, `1 / (constant + linear * d + quadratic * d²)`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Attenuation {
+    pub constant: f64,
+    pub linear: f64,
+    pub quadratic: f64,
+}
+
+impl Attenuation {
+    /// No falloff, the light is as bright at any distance
+    pub const NONE: Self = Self::new(1., 0., 0.);
+
+    pub const fn new(constant: f64, linear: f64, quadratic: f64) -> Self {
+        Self {
+            constant,
+            linear,
+            quadratic,
+        }
+    }
+
+    /// `1 / (1 + k * d²)`, close to the inverse square law far from the light
+    pub const fn quadratic(k: f64) -> Self {
+        Self::new(1., 0., k)
+    }
+
+    pub fn factor(&self, dist: f64) -> f64 {
+        1. / (self.constant + self.linear * dist + self.quadratic * dist * dist)
+    }
+}
+
+impl Default for Attenuation {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
 
 #[derive(Debug)]
-pub struct Light {
-    pub position: Position,
-    pub intensity: f64,
+pub enum Light {
+    /// Light emitted from a single point in every direction, fading with `attenuation`
+    Point {
+        position: Position,
+        intensity: f64,
+        attenuation: Attenuation,
+        color: Color,
+    },
+    /// Light coming from infinitely far away, e.g. the sun,
+    /// `direction` is where the light travels to
+    Directional {
+        direction: Direction,
+        intensity: f64,
+        color: Color,
+    },
+    /// Point light shining along `direction` within a cone,
+    /// full inside `inner` and fading out to `outer`, both half angles in radians
+    Spot {
+        position: Position,
+        direction: Direction,
+        inner: f64,
+        outer: f64,
+        intensity: f64,
+        color: Color,
+    },
+    /// Light emitted from the surface of a ball, it casts soft shadows
+    ///
+    /// Each shading samples points on the surface, see `Scene::update_light_samples`,
+    /// with a radius of 0 it's a point light, sampled once
+    Sphere {
+        position: Position,
+        radius: f64,
+        intensity: f64,
+        color: Color,
+    },
+    /// Light reaching every surface evenly, regardless of geometry
+    Ambient { intensity: f64, color: Color },
 }
 
 impl Light {
+    /// Point light, kept as the default kind of light
     pub fn new(position: Position, intensity: f64) -> Self {
-        Self {
+        Self::point(position, intensity)
+    }
+
+    pub fn point(position: Position, intensity: f64) -> Self {
+        Self::Point {
             position,
             intensity,
+            attenuation: Attenuation::NONE,
+            color: Color::WHITE,
+        }
+    }
+
+    /// Point light fading with the inverse square of the distance, `k` scales the falloff
+    pub fn with_attenuation(position: Position, intensity: f64, k: f64) -> Self {
+        Self::point(position, intensity).attenuated(Attenuation::quadratic(k))
+    }
+
+    pub fn directional(direction: Direction, intensity: f64) -> Self {
+        Self::Directional {
+            direction,
+            intensity,
+            color: Color::WHITE,
+        }
+    }
+
+    /// Spot light, the cone angles are in degrees, measured from `direction`
+    pub fn spot(
+        position: Position,
+        direction: Direction,
+        inner_angle: f64,
+        outer_angle: f64,
+        intensity: f64,
+    ) -> Self {
+        Self::Spot {
+            position,
+            direction,
+            inner: inner_angle.to_radians(),
+            outer: outer_angle.to_radians(),
+            intensity,
+            color: Color::WHITE,
+        }
+    }
+
+    pub fn sphere(position: Position, radius: f64, intensity: f64) -> Self {
+        Self::Sphere {
+            position,
+            radius: radius.max(0.),
+            intensity,
+            color: Color::WHITE,
+        }
+    }
+
+    pub fn ambient(intensity: f64) -> Self {
+        Self::Ambient {
+            intensity,
+            color: Color::WHITE,
+        }
+    }
+
+    /// Set how a point light fades with distance, other lights don't fade
+    pub fn attenuated(mut self, attenuation: Attenuation) -> Self {
+        if let Self::Point { attenuation: a, .. } = &mut self {
+            *a = attenuation;
+        }
+        self
+    }
+
+    /// Shorthand for `attenuated(Attenuation::quadratic(quadratic))`
+    pub fn with_falloff(self, quadratic: f64) -> Self {
+        self.attenuated(Attenuation::quadratic(quadratic))
+    }
+
+    /// Tint the light, it's white by default
+    pub fn with_color(mut self, color: Color) -> Self {
+        match &mut self {
+            Self::Point { color: c, .. }
+            | Self::Directional { color: c, .. }
+            | Self::Spot { color: c, .. }
+            | Self::Sphere { color: c, .. }
+            | Self::Ambient { color: c, .. } => *c = color,
+        }
+        self
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            Self::Point { color, .. }
+            | Self::Directional { color, .. }
+            | Self::Spot { color, .. }
+            | Self::Sphere { color, .. }
+            | Self::Ambient { color, .. } => *color,
+        }
+    }
+
+    pub fn intensity(&self) -> f64 {
+        match self {
+            Self::Point { intensity, .. }
+            | Self::Directional { intensity, .. }
+            | Self::Spot { intensity, .. }
+            | Self::Sphere { intensity, .. }
+            | Self::Ambient { intensity, .. } => *intensity,
+        }
+    }
+
+    /// The intensity reaching a point `dist` away from the light
+    pub fn intensity_at(&self, dist: f64) -> f64 {
+        match self {
+            Self::Point {
+                intensity,
+                attenuation,
+                ..
+            } => intensity * attenuation.factor(dist),
+            _ => self.intensity(),
         }
     }
+
+    /// How much of the light goes towards `-to_light`, only spot lights are not 1
+    ///
+    /// Smoothstep from the outer cone, where it's 0, to the inner one, where it's 1
+    pub fn cone_falloff(&self, to_light: &Direction) -> f64 {
+        let Self::Spot {
+            direction,
+            inner,
+            outer,
+            ..
+        } = self
+        else {
+            return 1.;
+        };
+
+        let cos = -to_light.dot(direction);
+        let (cos_inner, cos_outer) = (inner.cos(), outer.cos());
+        if cos_inner <= cos_outer {
+            return if cos >= cos_outer { 1. } else { 0. };
+        }
+
+        let x = ((cos - cos_outer) / (cos_inner - cos_outer)).clamp(0., 1.);
+        x * x * (3. - 2. * x)
+    }
+
+    /// The direction from `pos` to the light and the distance between them
+    ///
+    /// Return `None` for ambient light, which comes from nowhere in particular
+    pub fn illuminate(&self, pos: &Position) -> Option<(Direction, f64)> {
+        match self {
+            Self::Point { position, .. }
+            | Self::Spot { position, .. }
+            | Self::Sphere { position, .. } => {
+                Some((Direction::a_to_b(pos, position), position.distance_to(pos)))
+            }
+            Self::Directional { direction, .. } => Some((direction.reverse(), f64::INFINITY)),
+            Self::Ambient { .. } => None,
+        }
+    }
+
+    /// How many times a light is worth sampling for one shading,
+    /// `samples` for a sphere light with a radius, 1 for the other lights
+    pub fn samples(&self, samples: usize) -> usize {
+        match self {
+            Self::Sphere { radius, .. } if *radius > 0. => samples.max(1),
+            _ => 1,
+        }
+    }
+
+    /// Like `illuminate`, towards a random point of a sphere light seen from `pos`
+    ///
+    /// Return `None` for the other lights, `illuminate` is all there is to them
+    pub fn illuminate_sample<R: Rng>(
+        &self,
+        pos: &Position,
+        rng: &mut R,
+    ) -> Option<(Direction, f64)> {
+        let Self::Sphere {
+            position, radius, ..
+        } = self
+        else {
+            return None;
+        };
+
+        let dist = position.distance_to(pos);
+        // NOTE: inside the light, all of it is in sight, uniform on the sphere,
+        // z uniform in [-1, 1] and a uniform angle around z
+        if dist <= *radius {
+            let z: f64 = rng.random_range(-1.0..=1.0);
+            let phi = 2. * PI * rng.random::<f64>();
+            let r = (1. - z * z).sqrt();
+            let point =
+                position.move_forward(*radius, &Direction::new(r * phi.cos(), r * phi.sin(), z));
+
+            return Some((Direction::a_to_b(pos, &point), point.distance_to(pos)));
+        }
+
+        // NOTE: uniform over the cone of directions from `pos` hitting the sphere,
+        // so only the cap facing `pos` is sampled
+        let axis = Direction::a_to_b(pos, position);
+        let cos_max = (1. - (radius / dist).powi(2)).max(0.).sqrt();
+        let cos = rng.random_range(cos_max..=1.);
+        let sin = (1. - cos * cos).max(0.).sqrt();
+        let phi = 2. * PI * rng.random::<f64>();
+        let (u, v) = axis.orthonormal_basis();
+        let dir = Direction::from(
+            sin * phi.cos() * u.as_ref() + sin * phi.sin() * v.as_ref() + cos * axis.as_ref(),
+        );
+
+        // NOTE: the nearer of the two points where `dir` meets the sphere
+        let t = dist * cos - (radius * radius - (dist * sin).powi(2)).max(0.).sqrt();
+
+        Some((dir, t))
+    }
 }
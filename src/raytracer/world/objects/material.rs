@@ -1,6 +1,8 @@
+use anyhow::{ensure, Result};
+
 use crate::raytracer::{Albedo, Color};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Material {
     pub diffuse_color: Color,
     // NOTE: albedo represents reflectivity of the surface
@@ -11,6 +13,23 @@ pub struct Material {
     pub albedo: Albedo,
     pub specular_exponent: f64,
     pub refractive_index: f64,
+    // NOTE: light emitted by the surface itself, only used by MonteCarlo
+    // any object with nonzero emission acts as a light source
+    pub emission: Color,
+    // NOTE: how blurry the reflection of a metal is, 0 is a perfect mirror, only used by MonteCarlo
+    pub fuzz: f64,
+    // NOTE: how light scatters off the material, only used by MonteCarlo
+    pub kind: MaterialKind,
+}
+
+/// How MonteCarlo scatters light off a material
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MaterialKind {
+    /// Diffuse, metal or glass by the albedo
+    #[default]
+    Surface,
+    /// Evenly in every direction, like the particles of smoke
+    Isotropic,
 }
 
 impl Default for Material {
@@ -20,6 +39,9 @@ impl Default for Material {
             albedo: Albedo::new(1.0, 0.0, 0.0, 0.0),
             specular_exponent: 50.,
             refractive_index: 1.,
+            emission: Color::BLACK,
+            fuzz: 0.,
+            kind: MaterialKind::Surface,
         }
     }
 }
@@ -36,13 +58,256 @@ impl Material {
             albedo,
             specular_exponent,
             refractive_index,
+            emission: Color::BLACK,
+            fuzz: 0.,
+            kind: MaterialKind::Surface,
         }
     }
 
+    pub const fn with_emission(mut self, emission: Color) -> Self {
+        self.emission = emission;
+        self
+    }
+
+    pub const fn with_fuzz(mut self, fuzz: f64) -> Self {
+        self.fuzz = fuzz;
+        self
+    }
+
+    /// Material of a participating medium, tinting the light it scatters with `color`
+    pub const fn isotropic(color: Color) -> Self {
+        Self {
+            diffuse_color: color,
+            albedo: Albedo::new(1.0, 0.0, 0.0, 0.0),
+            specular_exponent: 1.,
+            refractive_index: 1.,
+            emission: Color::BLACK,
+            fuzz: 0.,
+            kind: MaterialKind::Isotropic,
+        }
+    }
+
+    pub fn builder() -> MaterialBuilder {
+        MaterialBuilder::default()
+    }
+
+    /// One of the presets below by its name in snake case, e.g. `"red_rubber"`
+    pub fn preset(name: &str) -> Option<Material> {
+        let material = match name {
+            "ivory" => Self::IVORY,
+            "red_rubber" => Self::RED_RUBBER,
+            "glass" => Self::GLASS,
+            "gold" => Self::GOLD,
+            "mirror" => Self::MIRROR,
+            "dark_mirror" => Self::DARK_MIRROR,
+            "chrome" => Self::CHROME,
+            "magenta_plastic" => Self::MAGENTA_PLASTIC,
+            _ => return None,
+        };
+
+        Some(material)
+    }
+
     pub const IVORY: Material = Material::new(
         Color::new(0.4, 0.4, 0.3),
         Albedo::new(0.6, 0.3, 0.1, 0.0),
         50.,
         1.,
     );
+
+    pub const RED_RUBBER: Material = Material::new(
+        Color::new(0.3, 0.1, 0.1),
+        Albedo::new(0.9, 0.1, 0.0, 0.0),
+        10.,
+        1.,
+    );
+
+    /// Mostly refraction, the diffuse color is only seen through the little reflection
+    pub const GLASS: Material = Material::new(
+        Color::new(0.6, 0.7, 0.8),
+        Albedo::new(0.0, 0.5, 0.1, 0.8),
+        125.,
+        1.5,
+    );
+
+    pub const GOLD: Material = Material::new(
+        Color::new(0.6, 0.5, 0.3),
+        Albedo::new(0.5, 0.5, 0.1, 0.0),
+        80.,
+        0.8,
+    );
+
+    pub const MIRROR: Material =
+        Material::new(Color::BLACK, Albedo::new(1., 1., 0.87, 0.0), 1425., 1.);
+
+    /// Dim gray reflecting a little, used for floors
+    pub const DARK_MIRROR: Material = Material::new(
+        Color::new(40. / 255., 40. / 255., 40. / 255.),
+        Albedo::new(1., 0.1, 0.1, 0.0),
+        30.,
+        1.,
+    );
+
+    pub const CHROME: Material = Material::new(
+        Color::new(0.55, 0.55, 0.55),
+        Albedo::new(0.3, 0.8, 0.6, 0.0),
+        300.,
+        1.,
+    );
+
+    pub const MAGENTA_PLASTIC: Material =
+        Material::new(Color::MAGENTA, Albedo::new(0.3, 0.3, 0.1, 0.0), 20., 0.8);
+}
+
+/// Build a material step by step, starting from `Material::default()`
+///
+/// `build` checks the albedo components are not negative and the refractive index is positive
+#[derive(Default)]
+pub struct MaterialBuilder(Material);
+
+impl MaterialBuilder {
+    pub fn diffuse(&mut self, color: Color) -> &mut Self {
+        self.0.diffuse_color = color;
+        self
+    }
+
+    pub fn albedo(&mut self, albedo: Albedo) -> &mut Self {
+        self.0.albedo = albedo;
+        self
+    }
+
+    /// The specular exponent, bigger is a smaller and sharper highlight
+    pub fn specular(&mut self, exponent: f64) -> &mut Self {
+        self.0.specular_exponent = exponent;
+        self
+    }
+
+    pub fn refractive_index(&mut self, refractive_index: f64) -> &mut Self {
+        self.0.refractive_index = refractive_index;
+        self
+    }
+
+    pub fn emission(&mut self, emission: Color) -> &mut Self {
+        self.0.emission = emission;
+        self
+    }
+
+    pub fn fuzz(&mut self, fuzz: f64) -> &mut Self {
+        self.0.fuzz = fuzz;
+        self
+    }
+
+    pub fn build(&mut self) -> Result<Material> {
+        let albedo = &self.0.albedo;
+        ensure!(
+            [
+                albedo.diffusive(),
+                albedo.specular(),
+                albedo.reflective(),
+                albedo.refractive()
+            ]
+            .iter()
+            .all(|a| *a >= 0.),
+            "albedo components must not be negative"
+        );
+        ensure!(
+            self.0.refractive_index > 0.,
+            "refractive index must be positive, got {}",
+            self.0.refractive_index
+        );
+
+        Ok(self.0.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_match_hand_written_materials() {
+        let hand_written = |diffuse_color, albedo, specular_exponent, refractive_index| Material {
+            diffuse_color,
+            albedo,
+            specular_exponent,
+            refractive_index,
+            emission: Color::BLACK,
+            fuzz: 0.,
+            kind: MaterialKind::Surface,
+        };
+
+        // NOTE: as they were in examples/first.rs
+        for (preset, material) in [
+            (
+                Material::RED_RUBBER,
+                hand_written(
+                    Color::new(0.3, 0.1, 0.1),
+                    Albedo::new(0.9, 0.1, 0.0, 0.0),
+                    10.,
+                    1.,
+                ),
+            ),
+            (
+                Material::GLASS,
+                hand_written(
+                    Color::new(0.6, 0.7, 0.8),
+                    Albedo::new(0.0, 0.5, 0.1, 0.8),
+                    125.,
+                    1.5,
+                ),
+            ),
+            (
+                Material::GOLD,
+                hand_written(
+                    Color::new(0.6, 0.5, 0.3),
+                    Albedo::new(0.5, 0.5, 0.1, 0.0),
+                    80.,
+                    0.8,
+                ),
+            ),
+            (
+                Material::MIRROR,
+                hand_written(
+                    Color::new(0., 0., 0.),
+                    Albedo::new(1., 1., 0.87, 0.0),
+                    1425.,
+                    1.,
+                ),
+            ),
+        ] {
+            assert_eq!(preset, material);
+        }
+    }
+
+    #[test]
+    fn test_preset_by_name() {
+        let chrome = Material::preset("chrome").unwrap();
+        assert_eq!(chrome.specular_exponent, Material::CHROME.specular_exponent);
+        assert!(Material::preset("unobtainium").is_none());
+    }
+
+    #[test]
+    fn test_material_builder() {
+        let glass = Material::builder()
+            .diffuse(Color::new(0.6, 0.7, 0.8))
+            .albedo(Albedo::new(0.0, 0.5, 0.1, 0.8))
+            .specular(125.)
+            .refractive_index(1.5)
+            .build()
+            .unwrap();
+        assert_eq!(glass, Material::GLASS);
+
+        let err = Material::builder()
+            .albedo(Albedo::new(0.5, -0.1, 0., 0.))
+            .build()
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("albedo"), "{err}");
+        let err = Material::builder()
+            .refractive_index(0.)
+            .build()
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("refractive"), "{err}");
+    }
 }
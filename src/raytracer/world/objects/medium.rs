@@ -0,0 +1,172 @@
+use rand::{Rng, RngCore};
+
+use std::borrow::Cow;
+
+use super::{Material, Ray, Visible};
+use crate::raytracer::{Color, Direction, Interval, Position};
+
+/// Smoke or fog filling a closed `boundary`, e.g. a sphere or an AABBox
+///
+/// A ray going through it may scatter anywhere inside, the denser the sooner,
+/// the chance to go through a length `d` is `exp(-density * d)`
+pub struct ConstantMedium<V> {
+    boundary: V,
+    density: f64,
+    material: Material,
+}
+
+impl<V: Visible> ConstantMedium<V> {
+    pub fn new(boundary: V, density: f64, color: Color) -> Self {
+        Self {
+            boundary,
+            density,
+            material: Material::isotropic(color),
+        }
+    }
+}
+
+impl<V: Visible> ConstantMedium<V> {
+    /// Where `ray` scatters within `interval`, the distance to the next particle is drawn
+    /// from `rng`
+    fn scatter<R: Rng + ?Sized>(&self, ray: &Ray, interval: &Interval, rng: &mut R) -> Option<f64> {
+        for (enter, exit) in self.boundary.hit_intervals(ray) {
+            // NOTE: the ray may start inside the medium, or stop before getting out of it
+            let Some(Interval {
+                min: enter,
+                max: exit,
+            }) = Interval::new(enter, exit).intersect(interval)
+            else {
+                continue;
+            };
+
+            // NOTE: distance to the next particle, exponentially distributed,
+            // when it's farther than the way out, the ray goes through
+            let scatter = -rng.random::<f64>().ln() / self.density;
+            if scatter < exit - enter {
+                return Some(enter + scatter);
+            }
+        }
+
+        None
+    }
+}
+
+impl<V: Visible> Visible for ConstantMedium<V> {
+    fn hit_by_ray(&self, ray: &Ray, interval: &Interval) -> Option<f64> {
+        self.scatter(ray, interval, &mut rand::rng())
+    }
+
+    fn hit_part_by_ray_with_rng(
+        &self,
+        ray: &Ray,
+        interval: &Interval,
+        rng: &mut dyn RngCore,
+    ) -> Option<(f64, usize)> {
+        self.scatter(ray, interval, rng).map(|t| (t, 0))
+    }
+
+    fn material_of(&self, _pos: &Position) -> Cow<'_, Material> {
+        Cow::Borrowed(&self.material)
+    }
+
+    fn surface_norm(&self, _pos: &Position) -> Direction {
+        // NOTE: the hit is inside the volume, there is no surface,
+        // isotropic scattering doesn't use the normal anyway
+        Direction::new(1., 0., 0.)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raytracer::world::objects::{AABBox, MaterialKind};
+    use approx::assert_abs_diff_eq;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn transmission<V: Visible>(medium: &ConstantMedium<V>, ray: &Ray) -> f64 {
+        let samples = 20000;
+        let through = (0..samples)
+            .filter(|_| medium.hit_by_ray(ray, &Interval::POSITIVE).is_none())
+            .count();
+
+        through as f64 / samples as f64
+    }
+
+    #[test]
+    fn test_slab_follows_beer_lambert() {
+        // NOTE: a slab of thickness 1 between z = -1 and z = -2
+        let slab = AABBox::try_build(
+            Position::new(-100., -100., -2.),
+            Position::new(100., 100., -1.),
+            Material::default(),
+        )
+        .unwrap();
+        let fog = ConstantMedium::new(slab, 0.5, Color::WHITE);
+
+        let ray = Ray::new(Position::new(0., 0., 0.), Direction::new(0., 0., -1.));
+        assert_abs_diff_eq!(transmission(&fog, &ray), (-0.5f64).exp(), epsilon = 0.015);
+
+        // NOTE: starting in the middle, only half of the slab is left
+        let ray = Ray::new(Position::new(0., 0., -1.5), Direction::new(0., 0., -1.));
+        assert_abs_diff_eq!(transmission(&fog, &ray), (-0.25f64).exp(), epsilon = 0.015);
+
+        // NOTE: slanted, the way through is sqrt(2) long
+        let ray = Ray::new(Position::new(0., 0., 0.), Direction::new(1., 0., -1.));
+        assert_abs_diff_eq!(
+            transmission(&fog, &ray),
+            (-0.5 * 2f64.sqrt()).exp(),
+            epsilon = 0.015
+        );
+
+        // NOTE: scattering happens inside the slab, and never past the interval
+        for _ in 0..1000 {
+            if let Some(t) = fog.hit_by_ray(&ray, &Interval::new(0., 2.)) {
+                let z = ray.at(t).as_ref().z;
+                assert!((-2. ..=-1.).contains(&z) && t < 2., "t: {t}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_same_seed_scatters_at_same_distance() {
+        let fog = ConstantMedium::new(
+            AABBox::try_build(
+                Position::new(-1., -1., -2.),
+                Position::new(1., 1., -1.),
+                Material::default(),
+            )
+            .unwrap(),
+            2.,
+            Color::WHITE,
+        );
+        let ray = Ray::new(Position::new(0., 0., 0.), Direction::new(0., 0., -1.));
+        let hits = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (0..100)
+                .map(|_| fog.hit_part_by_ray_with_rng(&ray, &Interval::POSITIVE, &mut rng))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(hits(1), hits(1));
+        assert_ne!(hits(1), hits(2));
+    }
+
+    #[test]
+    fn test_medium_material_is_isotropic() {
+        let fog = ConstantMedium::new(
+            AABBox::try_build(
+                Position::new(-1., -1., -1.),
+                Position::new(1., 1., 1.),
+                Material::default(),
+            )
+            .unwrap(),
+            1.,
+            Color::new(0.5, 0.5, 0.5),
+        );
+
+        assert_eq!(
+            fog.material_of(&Position::new(0., 0., 0.)).kind,
+            MaterialKind::Isotropic
+        );
+    }
+}
@@ -1,18 +1,33 @@
+use rand::RngCore;
+
 use super::Ray;
 use crate::raytracer::{Direction, Interval, Position};
 
 use std::borrow::Cow;
 
 pub mod box_3d;
+pub mod csg;
+pub mod cylinder;
 pub mod light;
 pub mod material;
+pub mod medium;
+pub mod plane;
 pub mod sphere;
 pub mod torus;
+pub mod transformed;
+pub mod triangle_mesh;
 
 pub use box_3d::AABBox;
-pub use light::Light;
-pub use material::Material;
-pub use sphere::{GradientSphere, Sphere};
+pub use csg::{Csg, CsgOp};
+pub use cylinder::Cylinder;
+pub use light::{Attenuation, Light};
+pub use material::{Material, MaterialBuilder, MaterialKind};
+pub use medium::ConstantMedium;
+pub use plane::{Disk, Plane};
+pub use sphere::{GradientSphere, Sphere, TexturedSphere};
+pub use torus::Torus;
+pub use transformed::Transformed;
+pub use triangle_mesh::TriangleMesh;
 
 pub trait Visible: Sync + Send {
     /// return the distance from the origin to the hit point
@@ -22,6 +37,47 @@ pub trait Visible: Sync + Send {
     /// The material of the object on that position
     fn material_of(&self, pos: &Position) -> Cow<'_, material::Material>;
 
-    /// The normal vector of hit pos
+    /// The geometric normal at `pos`, always pointing outward of the object
+    ///
+    /// It's never flipped towards the incoming ray, `HitPoint::norm` does that
+    /// when the ray hits from inside
     fn surface_norm(&self, pos: &Position) -> Direction;
+
+    /// `hit_by_ray`, and which part of the object is hit, e.g. the face of a mesh
+    ///
+    /// The default is for objects that don't need to tell their parts apart, the part is always 0
+    fn hit_part_by_ray(&self, ray: &Ray, interval: &Interval) -> Option<(f64, usize)> {
+        self.hit_by_ray(ray, interval).map(|t| (t, 0))
+    }
+
+    /// `hit_part_by_ray` drawing its random choices from `rng`, e.g. where a ray scatters in
+    /// a medium, so a seeded generator makes them reproducible
+    fn hit_part_by_ray_with_rng(
+        &self,
+        ray: &Ray,
+        interval: &Interval,
+        _rng: &mut dyn RngCore,
+    ) -> Option<(f64, usize)> {
+        self.hit_part_by_ray(ray, interval)
+    }
+
+    /// `surface_norm` at `pos` on the `part` given by `hit_part_by_ray`
+    fn surface_norm_on_part(&self, pos: &Position, _part: usize) -> Direction {
+        self.surface_norm(pos)
+    }
+
+    /// The spans `(enter, exit)` of the whole ray line that are inside the object, sorted
+    ///
+    /// The default is for convex objects, where the line goes in and out at most once
+    fn hit_intervals(&self, ray: &Ray) -> Vec<(f64, f64)> {
+        let Some(enter) = self.hit_by_ray(ray, &Interval::FULL) else {
+            return Vec::new();
+        };
+        // NOTE: a tangent ray only touches the object, enter and exit are the same
+        let exit = self
+            .hit_by_ray(ray, &Interval::new(enter.next_up(), f64::INFINITY))
+            .unwrap_or(enter);
+
+        vec![(enter, exit)]
+    }
 }
@@ -0,0 +1,128 @@
+use std::borrow::Cow;
+
+use super::{Material, Ray, Visible};
+use crate::raytracer::{Direction, Interval, Position};
+
+/// Infinite plane going through `point`, facing `normal`
+#[derive(Clone, Debug)]
+pub struct Plane {
+    point: Position,
+    normal: Direction,
+    material: Material,
+}
+
+impl Plane {
+    pub fn new(point: Position, normal: Direction, material: Material) -> Self {
+        Self {
+            point,
+            normal,
+            material,
+        }
+    }
+
+    /// Distance along the ray to the plane, whatever the sign
+    fn distance(&self, ray: &Ray) -> Option<f64> {
+        let denom = ray.dir.dot(&self.normal);
+        // NOTE: parallel to the plane
+        if denom.abs() < f64::EPSILON {
+            return None;
+        }
+
+        Some((self.point.as_ref() - ray.position.as_ref()).dot(self.normal.as_ref()) / denom)
+    }
+}
+
+impl Visible for Plane {
+    fn hit_by_ray(&self, ray: &Ray, interval: &Interval) -> Option<f64> {
+        self.distance(ray).filter(|t| interval.contains(*t))
+    }
+
+    fn material_of(&self, _pos: &Position) -> Cow<'_, Material> {
+        Cow::Borrowed(&self.material)
+    }
+
+    fn surface_norm(&self, _pos: &Position) -> Direction {
+        self.normal
+    }
+}
+
+/// Round part of a plane, within `radius` of `center`
+#[derive(Clone, Debug)]
+pub struct Disk {
+    plane: Plane,
+    radius: f64,
+}
+
+impl Disk {
+    pub fn new(center: Position, normal: Direction, radius: f64, material: Material) -> Self {
+        Self {
+            plane: Plane::new(center, normal, material),
+            radius,
+        }
+    }
+}
+
+impl Visible for Disk {
+    fn hit_by_ray(&self, ray: &Ray, interval: &Interval) -> Option<f64> {
+        let t = self.plane.hit_by_ray(ray, interval)?;
+
+        (ray.at(t).distance_to(&self.plane.point) <= self.radius).then_some(t)
+    }
+
+    fn material_of(&self, pos: &Position) -> Cow<'_, Material> {
+        self.plane.material_of(pos)
+    }
+
+    fn surface_norm(&self, pos: &Position) -> Direction {
+        self.plane.surface_norm(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_plane_hit_by_ray() {
+        let plane = Plane::new(
+            Position::new(0., -1., 0.),
+            Direction::new(0., 1., 0.),
+            Material::default(),
+        );
+
+        let ray = Ray::new(Position::new(0., 1., 0.), Direction::new(0., -1., -1.));
+        let t = plane.hit_by_ray(&ray, &Interval::POSITIVE).unwrap();
+        assert_abs_diff_eq!(t, 2. * 2f64.sqrt(), epsilon = 1e-9);
+        assert_abs_diff_eq!(ray.at(t), Position::new(0., -1., -2.), epsilon = 1e-9);
+
+        // NOTE: going away from the plane, or parallel to it
+        let ray = Ray::new(Position::new(0., 1., 0.), Direction::new(0., 1., -1.));
+        assert!(plane.hit_by_ray(&ray, &Interval::POSITIVE).is_none());
+        let ray = Ray::new(Position::new(0., 1., 0.), Direction::new(0., 0., -1.));
+        assert!(plane.hit_by_ray(&ray, &Interval::POSITIVE).is_none());
+    }
+
+    #[test]
+    fn test_disk_hit_by_ray() {
+        let disk = Disk::new(
+            Position::new(0., 0., -5.),
+            Direction::new(0., 0., 1.),
+            1.,
+            Material::default(),
+        );
+
+        let inside = Ray::new(Position::new(0.5, 0.5, 0.), Direction::new(0., 0., -1.));
+        let t = disk.hit_by_ray(&inside, &Interval::POSITIVE).unwrap();
+        assert_abs_diff_eq!(t, 5., epsilon = 1e-9);
+        assert_abs_diff_eq!(disk.surface_norm(&inside.at(t)), Direction::new(0., 0., 1.));
+
+        // NOTE: the supporting plane is hit, outside the radius
+        let outside = Ray::new(Position::new(1., 1., 0.), Direction::new(0., 0., -1.));
+        assert!(disk
+            .plane
+            .hit_by_ray(&outside, &Interval::POSITIVE)
+            .is_some());
+        assert!(disk.hit_by_ray(&outside, &Interval::POSITIVE).is_none());
+    }
+}
@@ -1,9 +1,13 @@
+use anyhow::Result;
+use nalgebra::{Vector2, Vector3};
+
 use std::borrow::Cow;
+use std::f64::consts::PI;
+use std::path::Path;
 
 use super::{material::Material, Visible};
-use crate::raytracer::world::Ray;
+use crate::raytracer::world::{Ray, Texture};
 use crate::raytracer::{Color, Direction, Interval, Position};
-use nalgebra::Vector3;
 
 #[derive(Clone, Debug)]
 pub struct Sphere {
@@ -20,31 +24,72 @@ impl Sphere {
             material,
         }
     }
+
+    pub fn center(&self) -> Position {
+        self.center
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    pub fn set_center(&mut self, center: Position) {
+        self.center = center;
+    }
+
+    /// Move the sphere by `distance` along `direction`
+    pub fn translate(&mut self, direction: &Direction, distance: f64) {
+        self.center = self.center.move_forward(distance, direction);
+    }
+}
+
+impl Sphere {
+    /// Spherical uv coordinates of a point on the surface
+    ///
+    /// `u` is the longitude, growing counterclockwise around +Y starting from -X,
+    /// `v` is the latitude, from 0 at the bottom pole to 1 at the top pole
+    pub fn uv_of(&self, pos: &Position) -> Vector2<f64> {
+        let d = self.surface_norm(pos);
+        let d = d.as_ref();
+        let theta = (-d.y).clamp(-1., 1.).acos();
+        let phi = (-d.z).atan2(d.x) + PI;
+
+        Vector2::new(phi / (2. * PI), theta / PI)
+    }
 }
 
 impl Visible for Sphere {
     fn hit_by_ray(&self, ray: &Ray, interval: &Interval) -> Option<f64> {
-        // NOTE:
+        // NOTE: t * t + 2 * h * t + c = 0, with d the unit direction and CQ from the center
+        // to the ray origin
         //     ->  ->
-        // a=  d * d
-        //         ->  ->
-        // b = 2 * d * CQ
+        // h = d * CQ
         //     ->   ->
         // c = CQ * CQ - r * r
         let cq = ray.position.as_ref() - self.center.as_ref();
+        let d = ray.dir.as_ref();
 
-        let a = ray.dir.dot(&ray.dir);
-        let b = 2. * ray.dir.as_ref().dot(&cq);
+        let h = d.dot(&cq);
         let c = cq.dot(&cq) - self.radius.powi(2);
 
-        let descriminant = b.powi(2) - 4. * a * c;
+        // NOTE: h * h - c, written as r * r minus the squared distance from the center to the
+        // ray line, h * h and c are huge and nearly equal for a ray grazing a big sphere
+        let closest = cq - h * d;
+        let discriminant = self.radius.powi(2) - closest.dot(&closest);
 
-        if descriminant < 0. {
+        if discriminant < 0. {
             return None;
         }
 
-        let near = (-b - descriminant.sqrt()) / (2. * a);
-        let far = (-b + descriminant.sqrt()) / (2. * a);
+        // NOTE: the root away from zero doesn't subtract nearly equal numbers,
+        // the other one comes from the product of the roots being c
+        let q = -(h + discriminant.sqrt().copysign(h));
+        let (near, far) = if q == 0. {
+            (0., 0.)
+        } else {
+            let (t1, t2) = (q, c / q);
+            (t1.min(t2), t1.max(t2))
+        };
 
         if interval.contains(near) {
             Some(near)
@@ -90,11 +135,73 @@ impl Visible for GradientSphere {
     }
 }
 
+/// Sphere whose diffuse color is looked up from an image wrapped around it
+pub struct TexturedSphere {
+    sphere: Sphere,
+    texture: Texture,
+}
+
+impl TexturedSphere {
+    pub fn new(center: Position, radius: f64, material: Material, texture: Texture) -> Self {
+        Self {
+            sphere: Sphere::new(center, radius, material),
+            texture,
+        }
+    }
+
+    pub fn try_load<P: AsRef<Path>>(
+        center: Position,
+        radius: f64,
+        material: Material,
+        texture_path: P,
+    ) -> Result<Self> {
+        Ok(Self::new(
+            center,
+            radius,
+            material,
+            Texture::load(texture_path)?,
+        ))
+    }
+}
+
+impl Visible for TexturedSphere {
+    fn hit_by_ray(&self, ray: &Ray, interval: &Interval) -> Option<f64> {
+        self.sphere.hit_by_ray(ray, interval)
+    }
+
+    fn material_of(&self, pos: &Position) -> Cow<'_, Material> {
+        let uv = self.sphere.uv_of(pos);
+        let mut temp_mat = self.sphere.material.clone();
+        temp_mat.diffuse_color = self.texture.sample(uv.x, uv.y);
+        Cow::Owned(temp_mat)
+    }
+
+    fn surface_norm(&self, pos: &Position) -> Direction {
+        self.sphere.surface_norm(pos)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use approx::assert_abs_diff_eq;
 
+    #[test]
+    fn test_grazing_ray_on_huge_sphere() {
+        let r = 1000.;
+        let sphere = Sphere::new(Position::new(0., 0., 0.), r, Material::default());
+
+        for y in [999.9, 999.999, 999.999999] {
+            let ray = Ray::new(Position::new(-2000., y, 0.), Direction::new(1., 0., 0.));
+            // NOTE: (r - y) * (r + y) is exact enough, r * r - y * y would cancel as well
+            let expected = 2000. - ((r - y) * (r + y)).sqrt();
+
+            let t = sphere.hit_by_ray(&ray, &Interval::POSITIVE).unwrap();
+            // NOTE: b * b - 4 * a * c was 4e-9 off on the last one
+            assert_abs_diff_eq!(t, expected, epsilon = 1e-9);
+        }
+    }
+
     #[test]
     fn test_hit_by_ray() {
         // two intersection
@@ -108,6 +215,11 @@ mod tests {
             epsilon = 1e-6
         );
 
+        // both hits are past the end of the interval
+        assert!(sphere
+            .hit_by_ray(&ray, &Interval::new(0., l - 0.1))
+            .is_none());
+
         // no intersection
         let ray = Ray::new(Position::new(0., 0., 0.), Direction::new(0., 0., 1.));
         assert!(sphere.hit_by_ray(&ray, &Interval::POSITIVE).is_none());
@@ -120,4 +232,96 @@ mod tests {
             epsilon = 1e-6
         );
     }
+
+    #[test]
+    fn test_move_sphere() {
+        let mut sphere = Sphere::new(Position::new(0., 0., -5.), 1., Material::default());
+        let ray = Ray::new(Position::new(0., 0., 0.), Direction::new(0., 0., -1.));
+        assert_abs_diff_eq!(
+            sphere.hit_by_ray(&ray, &Interval::POSITIVE).unwrap(),
+            4.,
+            epsilon = 1e-9
+        );
+
+        sphere.translate(&Direction::new(0., 0., -1.), 3.);
+        assert_abs_diff_eq!(sphere.center(), Position::new(0., 0., -8.));
+        assert_abs_diff_eq!(
+            sphere.hit_by_ray(&ray, &Interval::POSITIVE).unwrap(),
+            7.,
+            epsilon = 1e-9
+        );
+
+        // NOTE: moved aside, the ray misses it now
+        sphere.set_center(Position::new(3., 0., -8.));
+        assert!(sphere.hit_by_ray(&ray, &Interval::POSITIVE).is_none());
+    }
+
+    #[test]
+    fn test_uv_of_sphere() {
+        let sphere = Sphere::new(Position::new(1., 2., 3.), 2., Material::default());
+        let on_surface = |x, y, z| Position::new(1. + 2. * x, 2. + 2. * y, 3. + 2. * z);
+
+        let test_cases = [
+            // poles
+            (on_surface(0., 1., 0.), None, 1.),
+            (on_surface(0., -1., 0.), None, 0.),
+            // equator at 0, 90 and 180 degrees
+            (on_surface(1., 0., 0.), Some(0.5), 0.5),
+            (on_surface(0., 0., -1.), Some(0.75), 0.5),
+            (on_surface(0., 0., 1.), Some(0.25), 0.5),
+        ];
+
+        for (pos, u, v) in test_cases {
+            let uv = sphere.uv_of(&pos);
+            if let Some(u) = u {
+                assert_abs_diff_eq!(uv.x, u, epsilon = 1e-9);
+            }
+            assert_abs_diff_eq!(uv.y, v, epsilon = 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_textured_sphere_samples_texture() {
+        use image::{DynamicImage, Rgb, RgbImage};
+
+        // NOTE: top row red, bottom row blue, the left/right halves differ in green
+        let img = RgbImage::from_fn(4, 2, |x, y| match (x < 2, y) {
+            (true, 0) => Rgb([255, 0, 0]),
+            (false, 0) => Rgb([255, 255, 0]),
+            (true, _) => Rgb([0, 0, 255]),
+            (false, _) => Rgb([0, 255, 255]),
+        });
+        let texture = Texture::new(DynamicImage::ImageRgb8(img));
+        let sphere =
+            TexturedSphere::new(Position::new(0., 0., 0.), 1., Material::default(), texture);
+
+        let color_at = |x, y, z| {
+            *sphere
+                .material_of(&Position::new(x, y, z))
+                .diffuse_color
+                .as_ref()
+        };
+
+        assert_abs_diff_eq!(
+            color_at(0., 1., 0.),
+            Vector3::new(1., 0.5, 0.),
+            epsilon = 1e-6
+        );
+        assert_abs_diff_eq!(
+            color_at(0., -1., 0.),
+            Vector3::new(0., 0.5, 1.),
+            epsilon = 1e-6
+        );
+        // NOTE: u = 0.25 is the center of the left half, u = 0.75 the right one
+        assert_abs_diff_eq!(
+            color_at(0., 0., 1.),
+            Vector3::new(0.5, 0., 0.5),
+            epsilon = 1e-6
+        );
+        assert_abs_diff_eq!(
+            color_at(0., 0., -1.),
+            Vector3::new(0.5, 1., 0.5),
+            epsilon = 1e-6
+        );
+    }
 }
@@ -1,8 +1,13 @@
 use std::borrow::Cow;
 
-use super::{Material, Visible};
+use nalgebra::Vector3;
+
+use super::{Material, Ray, Visible};
 use crate::raytracer::{Direction, Interval, Position};
 
+/// Torus around the z axis through `center`
+///
+/// `R` is the distance from the center to the middle of the tube, `r` the radius of the tube
 #[allow(non_snake_case)]
 pub struct Torus {
     center: Position,
@@ -12,32 +17,148 @@ pub struct Torus {
     material: Material,
 }
 
+// NOTE: the ray is sampled this many times across the bounding sphere to find the roots,
+// spans thinner than a step can be missed, e.g. a ray grazing the tube
+const STEPS: usize = 256;
+
+impl Torus {
+    #[allow(non_snake_case)]
+    pub fn new(center: Position, R: f64, r: f64, material: Material) -> Self {
+        Self {
+            center,
+            R,
+            r,
+            material,
+        }
+    }
+
+    /// `(|p|² + R² - r²)² - 4R²(x² + y²)`, negative inside the tube, `p` relative to the center
+    fn implicit(&self, p: &Vector3<f64>) -> f64 {
+        let s = p.magnitude_squared() + self.R.powi(2) - self.r.powi(2);
+        s.powi(2) - 4. * self.R.powi(2) * (p.x.powi(2) + p.y.powi(2))
+    }
+}
+
 impl Visible for Torus {
-    fn hit_by_ray(&self, _ray: &crate::raytracer::world::Ray, _interval: &Interval) -> Option<f64> {
-        todo!()
+    fn hit_by_ray(&self, ray: &Ray, interval: &Interval) -> Option<f64> {
+        self.hit_intervals(ray)
+            .into_iter()
+            .flat_map(|(enter, exit)| [enter, exit])
+            .find(|t| interval.contains(*t))
     }
 
     fn material_of(&self, _pos: &Position) -> Cow<'_, Material> {
         Cow::Borrowed(&self.material)
     }
 
-    fn surface_norm(&self, pos: &Position) -> crate::raytracer::Direction {
+    fn surface_norm(&self, pos: &Position) -> Direction {
         let rp = pos.as_ref() - self.center.as_ref();
 
-        let sin_theta = rp.z / self.r;
-        let cos_theta = (1. - sin_theta.powi(2)).sqrt();
-        let cos_phi = rp.x / (self.R + self.r * cos_theta);
-        let sin_phi = (1. - cos_phi.powi(2)).sqrt();
-        let r_circle_center = Position::new(self.R * cos_phi, self.R * sin_phi, 0.);
-        Direction::a_to_b(&r_circle_center, &Position::from(rp))
+        // NOTE: from the closest point on the circle in the middle of the tube
+        let around = Vector3::new(rp.x, rp.y, 0.).normalize();
+        Direction::from(rp - self.R * around)
+    }
+
+    /// The quartic has up to 4 roots, so the line can go through the tube twice
+    fn hit_intervals(&self, ray: &Ray) -> Vec<(f64, f64)> {
+        let o = ray.position.as_ref() - self.center.as_ref();
+        let d = ray.dir.as_ref();
+        let f = |t: f64| self.implicit(&(o + t * d));
+
+        // NOTE: the torus is inside the sphere of radius R + r, where the implicit is positive
+        let h = o.dot(d);
+        let disc = h * h - (o.magnitude_squared() - (self.R + self.r).powi(2));
+        if disc <= 0. {
+            return Vec::new();
+        }
+        let (t0, t1) = (-h - disc.sqrt(), -h + disc.sqrt());
+        let step = (t1 - t0) / STEPS as f64;
+
+        let mut spans = Vec::new();
+        let mut enter = None;
+        for i in 0..STEPS {
+            let (a, b) = (t0 + i as f64 * step, t0 + (i + 1) as f64 * step);
+            if (f(a) < 0.) == (f(b) < 0.) {
+                continue;
+            }
+
+            let root = bisect(&f, a, b);
+            match enter.take() {
+                Some(enter) => spans.push((enter, root)),
+                None => enter = Some(root),
+            }
+        }
+
+        spans
     }
 }
 
+/// The root of `f` between `a` and `b`, where `f` changes sign
+fn bisect<F: Fn(f64) -> f64>(f: &F, mut a: f64, mut b: f64) -> f64 {
+    let inside_a = f(a) < 0.;
+    for _ in 0..64 {
+        let mid = (a + b) / 2.;
+        if (f(mid) < 0.) == inside_a {
+            a = mid;
+        } else {
+            b = mid;
+        }
+    }
+
+    (a + b) / 2.
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::raytracer::world::objects::{AABBox, Csg};
     use approx::assert_abs_diff_eq;
 
+    #[test]
+    fn test_ray_through_both_sides_of_the_tube() {
+        let torus = Torus::new(Position::new(0., 0., 0.), 2., 1., Material::default());
+
+        // NOTE: along the x axis, in and out of the tube at -3, -1, 1 and 3
+        let ray = Ray::new(Position::new(-5., 0., 0.), Direction::new(1., 0., 0.));
+        let spans = torus.hit_intervals(&ray);
+        assert_eq!(spans.len(), 2);
+        assert_abs_diff_eq!(spans[0].0, 2., epsilon = 1e-9);
+        assert_abs_diff_eq!(spans[0].1, 4., epsilon = 1e-9);
+        assert_abs_diff_eq!(spans[1].0, 6., epsilon = 1e-9);
+        assert_abs_diff_eq!(spans[1].1, 8., epsilon = 1e-9);
+
+        // NOTE: from inside the hole, the first hit is the inner side of the tube
+        let t = torus
+            .hit_by_ray(
+                &Ray::new(Position::new(0., 0., 0.), Direction::new(0., -1., 0.)),
+                &Interval::POSITIVE,
+            )
+            .unwrap();
+        assert_abs_diff_eq!(t, 1., epsilon = 1e-9);
+        assert_abs_diff_eq!(
+            torus.surface_norm(&Position::new(0., -1., 0.)),
+            Direction::new(0., 1., 0.)
+        );
+
+        // NOTE: through the hole along the axis, nothing is hit
+        let axis = Ray::new(Position::new(0., 0., 5.), Direction::new(0., 0., -1.));
+        assert!(torus.hit_intervals(&axis).is_empty());
+
+        // NOTE: CSG sees both spans, the part of the torus in x > 0 is only the second one
+        let half = Csg::intersection(
+            Torus::new(Position::new(0., 0., 0.), 2., 1., Material::default()),
+            AABBox::try_build(
+                Position::new(0., -5., -5.),
+                Position::new(5., 5., 5.),
+                Material::default(),
+            )
+            .unwrap(),
+        );
+        let spans = half.hit_intervals(&ray);
+        assert_eq!(spans.len(), 1);
+        assert_abs_diff_eq!(spans[0].0, 6., epsilon = 1e-9);
+    }
+
     #[test]
     fn test_torus_norm_vector() {
         let torus = Torus {
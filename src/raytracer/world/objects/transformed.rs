@@ -0,0 +1,208 @@
+use nalgebra::{Matrix4, Vector3};
+use rand::RngCore;
+
+use std::borrow::Cow;
+
+use super::{Material, Ray, Visible};
+use crate::raytracer::{Direction, Interval, Position};
+
+/// Place an object in the world with translation, rotation and scale
+///
+/// The transforms apply in the order they are chained,
+/// e.g. `Transformed::new(obj).rotate_y(45.).translate(1., 0., 0.)` rotates first
+pub struct Transformed<V> {
+    inner: V,
+    // NOTE: object space to world space, and back
+    matrix: Matrix4<f64>,
+    inverse: Matrix4<f64>,
+}
+
+impl<V: Visible> Transformed<V> {
+    pub fn new(inner: V) -> Self {
+        Self {
+            inner,
+            matrix: Matrix4::identity(),
+            inverse: Matrix4::identity(),
+        }
+    }
+
+    /// Apply `m` after the current transform, `m_inv` is its inverse
+    fn then(mut self, m: Matrix4<f64>, m_inv: Matrix4<f64>) -> Self {
+        self.matrix = m * self.matrix;
+        self.inverse *= m_inv;
+        self
+    }
+
+    pub fn translate(self, x: f64, y: f64, z: f64) -> Self {
+        let v = Vector3::new(x, y, z);
+        self.then(Matrix4::new_translation(&v), Matrix4::new_translation(&-v))
+    }
+
+    pub fn scale(self, x: f64, y: f64, z: f64) -> Self {
+        self.then(
+            Matrix4::new_nonuniform_scaling(&Vector3::new(x, y, z)),
+            Matrix4::new_nonuniform_scaling(&Vector3::new(1. / x, 1. / y, 1. / z)),
+        )
+    }
+
+    /// Rotate by `angle` degrees around `axis`, counterclockwise looking from its tip
+    fn rotate(self, axis: Vector3<f64>, angle: f64) -> Self {
+        let angle = angle.to_radians();
+        self.then(
+            Matrix4::new_rotation(axis * angle),
+            Matrix4::new_rotation(axis * -angle),
+        )
+    }
+
+    pub fn rotate_x(self, angle: f64) -> Self {
+        self.rotate(Vector3::x(), angle)
+    }
+
+    pub fn rotate_y(self, angle: f64) -> Self {
+        self.rotate(Vector3::y(), angle)
+    }
+
+    pub fn rotate_z(self, angle: f64) -> Self {
+        self.rotate(Vector3::z(), angle)
+    }
+
+    fn to_local(&self, pos: &Position) -> Position {
+        Position::from(self.inverse.transform_point(&(*pos.as_ref()).into()).coords)
+    }
+
+    fn to_world_norm(&self, norm: &Direction) -> Direction {
+        // NOTE: normals go back with the inverse transpose, to stay orthogonal to the surface
+        Direction::from(self.inverse.transpose().transform_vector(norm.as_ref()))
+    }
+
+    /// The ray in object space, and how much longer distances are there
+    fn local_ray(&self, ray: &Ray) -> (Ray, f64) {
+        let dir = self.inverse.transform_vector(ray.dir.as_ref());
+        // NOTE: the local direction is normalized again, scaling distances by its length
+        let scale = dir.norm();
+
+        (
+            Ray::new(self.to_local(&ray.position), Direction::from(dir)),
+            scale,
+        )
+    }
+}
+
+impl<V: Visible> Visible for Transformed<V> {
+    fn hit_by_ray(&self, ray: &Ray, interval: &Interval) -> Option<f64> {
+        self.hit_part_by_ray(ray, interval).map(|(t, _)| t)
+    }
+
+    fn hit_part_by_ray(&self, ray: &Ray, interval: &Interval) -> Option<(f64, usize)> {
+        let (local_ray, scale) = self.local_ray(ray);
+        let local_interval = Interval::new(interval.min * scale, interval.max * scale);
+
+        self.inner
+            .hit_part_by_ray(&local_ray, &local_interval)
+            .map(|(t, part)| (t / scale, part))
+    }
+
+    fn hit_part_by_ray_with_rng(
+        &self,
+        ray: &Ray,
+        interval: &Interval,
+        rng: &mut dyn RngCore,
+    ) -> Option<(f64, usize)> {
+        let (local_ray, scale) = self.local_ray(ray);
+        let local_interval = Interval::new(interval.min * scale, interval.max * scale);
+
+        self.inner
+            .hit_part_by_ray_with_rng(&local_ray, &local_interval, rng)
+            .map(|(t, part)| (t / scale, part))
+    }
+
+    fn material_of(&self, pos: &Position) -> Cow<'_, Material> {
+        self.inner.material_of(&self.to_local(pos))
+    }
+
+    fn surface_norm(&self, pos: &Position) -> Direction {
+        let norm = self.inner.surface_norm(&self.to_local(pos));
+        self.to_world_norm(&norm)
+    }
+
+    fn surface_norm_on_part(&self, pos: &Position, part: usize) -> Direction {
+        let norm = self.inner.surface_norm_on_part(&self.to_local(pos), part);
+        self.to_world_norm(&norm)
+    }
+
+    fn hit_intervals(&self, ray: &Ray) -> Vec<(f64, f64)> {
+        let (local_ray, scale) = self.local_ray(ray);
+
+        self.inner
+            .hit_intervals(&local_ray)
+            .into_iter()
+            .map(|(enter, exit)| (enter / scale, exit / scale))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raytracer::world::objects::{AABBox, Sphere};
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_rotated_box() {
+        let unit_box = || {
+            AABBox::try_build(
+                Position::new(-1., -1., -1.),
+                Position::new(1., 1., 1.),
+                Material::default(),
+            )
+            .unwrap()
+        };
+        let rotated = Transformed::new(unit_box())
+            .rotate_y(45.)
+            .translate(0., 0., -5.);
+
+        // NOTE: the corner of the rotated box sticks out to x = sqrt(2)
+        let ray = Ray::new(Position::new(1.3, 0., 0.), Direction::new(0., 0., -1.));
+        let axis_aligned = Transformed::new(unit_box()).translate(0., 0., -5.);
+        assert!(axis_aligned.hit_by_ray(&ray, &Interval::POSITIVE).is_none());
+        let t = rotated.hit_by_ray(&ray, &Interval::POSITIVE).unwrap();
+        assert_abs_diff_eq!(t, 5. - (2f64.sqrt() - 1.3), epsilon = 1e-9);
+
+        // NOTE: the face seen is rotated by 45 degrees too
+        assert_abs_diff_eq!(
+            rotated.surface_norm(&ray.at(t)),
+            Direction::new(1., 0., 1.),
+            epsilon = 1e-9
+        );
+    }
+
+    #[test]
+    fn test_scaled_sphere() {
+        let ellipsoid = Transformed::new(Sphere::new(
+            Position::new(0., 0., 0.),
+            1.,
+            Material::default(),
+        ))
+        .scale(2., 1., 1.)
+        .translate(0., 0., -10.);
+
+        // NOTE: distances are in world space, stretched along x
+        let along_x = Ray::new(Position::new(-5., 0., -10.), Direction::new(1., 0., 0.));
+        let t = ellipsoid.hit_by_ray(&along_x, &Interval::POSITIVE).unwrap();
+        assert_abs_diff_eq!(t, 3., epsilon = 1e-9);
+
+        let along_z = Ray::new(Position::new(0., 0., 0.), Direction::new(0., 0., -1.));
+        let t = ellipsoid.hit_by_ray(&along_z, &Interval::POSITIVE).unwrap();
+        assert_abs_diff_eq!(t, 9., epsilon = 1e-9);
+
+        // NOTE: the interval is in world space as well
+        assert!(ellipsoid
+            .hit_by_ray(&along_x, &Interval::new(0., 2.9))
+            .is_none());
+
+        let pos = Position::new(2f64.sqrt(), 2f64.sqrt() / 2., -10.);
+        let norm = ellipsoid.surface_norm(&pos);
+        assert_abs_diff_eq!(norm.as_ref().norm(), 1., epsilon = 1e-9);
+        assert_abs_diff_eq!(norm, Direction::new(1., 2., 0.), epsilon = 1e-9);
+    }
+}
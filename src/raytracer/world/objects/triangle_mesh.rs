@@ -0,0 +1,292 @@
+use std::borrow::Cow;
+
+use super::{Material, Ray, Visible};
+use crate::raytracer::{Direction, Interval, Position, EPSILON};
+use crate::tiny_render::lesson_03_apply_texture::Model;
+
+/// Triangles sharing one material, faces index into `vertices`
+///
+/// Faces with vertex normals are shaded smoothly, the others are flat
+pub struct TriangleMesh {
+    vertices: Vec<Position>,
+    faces: Vec<[usize; 3]>,
+    normals: Vec<Direction>,
+    // NOTE: indices into `normals`, one entry per face
+    face_normals: Vec<Option<[usize; 3]>>,
+    material: Material,
+}
+
+impl TriangleMesh {
+    pub fn new(vertices: Vec<Position>, faces: Vec<[usize; 3]>, material: Material) -> Self {
+        let face_normals = vec![None; faces.len()];
+
+        Self {
+            vertices,
+            faces,
+            normals: Vec::new(),
+            face_normals,
+            material,
+        }
+    }
+
+    /// Attach vertex normals, `face_normals[i]` indexes the normals of the three corners of face `i`
+    pub fn with_normals(
+        mut self,
+        normals: Vec<Direction>,
+        face_normals: Vec<Option<[usize; 3]>>,
+    ) -> Self {
+        assert_eq!(face_normals.len(), self.faces.len());
+        self.normals = normals;
+        self.face_normals = face_normals;
+        self
+    }
+
+    /// Build the mesh from an OBJ model parsed by the rasterizer
+    pub fn from_model(model: &Model, material: Material) -> Self {
+        let vertices = model.vertices.iter().map(|v| Position::from(*v)).collect();
+        let faces = model
+            .faces
+            .iter()
+            .map(|f| {
+                let idx = f.vertex_idx();
+                [idx.x, idx.y, idx.z]
+            })
+            .collect();
+
+        let normals = model.normals.iter().map(|n| Direction::from(*n)).collect();
+        let face_normals = model
+            .faces
+            .iter()
+            .map(|f| f.normal_idx().map(|idx| [idx.x, idx.y, idx.z]))
+            .collect();
+
+        Self::new(vertices, faces, material).with_normals(normals, face_normals)
+    }
+
+    fn triangle(&self, face: &[usize; 3]) -> [&Position; 3] {
+        face.map(|i| &self.vertices[i])
+    }
+
+    /// Möller–Trumbore, return the distance along the ray to the triangle
+    fn hit_triangle(ray: &Ray, [a, b, c]: [&Position; 3]) -> Option<f64> {
+        let e1 = b.as_ref() - a.as_ref();
+        let e2 = c.as_ref() - a.as_ref();
+        let p = ray.dir.as_ref().cross(&e2);
+        let det = e1.dot(&p);
+
+        // NOTE: the ray is parallel to the triangle
+        if det.abs() < EPSILON * EPSILON {
+            return None;
+        }
+
+        let inv_det = 1. / det;
+        let s = ray.position.as_ref() - a.as_ref();
+        let u = s.dot(&p) * inv_det;
+        if !(0. ..=1.).contains(&u) {
+            return None;
+        }
+
+        let q = s.cross(&e1);
+        let v = ray.dir.as_ref().dot(&q) * inv_det;
+        if v < 0. || u + v > 1. {
+            return None;
+        }
+
+        Some(e2.dot(&q) * inv_det)
+    }
+
+    fn geometric_norm([a, b, c]: [&Position; 3]) -> Direction {
+        let e1 = b.as_ref() - a.as_ref();
+        let e2 = c.as_ref() - a.as_ref();
+        Direction::from(e1.cross(&e2))
+    }
+
+    /// Barycentric coordinates `(u, v)` of the projection of `pos` on face `i`, `None` if degenerate
+    fn barycentric(&self, i: usize, pos: &Position) -> Option<(f64, f64)> {
+        let [a, b, c] = self.triangle(&self.faces[i]);
+        let e1 = b.as_ref() - a.as_ref();
+        let e2 = c.as_ref() - a.as_ref();
+        let n = e1.cross(&e2);
+        let area2 = n.magnitude_squared();
+        if area2 < EPSILON * EPSILON {
+            return None;
+        }
+
+        let ap = pos.as_ref() - a.as_ref();
+        let u = e2.cross(&ap).dot(&n) / -area2;
+        let v = e1.cross(&ap).dot(&n) / area2;
+
+        Some((u, v))
+    }
+
+    /// Find the face `pos` lies on, the closest one to its plane when several qualify
+    ///
+    /// Only for positions that don't come from `hit_part_by_ray`, it checks every face
+    fn face_at(&self, pos: &Position) -> Option<usize> {
+        let tolerance = 1e-6;
+
+        self.faces
+            .iter()
+            .enumerate()
+            .filter_map(|(i, face)| {
+                let (u, v) = self.barycentric(i, pos)?;
+                let inside = u >= -tolerance && v >= -tolerance && u + v <= 1. + tolerance;
+
+                let corners = self.triangle(face);
+                let ap = pos.as_ref() - corners[0].as_ref();
+                let dist = Self::geometric_norm(corners).as_ref().dot(&ap).abs();
+
+                inside.then_some((i, dist))
+            })
+            .min_by(|(_, d1), (_, d2)| d1.total_cmp(d2))
+            .map(|(i, _)| i)
+    }
+}
+
+impl Visible for TriangleMesh {
+    fn hit_by_ray(&self, ray: &Ray, interval: &Interval) -> Option<f64> {
+        self.hit_part_by_ray(ray, interval).map(|(t, _)| t)
+    }
+
+    /// The part is the index of the face which is hit
+    fn hit_part_by_ray(&self, ray: &Ray, interval: &Interval) -> Option<(f64, usize)> {
+        self.faces
+            .iter()
+            .enumerate()
+            .filter_map(|(i, face)| Self::hit_triangle(ray, self.triangle(face)).map(|t| (t, i)))
+            .filter(|(t, _)| interval.contains(*t))
+            .min_by(|(t1, _), (t2, _)| t1.total_cmp(t2))
+    }
+
+    fn material_of(&self, _pos: &Position) -> Cow<'_, Material> {
+        Cow::Borrowed(&self.material)
+    }
+
+    fn surface_norm(&self, pos: &Position) -> Direction {
+        match self.face_at(pos) {
+            Some(i) => self.surface_norm_on_part(pos, i),
+            None => Direction::new(0., 0., 1.),
+        }
+    }
+
+    fn surface_norm_on_part(&self, pos: &Position, i: usize) -> Direction {
+        // NOTE: interpolate vertex normals, P = (1 - u - v)A + uB + vC
+        match (self.face_normals[i], self.barycentric(i, pos)) {
+            (Some([a, b, c]), Some((u, v))) => {
+                let n = (1. - u - v) * self.normals[a].as_ref()
+                    + u * self.normals[b].as_ref()
+                    + v * self.normals[c].as_ref();
+                Direction::from(n)
+            }
+            // NOTE: faces are counterclockwise seen from outside, as in OBJ files
+            _ => Self::geometric_norm(self.triangle(&self.faces[i])),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_single_triangle() {
+        let mesh = TriangleMesh::new(
+            vec![
+                Position::new(0., 0., 0.),
+                Position::new(1., 0., 0.),
+                Position::new(0., 1., 0.),
+            ],
+            vec![[0, 1, 2]],
+            Material::default(),
+        );
+
+        let ray = Ray::new(Position::new(0.2, 0.2, 5.), Direction::new(0., 0., -1.));
+        let t = mesh.hit_by_ray(&ray, &Interval::POSITIVE).unwrap();
+        assert_abs_diff_eq!(t, 5., epsilon = EPSILON);
+        assert_abs_diff_eq!(mesh.surface_norm(&ray.at(t)), Direction::new(0., 0., 1.));
+
+        let miss = Ray::new(Position::new(0.8, 0.8, 5.), Direction::new(0., 0., -1.));
+        assert!(mesh.hit_by_ray(&miss, &Interval::POSITIVE).is_none());
+
+        let behind = Ray::new(Position::new(0.2, 0.2, -5.), Direction::new(0., 0., -1.));
+        assert!(mesh.hit_by_ray(&behind, &Interval::POSITIVE).is_none());
+    }
+
+    #[test]
+    fn test_head_mesh_hit() {
+        let model = Model::default().load_model("obj/head.obj").unwrap();
+        let mesh = TriangleMesh::from_model(&model, Material::default());
+
+        // NOTE: the head faces +z, a ray from the front hits the face
+        let ray = Ray::new(Position::new(0., 0., 5.), Direction::new(0., 0., -1.));
+        let t = mesh.hit_by_ray(&ray, &Interval::POSITIVE).unwrap();
+        let hit = ray.at(t);
+
+        assert!(t > 3. && t < 5., "{t}");
+        assert!(mesh.surface_norm(&hit).as_ref().z > 0.);
+    }
+
+    #[test]
+    fn test_normal_of_the_hit_face() {
+        let model = Model::default().load_model("obj/head.obj").unwrap();
+        let mesh = TriangleMesh::from_model(&model, Material::default());
+
+        for (x, y) in [(0., 0.), (0.3, 0.2), (-0.4, -0.1), (0.1, 0.5)] {
+            let ray = Ray::new(Position::new(x, y, 5.), Direction::new(0., 0., -1.));
+            let (t, face) = mesh.hit_part_by_ray(&ray, &Interval::POSITIVE).unwrap();
+            let hit = ray.at(t);
+
+            assert_eq!(mesh.hit_by_ray(&ray, &Interval::POSITIVE), Some(t));
+            assert!(TriangleMesh::hit_triangle(&ray, mesh.triangle(&mesh.faces[face])).is_some());
+            assert_abs_diff_eq!(
+                mesh.surface_norm_on_part(&hit, face),
+                mesh.surface_norm(&hit),
+                epsilon = 1e-9
+            );
+        }
+    }
+
+    #[test]
+    fn test_smooth_normals() {
+        let vertices = vec![
+            Position::new(0., 0., 0.),
+            Position::new(1., 0., 0.),
+            Position::new(0., 1., 0.),
+        ];
+        let tilted = Direction::new(1., 1., 1.);
+        let flat = TriangleMesh::new(vertices.clone(), vec![[0, 1, 2]], Material::default())
+            .with_normals(vec![tilted], vec![Some([0, 0, 0])]);
+
+        // NOTE: identical vertex normals, the interpolated normal is the same everywhere
+        for (x, y) in [
+            (0., 0.),
+            (1., 0.),
+            (0., 1.),
+            (0.25, 0.25),
+            (0.5, 0.4),
+            (0.1, 0.7),
+        ] {
+            assert_abs_diff_eq!(flat.surface_norm(&Position::new(x, y, 0.)), tilted);
+        }
+
+        // NOTE: distinct vertex normals, each corner gets its own and the center the average
+        let normals = vec![
+            Direction::new(0., 0., 1.),
+            Direction::new(1., 0., 1.),
+            Direction::new(0., 1., 1.),
+        ];
+        let smooth = TriangleMesh::new(vertices, vec![[0, 1, 2]], Material::default())
+            .with_normals(normals.clone(), vec![Some([0, 1, 2])]);
+
+        assert_abs_diff_eq!(smooth.surface_norm(&Position::new(1., 0., 0.)), normals[1]);
+        assert_abs_diff_eq!(smooth.surface_norm(&Position::new(0., 1., 0.)), normals[2]);
+        let center = normals
+            .iter()
+            .fold(nalgebra::Vector3::zeros(), |acc, n| acc + n.as_ref());
+        assert_abs_diff_eq!(
+            smooth.surface_norm(&Position::new(1. / 3., 1. / 3., 0.)),
+            Direction::from(center)
+        );
+    }
+}
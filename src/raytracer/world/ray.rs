@@ -1,4 +1,5 @@
 use std::borrow::Cow;
+use std::cell::OnceCell;
 
 use super::objects::Material;
 use crate::raytracer::{world::Visible, Direction, Position};
@@ -7,23 +8,67 @@ pub struct HitPoint<'a> {
     pub obj: &'a dyn Visible,
     pub position: Position,
     pub is_outside: bool,
+    /// Distance along the incoming ray to the hit
+    pub t: f64,
+    /// Direction of the incoming ray
+    pub incident: Direction,
+    /// Part of the object which is hit, see `Visible::hit_part_by_ray`
+    pub part: usize,
+    /// Looked up on first use, textured objects build a new material on every lookup
+    material: OnceCell<Cow<'a, Material>>,
 }
 
 impl<'a> HitPoint<'a> {
+    /// A hit at `position` without the ray which found it
+    ///
+    /// `t` is 0 and `incident` goes straight into the surface, use `from_ray` when the ray is known
     pub fn new(object: &'a dyn Visible, position: Position, is_outside: bool) -> Self {
+        let mut hit = Self::on(object, position, is_outside, 0., Direction::new(0., 0., 1.));
+        hit.incident = hit.norm().reverse();
+        hit
+    }
+
+    /// The hit of `ray` on `object`, `t` along the ray
+    pub fn from_ray(object: &'a dyn Visible, ray: &Ray, t: f64, is_outside: bool) -> Self {
+        Self::on(object, ray.at(t), is_outside, t, ray.dir)
+    }
+
+    fn on(
+        obj: &'a dyn Visible,
+        position: Position,
+        is_outside: bool,
+        t: f64,
+        incident: Direction,
+    ) -> Self {
         Self {
-            obj: object,
+            obj,
             position,
             is_outside,
+            t,
+            incident,
+            part: 0,
+            material: OnceCell::new(),
         }
     }
 
-    pub fn surface_material(&self) -> Cow<'_, Material> {
-        self.obj.material_of(&self.position)
+    /// The material of the object at the hit, `material_of` is called at most once per hit
+    pub fn surface_material(&self) -> &Material {
+        self.material
+            .get_or_init(|| self.obj.material_of(&self.position))
+    }
+
+    /// Refractive indices `(n1, n2)` of the medium the ray comes from and the one it goes into
+    pub fn refractive_indices(&self) -> (f64, f64) {
+        let n = self.surface_material().refractive_index;
+        if self.is_outside {
+            (1., n)
+        } else {
+            (n, 1.)
+        }
     }
 
     pub fn norm(&self) -> Direction {
-        let norm = self.obj.surface_norm(&self.position);
+        let norm = self.obj.surface_norm_on_part(&self.position, self.part);
         if self.is_outside {
             norm
         } else {
@@ -54,29 +99,31 @@ impl Ray {
         Self::new(hit_point.position, self.dir.reflection(&N))
     }
 
+    /// Fall back to the reflected ray on total internal reflection
     #[allow(non_snake_case)]
     pub fn refracted(&self, hit_point: &HitPoint) -> Self {
         let N = hit_point.norm();
-        let mut n1 = 1.;
-        let mut n2 = hit_point.surface_material().refractive_index;
+        let (n1, n2) = hit_point.refractive_indices();
 
-        if !hit_point.is_outside {
-            std::mem::swap(&mut n1, &mut n2);
-        };
-
-        Self::new(hit_point.position, self.dir.refraction(&N, n1, n2))
+        match self.dir.refraction(&N, n1, n2) {
+            Some(dir) => Self::new(hit_point.position, dir),
+            None => self.reflected(hit_point),
+        }
     }
 
-    pub fn shadowed(hit_point: &HitPoint, light_pos: &Position) -> Self {
-        let to_light = Direction::a_to_b(&hit_point.position, light_pos);
+    pub fn shadowed(hit_point: &HitPoint, to_light: &Direction) -> Self {
         // WARN: I change the move direction to to_light, previous I use N
-        Self::new(hit_point.position, to_light)
+        Self::new(hit_point.position, *to_light)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::raytracer::{
+        world::objects::{Material, Sphere},
+        Albedo,
+    };
     use approx::assert_abs_diff_eq;
 
     #[test]
@@ -87,4 +134,53 @@ mod tests {
         let ray = Ray::new(Position::new(1., 1., 1.), Direction::new(1., 1., 1.));
         assert_abs_diff_eq!(ray.at(3f64.sqrt()), Position::new(2., 2., 2.));
     }
+
+    #[test]
+    fn test_hit_point_on_ray() {
+        let sphere = Sphere::new(Position::new(0., 0., 0.), 1., Material::default());
+        let ray = Ray::new(Position::new(0., 0., 5.), Direction::new(0., 0., -1.));
+        let hit = HitPoint::from_ray(&sphere, &ray, 4., true);
+
+        assert_abs_diff_eq!(hit.position, Position::new(0., 0., 1.));
+        assert_abs_diff_eq!(hit.t, 4.);
+        assert_abs_diff_eq!(hit.incident, ray.dir);
+
+        // NOTE: without the ray, the hit is taken head-on
+        let hit = HitPoint::new(&sphere, Position::new(0., 0., 1.), true);
+        assert_abs_diff_eq!(hit.t, 0.);
+        assert_abs_diff_eq!(hit.incident, Direction::new(0., 0., -1.));
+        let hit = HitPoint::new(&sphere, Position::new(0., 0., 1.), false);
+        assert_abs_diff_eq!(hit.incident, Direction::new(0., 0., 1.));
+    }
+
+    #[test]
+    fn test_refracted_falls_back_to_reflected() {
+        let glass = Material {
+            albedo: Albedo::new(0., 0., 0., 1.),
+            refractive_index: 1.5,
+            ..Material::default()
+        };
+        let sphere = Sphere::new(Position::new(0., 0., 0.), 1., glass);
+        let hit = HitPoint::new(&sphere, Position::new(0., 1., 0.), false);
+
+        // NOTE: steep ray inside glass, 70 degrees from the normal
+        let theta = 70f64.to_radians();
+        let ray = Ray::new(
+            Position::new(0., 0., 0.),
+            Direction::new(theta.sin(), theta.cos(), 0.),
+        );
+        assert!(ray.dir.refraction(&hit.norm(), 1.5, 1.).is_none());
+
+        let refracted = ray.refracted(&hit);
+        assert_abs_diff_eq!(refracted.dir, ray.reflected(&hit).dir);
+        assert!(refracted.dir.as_ref().y < 0.);
+
+        // NOTE: a gentle ray gets out of the glass
+        let theta = 20f64.to_radians();
+        let ray = Ray::new(
+            Position::new(0., 0., 0.),
+            Direction::new(theta.sin(), theta.cos(), 0.),
+        );
+        assert!(ray.refracted(&hit).dir.as_ref().y > 0.);
+    }
 }
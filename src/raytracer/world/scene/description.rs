@@ -0,0 +1,599 @@
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use super::{Lambertian, RayCastStrategy, Scene};
+use crate::raytracer::{
+    camera::{Camera, CameraBuilder},
+    world::{
+        background::{Background, DummyBackground, Sky},
+        objects::{AABBox, GradientSphere, Light, Material, Plane, Sphere},
+    },
+    Albedo, Color, Direction, Position,
+};
+
+/// A scene written in TOML or JSON, so it can be tweaked without recompiling
+///
+/// Materials can be declared once under `[materials.<name>]` and referred to by name,
+/// or written inline in an object
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SceneDescription {
+    #[serde(default)]
+    camera: CameraDescription,
+    #[serde(default)]
+    background: BackgroundDescription,
+    #[serde(default)]
+    materials: HashMap<String, MaterialDescription>,
+    #[serde(default)]
+    lights: Vec<LightDescription>,
+    #[serde(default)]
+    objects: Vec<ObjectDescription>,
+}
+
+// NOTE: fields left out of `[camera]` keep their `Default` value
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct CameraDescription {
+    position: [f64; 3],
+    forward: [f64; 3],
+    fov: f64,
+    film_distance: f64,
+    antialiasing: bool,
+    samples_per_pixel: Option<usize>,
+}
+
+impl Default for CameraDescription {
+    fn default() -> Self {
+        Self {
+            position: [0., 0., 0.],
+            forward: [0., 0., -1.],
+            fov: 90.,
+            film_distance: 1.,
+            antialiasing: false,
+            samples_per_pixel: None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum BackgroundDescription {
+    #[default]
+    None,
+    Dummy,
+    Sky,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MaterialDescription {
+    diffuse_color: [f64; 3],
+    albedo: [f64; 4],
+    specular_exponent: f64,
+    refractive_index: f64,
+    #[serde(default)]
+    emission: [f64; 3],
+    #[serde(default)]
+    fuzz: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum MaterialRef {
+    Named(String),
+    Inline(MaterialDescription),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum LightDescription {
+    Point {
+        position: [f64; 3],
+        intensity: f64,
+        #[serde(default)]
+        attenuation: f64,
+        color: Option<[f64; 3]>,
+    },
+    Directional {
+        direction: [f64; 3],
+        intensity: f64,
+        color: Option<[f64; 3]>,
+    },
+    Spot {
+        position: [f64; 3],
+        direction: [f64; 3],
+        inner_angle: f64,
+        outer_angle: f64,
+        intensity: f64,
+        color: Option<[f64; 3]>,
+    },
+    Sphere {
+        position: [f64; 3],
+        radius: f64,
+        intensity: f64,
+        color: Option<[f64; 3]>,
+    },
+    Ambient {
+        intensity: f64,
+        color: Option<[f64; 3]>,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum ObjectDescription {
+    Sphere {
+        center: [f64; 3],
+        radius: f64,
+        material: MaterialRef,
+    },
+    GradientSphere {
+        center: [f64; 3],
+        radius: f64,
+    },
+    #[serde(rename = "aabbox", alias = "box")]
+    AABBox {
+        low: [f64; 3],
+        high: [f64; 3],
+        material: MaterialRef,
+    },
+    Plane {
+        point: [f64; 3],
+        normal: [f64; 3],
+        material: MaterialRef,
+    },
+}
+
+fn position([x, y, z]: [f64; 3]) -> Position {
+    Position::new(x, y, z)
+}
+
+fn direction([x, y, z]: [f64; 3]) -> Direction {
+    Direction::new(x, y, z)
+}
+
+fn color([r, g, b]: [f64; 3]) -> Color {
+    Color::new(r, g, b)
+}
+
+impl From<&MaterialDescription> for Material {
+    fn from(desc: &MaterialDescription) -> Self {
+        let [d, s, rl, rr] = desc.albedo;
+
+        Material {
+            diffuse_color: color(desc.diffuse_color),
+            albedo: Albedo::new(d, s, rl, rr),
+            specular_exponent: desc.specular_exponent,
+            refractive_index: desc.refractive_index,
+            emission: color(desc.emission),
+            fuzz: desc.fuzz,
+            ..Material::default()
+        }
+    }
+}
+
+impl From<&LightDescription> for Light {
+    fn from(desc: &LightDescription) -> Self {
+        let (light, c) = match *desc {
+            LightDescription::Point {
+                position: p,
+                intensity,
+                attenuation,
+                color,
+            } => (
+                Light::with_attenuation(position(p), intensity, attenuation),
+                color,
+            ),
+            LightDescription::Directional {
+                direction: d,
+                intensity,
+                color,
+            } => (Light::directional(direction(d), intensity), color),
+            LightDescription::Spot {
+                position: p,
+                direction: d,
+                inner_angle,
+                outer_angle,
+                intensity,
+                color,
+            } => (
+                Light::spot(
+                    position(p),
+                    direction(d),
+                    inner_angle,
+                    outer_angle,
+                    intensity,
+                ),
+                color,
+            ),
+            LightDescription::Sphere {
+                position: p,
+                radius,
+                intensity,
+                color,
+            } => (Light::sphere(position(p), radius, intensity), color),
+            LightDescription::Ambient { intensity, color } => (Light::ambient(intensity), color),
+        };
+
+        match c {
+            Some(c) => light.with_color(color(c)),
+            None => light,
+        }
+    }
+}
+
+impl SceneDescription {
+    pub fn parse(text: &str) -> Result<Self> {
+        Ok(toml::from_str(text)?)
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read scene file {}", path.display()))?;
+
+        Self::parse(&text).with_context(|| format!("invalid scene file {}", path.display()))
+    }
+
+    pub fn parse_json(text: &str) -> Result<Self> {
+        Ok(serde_json::from_str(text)?)
+    }
+
+    pub fn from_json_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read scene file {}", path.display()))?;
+
+        Self::parse_json(&text).with_context(|| format!("invalid scene file {}", path.display()))
+    }
+
+    fn material(&self, material: &MaterialRef) -> Result<Material> {
+        match material {
+            MaterialRef::Inline(desc) => Ok(Material::from(desc)),
+            MaterialRef::Named(name) => match self.materials.get(name) {
+                Some(desc) => Ok(Material::from(desc)),
+                None => bail!("unknown material `{name}`"),
+            },
+        }
+    }
+
+    pub fn camera(&self) -> Camera {
+        let desc = &self.camera;
+        let mut builder = CameraBuilder::new();
+        builder
+            .position(position(desc.position))
+            .forward_to(direction(desc.forward))
+            .adjust_fov_in_degree(desc.fov)
+            .adjust_screen(desc.film_distance)
+            .antialiasing(desc.antialiasing);
+
+        if let Some(spp) = desc.samples_per_pixel {
+            builder.samples_per_pixel(spp);
+        }
+
+        builder.build()
+    }
+
+    pub fn scene(&self) -> Result<Scene<Box<dyn Background>>> {
+        self.scene_with(Lambertian)
+    }
+
+    /// The scene, casting rays with `ray_caster` instead of `Lambertian`
+    pub fn scene_with<S: RayCastStrategy>(
+        &self,
+        ray_caster: S,
+    ) -> Result<Scene<Box<dyn Background>, S>> {
+        let mut scene = Scene::new(ray_caster);
+
+        let background: Option<Box<dyn Background>> = match self.background {
+            BackgroundDescription::None => None,
+            BackgroundDescription::Dummy => Some(Box::new(DummyBackground)),
+            BackgroundDescription::Sky => Some(Box::new(Sky)),
+        };
+        if let Some(background) = background {
+            scene = scene.add_background(background);
+        }
+
+        for light in &self.lights {
+            scene = scene.add_light(Light::from(light));
+        }
+
+        for object in &self.objects {
+            scene = match object {
+                ObjectDescription::Sphere {
+                    center,
+                    radius,
+                    material,
+                } => scene.add_object(Sphere::new(
+                    position(*center),
+                    *radius,
+                    self.material(material)?,
+                )),
+                ObjectDescription::GradientSphere { center, radius } => {
+                    scene.add_object(GradientSphere::new(position(*center), *radius))
+                }
+                ObjectDescription::AABBox {
+                    low,
+                    high,
+                    material,
+                } => scene.add_object(AABBox::try_build(
+                    position(*low),
+                    position(*high),
+                    self.material(material)?,
+                )?),
+                ObjectDescription::Plane {
+                    point,
+                    normal,
+                    material,
+                } => scene.add_object(Plane::new(
+                    position(*point),
+                    direction(*normal),
+                    self.material(material)?,
+                )),
+            };
+        }
+
+        Ok(scene)
+    }
+}
+
+impl Scene<Box<dyn Background>> {
+    /// Load the scene and its camera from a TOML scene file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<(Self, Camera)> {
+        let desc = SceneDescription::from_file(path)?;
+
+        Ok((desc.scene()?, desc.camera()))
+    }
+
+    /// Load the scene and its camera from a JSON scene file
+    pub fn from_json<P: AsRef<Path>>(path: P) -> Result<(Self, Camera)> {
+        let desc = SceneDescription::from_json_file(path)?;
+
+        Ok((desc.scene()?, desc.camera()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbImage;
+
+    fn first_scene() -> Scene<DummyBackground> {
+        let ivory = Material::new(
+            Color::new(0.4, 0.4, 0.3),
+            Albedo::new(0.6, 0.3, 0.1, 0.0),
+            50.,
+            1.,
+        );
+        let red_rubber = Material::new(
+            Color::new(0.3, 0.1, 0.1),
+            Albedo::new(0.9, 0.1, 0.0, 0.0),
+            10.,
+            1.,
+        );
+        let glass = Material::new(
+            Color::new(0.6, 0.7, 0.8),
+            Albedo::new(0.0, 0.5, 0.1, 0.8),
+            125.,
+            1.5,
+        );
+        let gold = Material::new(
+            Color::new(0.6, 0.5, 0.3),
+            Albedo::new(0.5, 0.5, 0.1, 0.0),
+            80.,
+            0.8,
+        );
+        let magenta = Material::new(Color::MAGENTA, Albedo::new(0.3, 0.3, 0.1, 0.0), 20., 0.8);
+        let mirror = Material::new(
+            Color::new(0., 0., 0.),
+            Albedo::new(1., 1., 0.87, 0.0),
+            1425.,
+            1.,
+        );
+        let mirror2 = Material::new(
+            Color::new(40. / 255., 40. / 255., 40. / 255.),
+            Albedo::new(1., 0.1, 0.1, 0.0),
+            30.,
+            1.,
+        );
+
+        Scene::default()
+            .add_background(DummyBackground)
+            .add_object(Sphere::new(Position::new(-3., 0., -16.), 2., ivory))
+            .add_object(Sphere::new(Position::new(-1., -1.5, -12.), 2., glass))
+            .add_object(Sphere::new(Position::new(1.5, -0.5, -18.), 3., red_rubber))
+            .add_object(Sphere::new(Position::new(5., 8., -18.), 4., mirror))
+            .add_object(Sphere::new(Position::new(-3., 2.5, -8.), 2., gold))
+            .add_object(GradientSphere::new(Position::new(7., 0.5, -10.), 2.))
+            .add_object(
+                AABBox::try_build(
+                    Position::new(-100., -20., -100.),
+                    Position::new(100., -3.5, 100.),
+                    mirror2,
+                )
+                .unwrap(),
+            )
+            .add_object(
+                AABBox::try_build(
+                    Position::new(4.5, -3.5, -18.),
+                    Position::new(10., -1.5, -8.),
+                    magenta,
+                )
+                .unwrap(),
+            )
+            .add_light(Light::new(Position::new(-20., 20., 20.), 1.5))
+            .add_light(Light::new(Position::new(30., 50., -25.), 1.8))
+            .add_light(Light::new(Position::new(30., 20., 30.), 1.7))
+    }
+
+    #[test]
+    fn test_scene_file_matches_programmatic_scene() {
+        let (scene, camera) = Scene::from_file("scenes/first.toml").unwrap();
+
+        let mut from_file = RgbImage::new(48, 27);
+        let mut expected = RgbImage::new(48, 27);
+        camera.render(&scene, &mut from_file);
+        Camera::default().render(&first_scene(), &mut expected);
+
+        assert_eq!(from_file, expected);
+    }
+
+    #[test]
+    fn test_json_scene_matches_programmatic_scene() {
+        let json = r#"{
+            "camera": {
+                "position": [0.0, 0.0, 0.0],
+                "forward": [0.0, 0.0, -1.0],
+                "fov": 60.0,
+                "film_distance": 1.0,
+                "antialiasing": false
+            },
+            "background": "dummy",
+            "materials": {
+                "ivory": {
+                    "diffuse_color": [0.4, 0.4, 0.3],
+                    "albedo": [0.6, 0.3, 0.1, 0.0],
+                    "specular_exponent": 50.0,
+                    "refractive_index": 1.0
+                }
+            },
+            "lights": [
+                { "type": "point", "position": [-20.0, 20.0, 20.0], "intensity": 1.5 }
+            ],
+            "objects": [
+                { "type": "sphere", "center": [-2.0, 0.0, -10.0], "radius": 1.5, "material": "ivory" },
+                {
+                    "type": "sphere",
+                    "center": [2.0, 0.0, -10.0],
+                    "radius": 1.5,
+                    "material": {
+                        "diffuse_color": [0.3, 0.1, 0.1],
+                        "albedo": [0.9, 0.1, 0.0, 0.0],
+                        "specular_exponent": 10.0,
+                        "refractive_index": 1.0
+                    }
+                }
+            ]
+        }"#;
+        let desc = SceneDescription::parse_json(json).unwrap();
+        let scene = desc.scene().unwrap();
+
+        let ivory = Material::new(
+            Color::new(0.4, 0.4, 0.3),
+            Albedo::new(0.6, 0.3, 0.1, 0.0),
+            50.,
+            1.,
+        );
+        let red_rubber = Material::new(
+            Color::new(0.3, 0.1, 0.1),
+            Albedo::new(0.9, 0.1, 0.0, 0.0),
+            10.,
+            1.,
+        );
+        let expected_scene: Scene<DummyBackground> = Scene::default()
+            .add_background(DummyBackground)
+            .add_object(Sphere::new(Position::new(-2., 0., -10.), 1.5, ivory))
+            .add_object(Sphere::new(Position::new(2., 0., -10.), 1.5, red_rubber))
+            .add_light(Light::new(Position::new(-20., 20., 20.), 1.5));
+
+        let mut from_json = RgbImage::new(48, 27);
+        let mut expected = RgbImage::new(48, 27);
+        desc.camera().render(&scene, &mut from_json);
+        CameraBuilder::new()
+            .adjust_fov_in_degree(60.)
+            .build()
+            .render(&expected_scene, &mut expected);
+
+        assert_eq!(from_json, expected);
+        // NOTE: both spheres are actually in the picture
+        assert_ne!(from_json.get_pixel(12, 13), from_json.get_pixel(0, 0));
+        assert_ne!(from_json.get_pixel(36, 13), from_json.get_pixel(0, 0));
+    }
+
+    #[test]
+    fn test_json_box_and_plane() {
+        let json = r#"{
+            "materials": {
+                "matte": {
+                    "diffuse_color": [0.5, 0.5, 0.5],
+                    "albedo": [1.0, 0.0, 0.0, 0.0],
+                    "specular_exponent": 1.0,
+                    "refractive_index": 1.0
+                }
+            },
+            "objects": [
+                { "type": "box", "low": [-1.0, -1.0, -6.0], "high": [1.0, 1.0, -4.0], "material": "matte" },
+                { "type": "plane", "point": [0.0, -2.0, 0.0], "normal": [0.0, 1.0, 0.0], "material": "matte" }
+            ]
+        }"#;
+        let desc = SceneDescription::parse_json(json).unwrap();
+        assert_eq!(desc.scene().unwrap().scene_data.objects.len(), 2);
+    }
+
+    #[test]
+    fn test_sphere_light() {
+        let toml = r#"
+            [[lights]]
+            type = "sphere"
+            position = [0.0, 10.0, 0.0]
+            radius = 0.5
+            intensity = 2.0
+            color = [1.0, 0.5, 0.5]
+        "#;
+        let scene = SceneDescription::parse(toml).unwrap().scene().unwrap();
+        let light = &scene.scene_data.lights[0];
+
+        assert!(matches!(light, Light::Sphere { radius, .. } if *radius == 0.5));
+        assert_eq!(light.intensity(), 2.);
+        assert_eq!(light.color(), Color::new(1., 0.5, 0.5));
+    }
+
+    #[test]
+    fn test_partial_camera_table() {
+        let toml = r#"
+            [camera]
+            position = [1.0, 2.0, 3.0]
+        "#;
+        let desc = SceneDescription::parse(toml).unwrap();
+        let default = CameraDescription::default();
+
+        assert_eq!(desc.camera.position, [1., 2., 3.]);
+        assert_eq!(desc.camera.forward, default.forward);
+        assert_eq!(desc.camera.fov, default.fov);
+        assert_eq!(desc.camera.film_distance, default.film_distance);
+        assert!(!desc.camera.antialiasing);
+        assert_eq!(desc.camera.samples_per_pixel, None);
+
+        let err = format!(
+            "{:#}",
+            SceneDescription::parse("[camera]\nzoom = 2.0").unwrap_err()
+        );
+        assert!(err.contains("zoom"), "{err}");
+    }
+
+    #[test]
+    fn test_scene_file_errors() {
+        let unknown_object = r#"
+            [[objects]]
+            type = "cone"
+            radius = 1.0
+        "#;
+        let err = format!("{:#}", SceneDescription::parse(unknown_object).unwrap_err());
+        assert!(err.contains("cone"), "{err}");
+
+        let unknown_material = r#"
+            [[objects]]
+            type = "sphere"
+            center = [0.0, 0.0, -5.0]
+            radius = 1.0
+            material = "unobtainium"
+        "#;
+        let desc = SceneDescription::parse(unknown_material).unwrap();
+        let err = desc.scene().err().unwrap().to_string();
+        assert!(err.contains("unobtainium"), "{err}");
+    }
+}
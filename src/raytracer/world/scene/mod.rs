@@ -1,3 +1,6 @@
+use anyhow::{bail, Result};
+use rand::Rng;
+
 use super::{
     background::{Background, DummyBackground},
     objects::{Light, Visible},
@@ -5,48 +8,78 @@ use super::{
 };
 use crate::raytracer::{Color, Interval};
 
+mod description;
 mod ray_cast;
 
-pub use ray_cast::{Lambertian, MonteCarlo, RayCastStrategy};
+pub use description::SceneDescription;
+pub use ray_cast::{
+    AmbientOcclusion, DepthView, Lambertian, MonteCarlo, NormalView, RayCastStrategy,
+};
+
+/// An object of the scene, with the name it was added under if any
+struct SceneObject {
+    name: Option<String>,
+    object: Box<dyn Visible>,
+}
 
 pub struct SceneData<B = DummyBackground> {
     lights: Vec<Light>,
-    objects: Vec<Box<dyn Visible>>,
+    objects: Vec<SceneObject>,
     background: Option<B>,
     view_range: f64,
+    // NOTE: rays leaving a surface ignore hits closer than this, to avoid self-intersection (acne)
+    shadow_bias: f64,
+    // NOTE: points sampled on a sphere light for each shading, other lights are sampled once
+    light_samples: usize,
 }
 
 impl<B: Background> SceneData<B> {
     /// Check if anything in Scene hit by ray
-    pub fn intersect(&self, ray: &Ray) -> Option<HitPoint> {
-        // don't use Option, cause at least one thing will be hit, that is background
-        // background should fill the whole scene
-        let mut min_hit_dist = f64::MAX;
-        let mut ret = None;
-        // TODO: set interval start so there is no need to move ray origin
-        let interval = Interval::new(1e-3, self.view_range);
+    pub fn intersect(&self, ray: &Ray) -> Option<HitPoint<'_>> {
+        self.closest_hit(ray, |obj, interval| obj.hit_part_by_ray(ray, interval))
+    }
+
+    /// `intersect`, objects draw their random choices from `rng`, e.g. where a ray scatters
+    /// in a medium
+    pub fn intersect_with_rng<R: Rng>(&self, ray: &Ray, rng: &mut R) -> Option<HitPoint<'_>> {
+        self.closest_hit(ray, |obj, interval| {
+            obj.hit_part_by_ray_with_rng(ray, interval, rng)
+        })
+    }
 
-        for obj in self.objects.iter() {
-            if let Some(t) = obj.hit_by_ray(ray, &interval) {
-                if t >= min_hit_dist {
-                    continue;
-                }
+    fn closest_hit<F>(&self, ray: &Ray, mut hit_part: F) -> Option<HitPoint<'_>>
+    where
+        F: FnMut(&dyn Visible, &Interval) -> Option<(f64, usize)>,
+    {
+        // NOTE: the closest hit so far bounds the interval, so farther hits are rejected early
+        let mut interval = Interval::new(self.shadow_bias, self.view_range);
+        let mut ret = None;
 
-                min_hit_dist = t;
-                let hit_point = ray.at(t);
-                let is_outside = ray.dir.dot(&obj.surface_norm(&hit_point)) < 0.;
+        for SceneObject { object: obj, .. } in self.objects.iter() {
+            if let Some((t, part)) = hit_part(obj.as_ref(), &interval) {
+                interval.max = t;
+                let is_outside = ray.dir.dot(&obj.surface_norm_on_part(&ray.at(t), part)) < 0.;
 
-                ret = Some(HitPoint::new(obj.as_ref(), hit_point, is_outside));
+                let mut hit = HitPoint::from_ray(obj.as_ref(), ray, t, is_outside);
+                hit.part = part;
+                ret = Some(hit);
             }
         }
 
-        if min_hit_dist > self.view_range {
-            return None;
-        }
-
         ret
     }
 
+    /// Check if anything is hit closer than `max_dist`, e.g. between a point and a light
+    ///
+    /// Stop at the first hit, the closest one doesn't matter
+    pub fn intersect_any(&self, ray: &Ray, max_dist: f64) -> bool {
+        let interval = Interval::new(self.shadow_bias, max_dist.min(self.view_range));
+
+        self.objects
+            .iter()
+            .any(|entry| entry.object.hit_by_ray(ray, &interval).is_some())
+    }
+
     pub fn intersect_background(&self, ray: &Ray) -> Color {
         self.background
             .as_ref()
@@ -54,6 +87,10 @@ impl<B: Background> SceneData<B> {
     }
 }
 
+/// Objects, lights and background, `B` is the type of background and `S` how rays are cast
+///
+/// `Scene::default()` needs both to be known, from a type annotation or the argument of
+/// `add_background`, `Scene::with_background` infers them for a Lambertian scene
 pub struct Scene<B = DummyBackground, S = Lambertian> {
     scene_data: SceneData<B>,
     ray_caster: S,
@@ -61,30 +98,42 @@ pub struct Scene<B = DummyBackground, S = Lambertian> {
 
 impl<B> Default for Scene<B, Lambertian> {
     fn default() -> Self {
-        self::Scene {
-            scene_data: SceneData {
-                lights: Vec::new(),
-                objects: Vec::new(),
-                background: None,
-                view_range: 1000.,
-            },
-            ray_caster: Lambertian,
-        }
+        Self::new(Lambertian)
+    }
+}
+
+impl<B: Background> Scene<B, Lambertian> {
+    /// Empty Lambertian scene with `background`, the types are inferred from it
+    pub fn with_background(background: B) -> Self {
+        Self::default().add_background(background)
     }
 }
 
 impl<B> Default for Scene<B, MonteCarlo> {
     fn default() -> Self {
-        self::Scene {
+        Self::new(MonteCarlo::default())
+    }
+}
+
+impl<B, S> Scene<B, S> {
+    /// Create an empty scene which casts rays with `ray_caster`
+    pub fn new(ray_caster: S) -> Self {
+        Self {
             scene_data: SceneData {
                 lights: Vec::new(),
                 objects: Vec::new(),
                 background: None,
                 view_range: 1000.,
+                shadow_bias: 1e-3,
+                light_samples: 16,
             },
-            ray_caster: MonteCarlo::default(),
+            ray_caster,
         }
     }
+
+    pub fn ray_caster(&self) -> &S {
+        &self.ray_caster
+    }
 }
 
 impl<B, S> Scene<B, S>
@@ -98,7 +147,15 @@ where
     }
 
     pub fn add_object<V: Visible + 'static>(mut self, object: V) -> Self {
-        self.scene_data.objects.push(Box::new(object));
+        self.push_object(object);
+        self
+    }
+
+    /// Add `object` under `name`, to remove or replace it by name later
+    ///
+    /// An object already called `name` is replaced
+    pub fn add_named_object<V: Visible + 'static>(mut self, name: &str, object: V) -> Self {
+        self.push_named_object(name, object);
         self
     }
 
@@ -112,7 +169,414 @@ where
         self
     }
 
+    /// Tune the offset of secondary rays, too small causes shadow acne,
+    /// too large detaches shadows from objects, scale it with the scene
+    pub fn update_shadow_bias(mut self, shadow_bias: f64) -> Self {
+        self.scene_data.shadow_bias = shadow_bias;
+        self
+    }
+
+    /// How many points of a sphere light are sampled for each shading, more makes smoother
+    /// penumbrae, point lights always take one
+    pub fn update_light_samples(mut self, light_samples: usize) -> Self {
+        self.scene_data.light_samples = light_samples.max(1);
+        self
+    }
+
+    /// The objects, to be edited in place, e.g. between the frames of an animation
+    ///
+    /// Objects are added and removed with `push_object` and `remove_object`, so they keep
+    /// their names
+    pub fn objects_mut(&mut self) -> impl Iterator<Item = &mut Box<dyn Visible>> {
+        self.scene_data
+            .objects
+            .iter_mut()
+            .map(|entry| &mut entry.object)
+    }
+
+    /// Add `object` to a scene already built
+    pub fn push_object<V: Visible + 'static>(&mut self, object: V) {
+        self.scene_data.objects.push(SceneObject {
+            name: None,
+            object: Box::new(object),
+        });
+    }
+
+    /// Add `object` under `name` to a scene already built, an object already called `name`
+    /// is replaced
+    pub fn push_named_object<V: Visible + 'static>(&mut self, name: &str, object: V) {
+        let object = Box::new(object);
+        match self.position_of(name) {
+            Some(idx) => self.scene_data.objects[idx].object = object,
+            None => self.scene_data.objects.push(SceneObject {
+                name: Some(name.to_string()),
+                object,
+            }),
+        }
+    }
+
+    /// Names of the named objects, in the order they were added
+    pub fn object_ids(&self) -> impl Iterator<Item = &str> {
+        self.scene_data
+            .objects
+            .iter()
+            .filter_map(|entry| entry.name.as_deref())
+    }
+
+    /// Take the object called `id` out of the scene, `None` if there is no such object
+    pub fn remove_object(&mut self, id: &str) -> Option<Box<dyn Visible>> {
+        let idx = self.position_of(id)?;
+        Some(self.scene_data.objects.remove(idx).object)
+    }
+
+    /// Put `object` in place of the object called `id`, and give back the old one
+    ///
+    /// Fail if there is no object called `id`, `object` is not added then
+    pub fn replace_object<V: Visible + 'static>(
+        &mut self,
+        id: &str,
+        object: V,
+    ) -> Result<Box<dyn Visible>> {
+        let Some(idx) = self.position_of(id) else {
+            bail!("no object called `{id}` in the scene");
+        };
+
+        Ok(std::mem::replace(
+            &mut self.scene_data.objects[idx].object,
+            Box::new(object),
+        ))
+    }
+
+    fn position_of(&self, name: &str) -> Option<usize> {
+        self.scene_data
+            .objects
+            .iter()
+            .position(|entry| entry.name.as_deref() == Some(name))
+    }
+
+    pub fn clear_objects(&mut self) {
+        self.scene_data.objects.clear();
+    }
+
+    pub fn lights_mut(&mut self) -> &mut Vec<Light> {
+        &mut self.scene_data.lights
+    }
+
+    pub fn clear_lights(&mut self) {
+        self.scene_data.lights.clear();
+    }
+
+    /// Color of the background along `ray`, ignoring the objects
+    pub fn background_color(&self, ray: &Ray) -> Color {
+        self.scene_data.intersect_background(ray)
+    }
+
     pub fn cast_ray(&self, ray: &Ray) -> Color {
-        self.ray_caster.cast_ray(&self.scene_data, ray, 0)
+        self.cast_ray_with_rng(ray, &mut rand::rng())
+    }
+
+    /// Cast `ray` drawing every random choice from `rng`, e.g. a seeded one for reproducible renders
+    pub fn cast_ray_with_rng<R: Rng>(&self, ray: &Ray, rng: &mut R) -> Color {
+        self.ray_caster.cast_ray(&self.scene_data, ray, 0, rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raytracer::{
+        world::{
+            background::{DummyBackground, Sky, SolidColor},
+            objects::{Light, Material, Sphere},
+        },
+        Albedo, Direction, Position,
+    };
+    use approx::assert_abs_diff_eq;
+    use std::borrow::Cow;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    /// Count how many times the wrapped object reports a hit, and how many times its material
+    /// is looked up
+    struct Counted<V> {
+        inner: V,
+        hits: Arc<AtomicUsize>,
+        lookups: Arc<AtomicUsize>,
+    }
+
+    impl<V: Visible> Visible for Counted<V> {
+        fn hit_by_ray(&self, ray: &Ray, interval: &Interval) -> Option<f64> {
+            let t = self.inner.hit_by_ray(ray, interval);
+            if t.is_some() {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+            }
+            t
+        }
+
+        fn material_of(&self, pos: &Position) -> Cow<'_, Material> {
+            self.lookups.fetch_add(1, Ordering::Relaxed);
+            self.inner.material_of(pos)
+        }
+
+        fn surface_norm(&self, pos: &Position) -> Direction {
+            self.inner.surface_norm(pos)
+        }
+    }
+
+    #[test]
+    fn test_edit_objects_in_place() {
+        let sphere_at = |z| Sphere::new(Position::new(0., 0., z), 1., Material::default());
+        let mut scene: Scene = Scene::default()
+            .add_named_object("near", sphere_at(-5.))
+            .add_named_object("middle", sphere_at(-10.))
+            .add_object(sphere_at(-15.))
+            .add_light(Light::new(Position::new(0., 10., 0.), 1.));
+        let ray = Ray::new(Position::new(0., 0., 0.), Direction::new(0., 0., -1.));
+
+        let hit = scene.scene_data.intersect(&ray).unwrap();
+        assert_abs_diff_eq!(hit.t, 4., epsilon = 1e-9);
+
+        // NOTE: the closest sphere is gone, the next one is hit instead
+        scene.remove_object("near").unwrap();
+        let hit = scene.scene_data.intersect(&ray).unwrap();
+        assert_abs_diff_eq!(hit.t, 9., epsilon = 1e-9);
+
+        // NOTE: moved out of the way of the ray
+        let old = scene
+            .replace_object(
+                "middle",
+                Sphere::new(Position::new(5., 0., -10.), 1., Material::default()),
+            )
+            .unwrap();
+        assert!(old.hit_by_ray(&ray, &Interval::POSITIVE).is_some());
+        let hit = scene.scene_data.intersect(&ray).unwrap();
+        assert_abs_diff_eq!(hit.t, 14., epsilon = 1e-9);
+
+        // NOTE: every sphere moved back along the ray
+        for obj in scene.objects_mut() {
+            *obj = Box::new(sphere_at(-30.));
+        }
+        let hit = scene.scene_data.intersect(&ray).unwrap();
+        assert_abs_diff_eq!(hit.t, 29., epsilon = 1e-9);
+
+        scene.clear_objects();
+        assert!(scene.scene_data.intersect(&ray).is_none());
+        assert_eq!(scene.lights_mut().len(), 1);
+        scene.clear_lights();
+        assert!(scene.scene_data.lights.is_empty());
+    }
+
+    #[test]
+    fn test_named_objects_keep_their_name() {
+        let sphere_at = |z| Sphere::new(Position::new(0., 0., z), 1., Material::default());
+        let mut scene: Scene = Scene::default()
+            .add_named_object("near", sphere_at(-5.))
+            .add_object(sphere_at(-10.));
+        scene.push_named_object("far", sphere_at(-15.));
+        assert_eq!(scene.object_ids().collect::<Vec<_>>(), ["near", "far"]);
+        let ray = Ray::new(Position::new(0., 0., 0.), Direction::new(0., 0., -1.));
+        let distance = |obj: Box<dyn Visible>| obj.hit_by_ray(&ray, &Interval::POSITIVE).unwrap();
+
+        // NOTE: the objects after a removed one are still found by their name
+        let near = scene.remove_object("near").unwrap();
+        assert_abs_diff_eq!(distance(near), 4., epsilon = 1e-9);
+        assert!(scene.remove_object("near").is_none());
+        let far = scene.replace_object("far", sphere_at(-20.)).unwrap();
+        assert_abs_diff_eq!(distance(far), 14., epsilon = 1e-9);
+        assert!(scene.replace_object("near", sphere_at(-5.)).is_err());
+
+        // NOTE: a name used again replaces the object under it
+        scene.push_named_object("far", sphere_at(-25.));
+        assert_eq!(scene.object_ids().collect::<Vec<_>>(), ["far"]);
+        assert_eq!(scene.scene_data.objects.len(), 2);
+
+        scene.clear_objects();
+        assert_eq!(scene.object_ids().count(), 0);
+    }
+
+    #[test]
+    fn test_hit_norm_flips_inside_sphere() {
+        let scene: Scene<Sky> = Scene::default().add_object(Sphere::new(
+            Position::new(0., 0., 0.),
+            1.,
+            Material::default(),
+        ));
+
+        // NOTE: from inside, the surface normal points away from the ray origin,
+        // the hit normal is flipped to face it
+        let ray = Ray::new(Position::new(0., 0., 0.), Direction::new(0., 0., -1.));
+        let hit = scene.scene_data.intersect(&ray).unwrap();
+        assert!(!hit.is_outside);
+        assert_abs_diff_eq!(hit.position, Position::new(0., 0., -1.));
+        assert_abs_diff_eq!(
+            hit.obj.surface_norm(&hit.position),
+            Direction::new(0., 0., -1.)
+        );
+        assert_abs_diff_eq!(hit.norm(), Direction::new(0., 0., 1.));
+
+        // NOTE: from outside, both normals agree
+        let ray = Ray::new(Position::new(0., 0., 5.), Direction::new(0., 0., -1.));
+        let hit = scene.scene_data.intersect(&ray).unwrap();
+        assert!(hit.is_outside);
+        assert_abs_diff_eq!(hit.position, Position::new(0., 0., 1.));
+        assert_abs_diff_eq!(hit.t, 4.);
+        assert_abs_diff_eq!(hit.incident, ray.dir);
+        assert_abs_diff_eq!(hit.norm(), Direction::new(0., 0., 1.));
+        assert_abs_diff_eq!(hit.norm(), hit.obj.surface_norm(&hit.position));
+    }
+
+    #[test]
+    fn test_scene_smoke() {
+        let scene: Scene<DummyBackground, Lambertian> = Scene::default()
+            .add_background(DummyBackground)
+            .add_object(Sphere::new(
+                Position::new(0., 0., -5.),
+                1.,
+                Material::default(),
+            ))
+            .add_light(Light::new(Position::new(0., 5., 0.), 1.));
+
+        let hit = Ray::new(Position::new(0., 0., 0.), Direction::new(0., 0.2, -1.));
+        let miss = Ray::new(Position::new(0., 0., 0.), Direction::new(0., 1., 0.));
+
+        assert!(scene.cast_ray(&hit).as_ref().x > 0.);
+        assert_abs_diff_eq!(
+            *scene.cast_ray(&miss).as_ref(),
+            *DummyBackground.get_color(&miss).as_ref()
+        );
+    }
+
+    #[test]
+    fn test_no_shadow_acne_far_from_origin() {
+        let far = Position::new(1e5, 2e4, -1e5);
+        let center = |dx: f64| Position::new(far.as_ref().x + dx, far.as_ref().y, far.as_ref().z);
+        let light = Position::new(far.as_ref().x, far.as_ref().y + 1e3, far.as_ref().z + 1e3);
+
+        let scene: Scene<Sky> = Scene::default()
+            .add_object(Sphere::new(center(0.), 10., Material::default()))
+            .add_object(Sphere::new(center(-100.), 10., Material::default()))
+            .add_light(Light::new(light, 1.))
+            .update_view_range(1e6)
+            .update_shadow_bias(1e-2);
+
+        // NOTE: every point of the lit hemisphere must get exactly the lambertian term
+        let mut rng = rand::rng();
+        let mut tested = 0;
+        while tested < 200 {
+            let norm = Direction::new(
+                rng.random_range(-1.0..1.0),
+                rng.random_range(-1.0..1.0),
+                rng.random_range(-1.0..1.0),
+            );
+            let surface = center(0.).move_forward(10., &norm);
+            let to_light = Direction::a_to_b(&surface, &light);
+            if norm.dot(&to_light) < 0.1 {
+                continue;
+            }
+
+            let eye = surface.move_forward(30., &norm);
+            let ray = Ray::new(eye, Direction::a_to_b(&eye, &surface));
+            let color = scene.cast_ray(&ray);
+            assert_abs_diff_eq!(color.as_ref().x, norm.dot(&to_light), epsilon = 1e-6);
+            tested += 1;
+        }
+    }
+
+    #[test]
+    fn test_near_hit_prunes_far_objects() {
+        let near_hits = Arc::new(AtomicUsize::new(0));
+        let far_hits = Arc::new(AtomicUsize::new(0));
+        let near = Sphere::new(Position::new(0., 0., -5.), 1., Material::default());
+        let far = Sphere::new(Position::new(0., 0., -20.), 1., Material::default());
+
+        let scene: Scene<Sky> = Scene::default()
+            .add_object(Counted {
+                inner: near,
+                hits: near_hits.clone(),
+                lookups: Arc::default(),
+            })
+            .add_object(Counted {
+                inner: far,
+                hits: far_hits.clone(),
+                lookups: Arc::default(),
+            });
+
+        let ray = Ray::new(Position::new(0., 0., 0.), Direction::new(0., 0., -1.));
+        let hit = scene.scene_data.intersect(&ray).unwrap();
+        assert_abs_diff_eq!(hit.position, Position::new(0., 0., -4.));
+
+        // NOTE: the far sphere only sees the interval ending at the near hit
+        assert_eq!(near_hits.load(Ordering::Relaxed), 1);
+        assert_eq!(far_hits.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_material_looked_up_once_per_hit() {
+        let lookups = Arc::new(AtomicUsize::new(0));
+        let shiny = Material {
+            albedo: Albedo::new(0.6, 0.4, 0.0, 0.0),
+            ..Material::default()
+        };
+        let sphere = || Counted {
+            inner: Sphere::new(Position::new(0., 0., -5.), 1., shiny.clone()),
+            hits: Arc::default(),
+            lookups: lookups.clone(),
+        };
+        let ray = Ray::new(Position::new(0., 0., 0.), Direction::new(0., 0., -1.));
+
+        // NOTE: every light asks for the specular exponent, the fresnel weights for the albedo
+        let scene: Scene<Sky> = Scene::default()
+            .add_object(sphere())
+            .add_light(Light::new(Position::new(-10., 10., 0.), 1.))
+            .add_light(Light::new(Position::new(0., 10., 0.), 1.))
+            .add_light(Light::new(Position::new(10., 10., 0.), 1.));
+        scene.cast_ray(&ray);
+        assert_eq!(lookups.swap(0, Ordering::Relaxed), 1);
+
+        // NOTE: the bounce off a lone sphere goes to the sky, it's hit once
+        let scene: Scene<Sky, MonteCarlo> = Scene::default().add_object(sphere());
+        scene.cast_ray(&ray);
+        assert_eq!(lookups.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_scene_with_background() {
+        // NOTE: no type annotation needed
+        let scene = Scene::with_background(SolidColor(Color::RED));
+        let ray = Ray::new(Position::new(0., 0., 0.), Direction::new(0., 1., 0.));
+
+        assert_abs_diff_eq!(*scene.cast_ray(&ray).as_ref(), *Color::RED.as_ref());
+    }
+
+    #[test]
+    fn test_intersect_any() {
+        let scene: Scene<Sky> = Scene::default()
+            .add_object(Sphere::new(
+                Position::new(0., 5., 0.),
+                1.,
+                Material::default(),
+            ))
+            .add_object(Sphere::new(
+                Position::new(5., 20., 0.),
+                1.,
+                Material::default(),
+            ));
+        let origin = Position::new(0., 0., 0.);
+
+        // NOTE: the light at (0, 10, 0) is behind the first sphere
+        let up = Ray::new(origin, Direction::new(0., 1., 0.));
+        assert!(scene.scene_data.intersect_any(&up, 10.));
+
+        // NOTE: nothing on the way to a light on the side
+        let side = Ray::new(origin, Direction::new(1., 0., 0.));
+        assert!(!scene.scene_data.intersect_any(&side, 10.));
+
+        // NOTE: the second sphere is on the line, but beyond the light at (2.5, 10, 0)
+        let tilted = Ray::new(origin, Direction::new(1., 4., 0.));
+        let light_dist = Position::new(2.5, 10., 0.).distance_to(&origin);
+        assert!(scene.scene_data.intersect_any(&tilted, f64::INFINITY));
+        assert!(!scene.scene_data.intersect_any(&tilted, light_dist));
     }
 }
@@ -1,68 +1,118 @@
-use crate::raytracer::world::{background::Background, HitPoint, Ray};
+use crate::raytracer::world::{
+    background::Background,
+    objects::{Material, MaterialKind},
+    HitPoint, Ray,
+};
+use nalgebra::Vector3;
 use rand::Rng;
 
-use crate::raytracer::{Color, Direction};
+use std::f64::consts::PI;
+
+use crate::raytracer::{fresnel, Albedo, Color, Direction};
 
 use super::SceneData;
 
 const RECURSION_DEPTH: usize = 5;
 
 pub trait RayCastStrategy: Send + Sync {
-    fn cast_ray<B: Background>(&self, scene: &SceneData<B>, ray: &Ray, depth: usize) -> Color;
+    /// Every random choice is drawn from `rng`, so a seeded one makes the result reproducible
+    fn cast_ray<B: Background, R: Rng>(
+        &self,
+        scene: &SceneData<B>,
+        ray: &Ray,
+        depth: usize,
+        rng: &mut R,
+    ) -> Color;
 }
 
 pub struct Lambertian;
 
 impl Lambertian {
     #[allow(non_snake_case)]
-    fn direct_illumination<B: Background>(
+    fn direct_illumination<B: Background, R: Rng>(
         &self,
         scene_data: &SceneData<B>,
-        ray: &Ray,
         hit_point: &HitPoint,
-    ) -> (f64, f64) {
-        let mut diffuse_light_intensity = 0.;
-        let mut specular_light_intensity = 0.;
-        // BUG: should be surface_norm or norm_of???
+        rng: &mut R,
+    ) -> (Color, Color) {
+        let mut diffuse_light = Color::BLACK;
+        let mut specular_light = Color::BLACK;
         let N = hit_point.norm();
+        let specular_exponent = hit_point.surface_material().specular_exponent;
 
         for light in &scene_data.lights {
-            let to_light = Direction::a_to_b(&hit_point.position, &light.position);
-            let hit_point_to_light_dist = light.position.distance_to(&hit_point.position);
-
-            if !to_light.is_acute_angle(&N) {
+            // NOTE: ambient light has no direction, it can't be shadowed or reflected
+            let Some(to_center) = light.illuminate(&hit_point.position) else {
+                diffuse_light += light.color() * light.intensity();
                 continue;
-            }
+            };
 
-            let shadow_ray = Ray::shadowed(hit_point, &light.position);
+            // NOTE: a sphere light is the average of points on it, the shadow fades where only
+            // some of them are hidden
+            let samples = light.samples(scene_data.light_samples);
+            let weight = 1. / samples as f64;
 
-            if scene_data
-                .intersect(&shadow_ray)
-                .is_some_and(|shadow_hit_point| {
-                    shadow_hit_point.position.distance_to(&shadow_ray.position)
-                        < hit_point_to_light_dist
-                })
-            {
-                continue;
+            for _ in 0..samples {
+                let (to_light, hit_point_to_light_dist) = light
+                    .illuminate_sample(&hit_point.position, rng)
+                    .unwrap_or(to_center);
+
+                let cone_falloff = light.cone_falloff(&to_light);
+                if !to_light.is_acute_angle(&N) || cone_falloff <= 0. {
+                    continue;
+                }
+
+                let shadow_ray = Ray::shadowed(hit_point, &to_light);
+
+                if scene_data.intersect_any(&shadow_ray, hit_point_to_light_dist) {
+                    continue;
+                }
+
+                let reverse_reflect_light_dir = to_light.reverse().reflection(&N).reverse();
+                let to_expo = hit_point
+                    .incident
+                    .dot(&reverse_reflect_light_dir)
+                    .max(0.)
+                    .powf(specular_exponent);
+
+                let light_color = light.color()
+                    * (light.intensity_at(hit_point_to_light_dist) * cone_falloff * weight);
+                diffuse_light += light_color * to_light.dot(&N).max(0.);
+                specular_light += light_color * to_expo;
             }
+        }
 
-            let reverse_reflect_light_dir = to_light.reverse().reflection(&N).reverse();
-            let to_expo = ray
-                .dir
-                .dot(&reverse_reflect_light_dir)
-                .max(0.)
-                .powf(hit_point.surface_material().specular_exponent);
+        (diffuse_light, specular_light)
+    }
 
-            diffuse_light_intensity += light.intensity * to_light.dot(&N).max(0.);
-            specular_light_intensity += light.intensity * to_expo;
+    /// For dielectrics, split the reflective and refractive albedo by the fresnel reflectance
+    fn fresnel_albedo(&self, hit_point: &HitPoint, material: &Material) -> Albedo {
+        let albedo = &material.albedo;
+        if material.refractive_index == 1. || albedo.refractive() <= 0. {
+            return albedo.clone();
         }
 
-        (diffuse_light_intensity, specular_light_intensity)
+        let (n1, n2) = hit_point.refractive_indices();
+        let kr = fresnel(-hit_point.incident.dot(&hit_point.norm()), n1, n2);
+        let total = albedo.reflective() + albedo.refractive();
+
+        Albedo::new(
+            albedo.diffusive(),
+            albedo.specular(),
+            kr * total,
+            (1. - kr) * total,
+        )
     }
 }
 
 impl RayCastStrategy for Lambertian {
-    fn cast_ray<B: Background>(&self, scene: &SceneData<B>, ray: &Ray, depth: usize) -> Color {
+    fn cast_ray<B: Background, R: Rng>(
+        &self,
+        scene: &SceneData<B>,
+        ray: &Ray,
+        depth: usize,
+        rng: &mut R,
+    ) -> Color {
         // WARN: Background color or Pure black?
         if depth > RECURSION_DEPTH {
             return Color::BLACK;
@@ -73,38 +123,36 @@ impl RayCastStrategy for Lambertian {
             return scene.intersect_background(ray);
         };
 
+        let material = hit_info.surface_material();
+        let albedo = self.fresnel_albedo(&hit_info, material);
+
         // NOTE: Calculate Reflection and Refraction: Indirect Illumination
-        let reflective_color = if hit_info.surface_material().albedo.reflective() > 0. {
+        // only traced when they have some weight, nothing comes from them otherwise
+        let reflective_color = if albedo.reflective() > 0. {
             let reflect_ray = ray.reflected(&hit_info);
-            self.cast_ray(scene, &reflect_ray, depth + 1)
+            self.cast_ray(scene, &reflect_ray, depth + 1, rng)
         } else {
-            scene.intersect_background(ray)
+            Color::BLACK
         };
 
-        let refractive_color = if hit_info.surface_material().albedo.refractive() > 0. {
+        let refractive_color = if albedo.refractive() > 0. {
             let refract_ray = ray.refracted(&hit_info);
-            self.cast_ray(scene, &refract_ray, depth + 1)
+            self.cast_ray(scene, &refract_ray, depth + 1, rng)
         } else {
-            scene.intersect_background(ray)
+            Color::BLACK
         };
 
         // NOTE: Calculate Diffusive and Specular Light: Direct Illumination
-        let (diffuse_light_intensity, specular_light_intensity) =
-            self.direct_illumination(scene, ray, &hit_info);
-
-        let albedo = &hit_info.surface_material().albedo;
-        let diffuse_color = hit_info
-            .surface_material()
-            .diffuse_color
-            .apply_intensity(diffuse_light_intensity);
-        let specular_color = Color::WHITE.apply_intensity(specular_light_intensity);
+        // NOTE: the light color tints the surface, white light keeps the diffuse color
+        let (diffuse_light, specular_light) = self.direct_illumination(scene, &hit_info, rng);
+        let diffuse_color = material.diffuse_color * diffuse_light;
 
         Color::apply_albedo(
             diffuse_color,
-            specular_color,
+            specular_light,
             reflective_color,
             refractive_color,
-            albedo,
+            &albedo,
         )
     }
 }
@@ -126,34 +174,862 @@ impl MonteCarlo {
         Self { recursion_depth }
     }
 
-    fn diffusive_ray_on_hemisphere(&self, hit: &HitPoint) -> Ray {
-        let mut rng = rand::rng();
-        let mut dir = Direction::new(
-            rng.random_range(-1f64..1.),
-            rng.random_range(-1f64..1.),
-            rng.random_range(-1f64..1.),
-        );
+    /// Reflect with probability of the fresnel reflectance, refract otherwise
+    ///
+    /// The reflectance is 1 on total internal reflection, so it always reflects
+    fn dielectric_ray<R: Rng>(&self, ray: &Ray, hit: &HitPoint, rng: &mut R) -> Ray {
+        let (n1, n2) = hit.refractive_indices();
+        let reflectance = fresnel(-hit.incident.dot(&hit.norm()), n1, n2);
 
-        if !dir.is_acute_angle(&hit.norm()) {
-            dir = dir.reverse();
+        if rng.random::<f64>() < reflectance {
+            ray.reflected(hit)
+        } else {
+            ray.refracted(hit)
         }
+    }
 
-        Ray::new(hit.position, dir)
+    /// Mirror reflection of `ray` perturbed by a random vector in a sphere of radius `fuzz`
+    ///
+    /// Return `None` when the perturbed ray goes below the surface, the ray is absorbed
+    fn metal_ray<R: Rng>(&self, hit: &HitPoint, fuzz: f64, rng: &mut R) -> Option<Ray> {
+        let norm = hit.norm();
+        let reflected = hit.incident.reflection(&norm);
+        let dir = reflected.as_ref() + fuzz * random_in_unit_sphere(rng);
+
+        (dir.dot(norm.as_ref()) > 0.).then(|| Ray::new(hit.position, Direction::from(dir)))
+    }
+}
+
+/// Sample a diffusive ray with cosine-weighted distribution around the hit normal
+fn diffusive_ray_on_hemisphere<R: Rng>(hit: &HitPoint, rng: &mut R) -> Ray {
+    let r1: f64 = rng.random();
+    let r2: f64 = rng.random();
+
+    // NOTE: local coordinates, w is the normal
+    // (cos(2π r1)√r2, sin(2π r1)√r2, √(1−r2))
+    let phi = 2. * PI * r1;
+    let x = phi.cos() * r2.sqrt();
+    let y = phi.sin() * r2.sqrt();
+    let z = (1. - r2).sqrt();
+
+    let w = hit.norm();
+    let (u, v) = w.orthonormal_basis();
+    let dir = x * u.as_ref() + y * v.as_ref() + z * w.as_ref();
+
+    Ray::new(hit.position, Direction::from(dir))
+}
+
+fn random_in_unit_sphere<R: Rng>(rng: &mut R) -> Vector3<f64> {
+    loop {
+        let v = Vector3::new(
+            rng.random_range(-1.0..1.0),
+            rng.random_range(-1.0..1.0),
+            rng.random_range(-1.0..1.0),
+        );
+        if v.magnitude_squared() < 1. {
+            return v;
+        }
     }
 }
 
 impl RayCastStrategy for MonteCarlo {
-    fn cast_ray<B: Background>(&self, scene: &SceneData<B>, ray: &Ray, depth: usize) -> Color {
+    fn cast_ray<B: Background, R: Rng>(
+        &self,
+        scene: &SceneData<B>,
+        ray: &Ray,
+        depth: usize,
+        rng: &mut R,
+    ) -> Color {
         if depth > self.recursion_depth {
             return Color::BLACK;
         }
 
         // NOTE: Not hit any object in scene, return background color
-        let Some(hit_p) = scene.intersect(ray) else {
+        let Some(hit_p) = scene.intersect_with_rng(ray, rng) else {
             return scene.intersect_background(ray);
         };
 
-        let diffusive_ray = self.diffusive_ray_on_hemisphere(&hit_p);
-        0.5 * self.cast_ray(scene, &diffusive_ray, depth + 1)
+        let material = hit_p.surface_material();
+
+        // NOTE: inside a participating medium, go on in a uniformly random direction
+        if material.kind == MaterialKind::Isotropic {
+            let dir = random_in_unit_sphere(rng);
+            let scattered = Ray::new(hit_p.position, Direction::from(dir));
+            let incoming = self.cast_ray(scene, &scattered, depth + 1, rng);
+
+            return material.emission + material.diffuse_color * incoming;
+        }
+
+        let albedo = &material.albedo;
+        let (refractive, reflective) = (albedo.refractive(), albedo.reflective());
+        let total = refractive + reflective + albedo.diffusive();
+        if total <= 0. {
+            return material.emission;
+        }
+
+        // NOTE: follow one of refraction, reflection and diffusion, picked with a probability
+        // proportional to its albedo. Weighted by its albedo over that probability, the average
+        // is the albedo-weighted sum, scaled down when the albedos add up to more than 1
+        let weight = total.min(1.);
+        let pick = rng.random::<f64>() * total;
+
+        let outgoing = if pick < refractive {
+            // NOTE: dielectric, either reflect or refract, chosen by fresnel reflectance
+            let dielectric_ray = self.dielectric_ray(ray, &hit_p, rng);
+            material.diffuse_color * self.cast_ray(scene, &dielectric_ray, depth + 1, rng)
+        } else if pick < refractive + reflective {
+            // NOTE: mirror reflection blurred by fuzz, not tinted by the surface, like in Lambertian
+            let Some(metal_ray) = self.metal_ray(&hit_p, material.fuzz, rng) else {
+                return material.emission;
+            };
+            self.cast_ray(scene, &metal_ray, depth + 1, rng)
+        } else {
+            let diffusive_ray = diffusive_ray_on_hemisphere(&hit_p, rng);
+            material.diffuse_color * self.cast_ray(scene, &diffusive_ray, depth + 1, rng)
+        };
+
+        material.emission + weight * outgoing
+    }
+}
+
+/// Ambient occlusion, the diffuse color of the first hit darkened by the geometry around it
+///
+/// `samples` rays go over the hemisphere, the fraction not hitting anything within
+/// `max_distance` is how much of the surface is lit. Lights are ignored, missed rays see the
+/// background
+pub struct AmbientOcclusion {
+    samples: usize,
+    max_distance: f64,
+}
+
+impl Default for AmbientOcclusion {
+    fn default() -> Self {
+        Self {
+            samples: 16,
+            max_distance: 1.,
+        }
+    }
+}
+
+impl AmbientOcclusion {
+    pub fn new(samples: usize, max_distance: f64) -> Self {
+        Self {
+            samples: samples.max(1),
+            max_distance,
+        }
+    }
+}
+
+impl RayCastStrategy for AmbientOcclusion {
+    fn cast_ray<B: Background, R: Rng>(
+        &self,
+        scene: &SceneData<B>,
+        ray: &Ray,
+        _depth: usize,
+        rng: &mut R,
+    ) -> Color {
+        let Some(hit) = scene.intersect(ray) else {
+            return scene.intersect_background(ray);
+        };
+
+        // NOTE: cosine weighted, so occluders above the surface count more than grazing ones
+        let escaped = (0..self.samples)
+            .filter(|_| {
+                let ray = diffusive_ray_on_hemisphere(&hit, rng);
+                !scene.intersect_any(&ray, self.max_distance)
+            })
+            .count();
+
+        hit.surface_material().diffuse_color * (escaped as f64 / self.samples as f64)
+    }
+}
+
+/// Debug view, shows the outward surface normal of the first hit as `0.5 * (n + 1)`
+///
+/// Nothing is lit, missed rays are black
+pub struct NormalView;
+
+impl RayCastStrategy for NormalView {
+    fn cast_ray<B: Background, R: Rng>(
+        &self,
+        scene: &SceneData<B>,
+        ray: &Ray,
+        _depth: usize,
+        _rng: &mut R,
+    ) -> Color {
+        let Some(hit) = scene.intersect(ray) else {
+            return Color::BLACK;
+        };
+        let norm = hit.obj.surface_norm(&hit.position);
+
+        Color::from((norm.as_ref() + Vector3::new(1., 1., 1.)) * 0.5)
+    }
+}
+
+/// Debug view, shows the distance to the first hit in grayscale
+///
+/// White at `near` fading to black at `far`, missed rays are black
+pub struct DepthView {
+    near: f64,
+    far: f64,
+}
+
+impl DepthView {
+    pub fn new(near: f64, far: f64) -> Self {
+        Self { near, far }
+    }
+}
+
+impl RayCastStrategy for DepthView {
+    fn cast_ray<B: Background, R: Rng>(
+        &self,
+        scene: &SceneData<B>,
+        ray: &Ray,
+        _depth: usize,
+        _rng: &mut R,
+    ) -> Color {
+        let Some(hit) = scene.intersect(ray) else {
+            return Color::BLACK;
+        };
+        let gray = 1. - ((hit.t - self.near) / (self.far - self.near)).clamp(0., 1.);
+
+        Color::new(gray, gray, gray)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raytracer::camera::{CameraBuilder, Progress};
+    use crate::raytracer::{
+        world::{
+            background::{DummyBackground, Sky, SolidColor},
+            objects::{AABBox, Attenuation, Material, Plane, Sphere},
+            Light, Scene,
+        },
+        Albedo, Position, EPSILON,
+    };
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_no_shadow_acne_on_single_ball() {
+        let red_rubber = Material {
+            diffuse_color: Color::new(0.3, 0.1, 0.1),
+            albedo: Albedo::new(0.9, 0.1, 0.0, 0.0),
+            specular_exponent: 10.,
+            ..Material::default()
+        };
+        // NOTE: the scene of examples/single_ball.rs, shadow rays skip the first shadow_bias
+        let scene: Scene<DummyBackground> = Scene::default()
+            .add_background(DummyBackground)
+            .add_object(Sphere::new(Position::new(-3., 0., -16.), 8., red_rubber))
+            .add_light(Light::new(Position::new(-20., 20., 20.), 1.5))
+            .add_light(Light::new(Position::new(30., 50., -25.), 1.8))
+            .add_light(Light::new(Position::new(30., 20., 30.), 1.7));
+        let (width, height) = (160, 90);
+        let camera = CameraBuilder::new().progress(Progress::Quiet).build();
+        let buffer = camera.render_to_buffer(&scene, width, height);
+        let lum = |x: u32, y: u32| buffer[(y * width + x) as usize].luminance();
+
+        // NOTE: acne is a dark pixel among lit neighbors, the lighting is smooth on the sphere
+        let acne = (1..height - 1)
+            .flat_map(|y| (1..width - 1).map(move |x| (x, y)))
+            .filter(|&(x, y)| {
+                let neighbors = [lum(x - 1, y), lum(x + 1, y), lum(x, y - 1), lum(x, y + 1)];
+                let darkest = neighbors.into_iter().fold(f64::MAX, f64::min);
+                darkest > 0.05 && lum(x, y) < 0.5 * darkest
+            })
+            .count();
+        assert_eq!(acne, 0);
+    }
+
+    fn average_radiance<S: RayCastStrategy>(scene: &Scene<Sky, S>, ray: &Ray) -> f64 {
+        let samples = 500;
+        let mut sum = Color::BLACK;
+        for _ in 0..samples {
+            sum += scene.cast_ray(ray);
+        }
+
+        let avg = sum / samples as f64;
+        avg.as_ref().sum() / 3.
+    }
+
+    #[test]
+    fn test_cosine_weighted_hemisphere_sampling() {
+        let sphere = Sphere::new(Position::new(0., 0., 0.), 1., Material::default());
+
+        for norm in [
+            Direction::new(0., 1., 0.),
+            Direction::new(0., 0., -1.),
+            Direction::new(1., 1., 1.),
+        ] {
+            let eye = Position::new(0., 0., 0.).move_forward(2., &norm);
+            let hit = HitPoint::from_ray(&sphere, &Ray::new(eye, norm.reverse()), 1., true);
+
+            let samples = 20000;
+            let mut sum = Vector3::zeros();
+            for _ in 0..samples {
+                let ray = diffusive_ray_on_hemisphere(&hit, &mut rand::rng());
+                assert!(ray.dir.dot(&norm) >= 0.);
+                sum += ray.dir.as_ref();
+            }
+
+            // NOTE: for cosine-weighted sampling, E[cos(theta)] = 2/3
+            let mean = sum / samples as f64;
+            assert_abs_diff_eq!(mean.dot(norm.as_ref()), 2. / 3., epsilon = 0.02);
+            assert_abs_diff_eq!(Direction::from(mean), norm, epsilon = 0.05);
+        }
+    }
+
+    #[test]
+    fn test_emissive_sphere_lights_floor() {
+        let lamp = Material::default().with_emission(Color::new(4., 4., 4.));
+        let floor = Material {
+            diffuse_color: Color::new(0.5, 0.5, 0.5),
+            ..Material::default()
+        };
+
+        let scene: Scene<Sky, MonteCarlo> = Scene::default()
+            .add_object(Sphere::new(Position::new(0., 2., 0.), 1., lamp))
+            .add_object(
+                AABBox::try_build(
+                    Position::new(-100., -1., -100.),
+                    Position::new(100., 0., 100.),
+                    floor,
+                )
+                .unwrap(),
+            );
+
+        let near = Position::new(1.5, 0., 0.);
+        let far = Position::new(30., 0., 0.);
+        let eye = Position::new(0., 5., 10.);
+
+        let near_radiance =
+            average_radiance(&scene, &Ray::new(eye, Direction::a_to_b(&eye, &near)));
+        let far_radiance = average_radiance(&scene, &Ray::new(eye, Direction::a_to_b(&eye, &far)));
+
+        assert!(near_radiance > 0.);
+        assert!(
+            near_radiance > 5. * far_radiance,
+            "near: {near_radiance}, far: {far_radiance}"
+        );
+    }
+
+    #[test]
+    fn test_directional_light_side_and_shadow() {
+        let sun = Light::directional(Direction::new(0., -1., 0.), 1.);
+        let ball = || Sphere::new(Position::new(0., 0., 0.), 1., Material::default());
+
+        let eye = Position::new(0., 0., 10.);
+        let top = Ray::new(eye, Direction::a_to_b(&eye, &Position::new(0., 0.9, 0.)));
+        let bottom = Ray::new(eye, Direction::a_to_b(&eye, &Position::new(0., -0.9, 0.)));
+
+        let scene: Scene<Sky> = Scene::default().add_object(ball()).add_light(sun);
+        assert!(scene.cast_ray(&top).as_ref().x > 0.1);
+        assert_abs_diff_eq!(scene.cast_ray(&bottom).as_ref().x, 0.);
+
+        // NOTE: a ball far above, between the sun and the sphere, shadows the top
+        let sun = Light::directional(Direction::new(0., -1., 0.), 1.);
+        let blocker = Sphere::new(Position::new(0., 50., 0.), 5., Material::default());
+        let scene: Scene<Sky> = Scene::default()
+            .add_object(ball())
+            .add_object(blocker)
+            .add_light(sun);
+        assert_abs_diff_eq!(scene.cast_ray(&top).as_ref().x, 0.);
+    }
+
+    #[test]
+    fn test_light_attenuation() {
+        let scene: Scene<Sky> = Scene::default().add_object(Sphere::new(
+            Position::new(0., 0., 0.),
+            1.,
+            Material::default(),
+        ));
+        let ray = Ray::new(Position::new(0., 5., 0.), Direction::new(0., -1., 0.));
+        let hit = HitPoint::from_ray(scene.scene_data.objects[0].object.as_ref(), &ray, 4., true);
+
+        let diffuse = |light: Light| {
+            let scene = Scene::<Sky>::default().add_light(light);
+            Lambertian
+                .direct_illumination(&scene.scene_data, &hit, &mut rand::rng())
+                .0
+                .as_ref()
+                .x
+        };
+        let above = |d: f64| Position::new(0., 1. + d, 0.);
+
+        // NOTE: without attenuation the distance doesn't matter
+        assert_abs_diff_eq!(diffuse(Light::new(above(2.), 1.)), 1.);
+        assert_abs_diff_eq!(diffuse(Light::new(above(50.), 1.)), 1.);
+
+        // NOTE: with a strong falloff, 1 + k * d² is dominated by d²
+        let near = diffuse(Light::with_attenuation(above(10.), 1., 100.));
+        let far = diffuse(Light::with_attenuation(above(20.), 1., 100.));
+        assert_abs_diff_eq!(far / near, 0.25, epsilon = 1e-4);
+    }
+
+    #[test]
+    fn test_light_inverse_square_falloff() {
+        let scene: Scene<Sky> = Scene::default().add_object(Sphere::new(
+            Position::new(0., 0., 0.),
+            1.,
+            Material::default(),
+        ));
+        let ray = Ray::new(Position::new(0., 5., 0.), Direction::new(0., -1., 0.));
+        let hit = HitPoint::from_ray(scene.scene_data.objects[0].object.as_ref(), &ray, 4., true);
+
+        let diffuse = |d: f64, attenuation: Attenuation| {
+            let light = Light::new(Position::new(0., 1. + d, 0.), 8.).attenuated(attenuation);
+            let scene = Scene::<Sky>::default().add_light(light);
+            Lambertian
+                .direct_illumination(&scene.scene_data, &hit, &mut rand::rng())
+                .0
+                .as_ref()
+                .x
+        };
+
+        // NOTE: the light is straight above, so the diffuse term is the intensity reaching it
+        let inverse_square = Attenuation::new(0., 0., 1.);
+        for d in [2., 4.] {
+            assert_abs_diff_eq!(diffuse(d, inverse_square), 8. / (d * d), epsilon = 1e-9);
+        }
+        assert_abs_diff_eq!(
+            diffuse(4., inverse_square) / diffuse(2., inverse_square),
+            0.25,
+            epsilon = 1e-9
+        );
+
+        let linear = Attenuation::new(1., 0.5, 0.);
+        assert_abs_diff_eq!(diffuse(2., linear), 4., epsilon = 1e-9);
+        assert_abs_diff_eq!(diffuse(2., Attenuation::NONE), 8., epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_red_light_on_white_sphere() {
+        let white = Material {
+            diffuse_color: Color::WHITE,
+            albedo: Albedo::new(1., 0., 0., 0.),
+            ..Material::default()
+        };
+        let scene: Scene<Sky> = Scene::default()
+            .add_object(Sphere::new(Position::new(0., 0., 0.), 1., white))
+            .add_light(Light::new(Position::new(0., 10., 10.), 1.).with_color(Color::RED));
+
+        let eye = Position::new(0., 0., 10.);
+        let ray = Ray::new(eye, Direction::a_to_b(&eye, &Position::new(0., 0.5, 0.)));
+        let color = scene.cast_ray(&ray);
+        let color = color.as_ref();
+
+        assert!(color.x > 0.1, "{color}");
+        assert_abs_diff_eq!(color.y, 0.);
+        assert_abs_diff_eq!(color.z, 0.);
+    }
+
+    #[test]
+    fn test_spot_light_cone() {
+        // NOTE: straight down on the floor at y = 0, the cone is 20 to 30 degrees wide
+        let spot = Light::spot(
+            Position::new(0., 10., 0.),
+            Direction::new(0., -1., 0.),
+            20.,
+            30.,
+            1.,
+        );
+        let scene: Scene<Sky> = Scene::default()
+            .add_object(
+                AABBox::try_build(
+                    Position::new(-100., -1., -100.),
+                    Position::new(100., 0., 100.),
+                    Material::default(),
+                )
+                .unwrap(),
+            )
+            .add_light(spot);
+
+        let diffuse_at = |x: f64| {
+            let ray = Ray::new(Position::new(x, 5., 0.), Direction::new(0., -1., 0.));
+            let hit = scene.scene_data.intersect(&ray).unwrap();
+            Lambertian
+                .direct_illumination(&scene.scene_data, &hit, &mut rand::rng())
+                .0
+                .as_ref()
+                .x
+        };
+        let edge = |deg: f64| 10. * deg.to_radians().tan();
+
+        assert!(diffuse_at(0.) > 0.9);
+        assert!(diffuse_at(edge(19.)) > 0.8);
+        let fading = diffuse_at(edge(25.));
+        assert!(0. < fading && fading < diffuse_at(edge(19.)), "{fading}");
+        assert_abs_diff_eq!(diffuse_at(edge(31.)), 0.);
+    }
+
+    #[test]
+    fn test_spot_light_shadow() {
+        let spot = || {
+            Light::spot(
+                Position::new(0., 10., 0.),
+                Direction::new(0., -1., 0.),
+                20.,
+                30.,
+                1.,
+            )
+        };
+        let floor = || {
+            AABBox::try_build(
+                Position::new(-100., -1., -100.),
+                Position::new(100., 0., 100.),
+                Material::default(),
+            )
+            .unwrap()
+        };
+        let ray = Ray::new(Position::new(0., 5., 1.), Direction::new(0., -1., 0.));
+        let diffuse = |scene: &Scene<Sky>| {
+            let hit = scene.scene_data.intersect(&ray).unwrap();
+            Lambertian
+                .direct_illumination(&scene.scene_data, &hit, &mut rand::rng())
+                .0
+                .as_ref()
+                .x
+        };
+
+        let lit: Scene<Sky> = Scene::default().add_object(floor()).add_light(spot());
+        assert!(diffuse(&lit) > 0.9);
+
+        // NOTE: a ball under the spot, but off the camera ray, shadows the floor inside the cone
+        let blocked: Scene<Sky> = Scene::default()
+            .add_object(floor())
+            .add_object(Sphere::new(
+                Position::new(0., 7., 0.7),
+                0.5,
+                Material::default(),
+            ))
+            .add_light(spot());
+        assert_abs_diff_eq!(diffuse(&blocked), 0.);
+    }
+
+    #[test]
+    fn test_sphere_light_soft_shadow() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let scene = |light: Light, blocked: bool| {
+            let floor = AABBox::try_build(
+                Position::new(-100., -1., -100.),
+                Position::new(100., 0., 100.),
+                Material::default(),
+            )
+            .unwrap();
+            let scene: Scene<Sky> = Scene::default()
+                .add_object(floor)
+                .add_light(light)
+                .update_light_samples(256);
+            if blocked {
+                scene.add_object(Sphere::new(
+                    Position::new(0., 5., 0.),
+                    1.,
+                    Material::default(),
+                ))
+            } else {
+                scene
+            }
+        };
+        // NOTE: from under the blocker, straight down on the floor
+        let diffuse_at = |scene: &Scene<Sky>, x: f64| {
+            let ray = Ray::new(Position::new(x, 3., 0.), Direction::new(0., -1., 0.));
+            let hit = scene.scene_data.intersect(&ray).unwrap();
+            let rng = &mut StdRng::seed_from_u64(3);
+            Lambertian
+                .direct_illumination(&scene.scene_data, &hit, rng)
+                .0
+                .as_ref()
+                .x
+        };
+        let above = Position::new(0., 10., 0.);
+
+        // NOTE: the blocker's shadow of a point light has a radius of about 2, with a hard edge
+        let point = Light::sphere(above, 0., 1.);
+        assert_eq!(point.samples(256), 1);
+        let (blocked, open) = (
+            scene(point, true),
+            scene(Light::sphere(above, 0., 1.), false),
+        );
+        let shade = |x| diffuse_at(&blocked, x) / diffuse_at(&open, x);
+        assert_abs_diff_eq!(shade(1.5), 0.);
+        assert_abs_diff_eq!(shade(2.5), 1.);
+
+        // NOTE: with a ball of light, the middle stays dark and the edge fades out
+        let area = || Light::sphere(above, 2., 1.);
+        let (blocked, open) = (scene(area(), true), scene(area(), false));
+        let shade = |x| diffuse_at(&blocked, x) / diffuse_at(&open, x);
+        assert_abs_diff_eq!(shade(0.), 0.);
+        let penumbra = shade(1.5);
+        assert!(0.2 < penumbra && penumbra < 0.8, "{penumbra}");
+        assert!(penumbra < shade(3.) && shade(3.) < 1.);
+    }
+
+    #[test]
+    fn test_sphere_light_samples_face_the_point() {
+        let center = Position::new(0., 10., 0.);
+        let light = Light::sphere(center, 2., 1.);
+        let pos = Position::new(3., 0., 1.);
+        let rng = &mut rand::rng();
+
+        // NOTE: every sample is on the light, on the side of it `pos` can see
+        for _ in 0..1000 {
+            let (dir, dist) = light.illuminate_sample(&pos, rng).unwrap();
+            let point = pos.move_forward(dist, &dir);
+            let out = Direction::a_to_b(&center, &point);
+            assert_abs_diff_eq!(point.distance_to(&center), 2., epsilon = 1e-9);
+            assert!(out.dot(&dir) <= 1e-9, "{point:?}");
+        }
+        assert!(Light::point(pos, 1.).illuminate_sample(&pos, rng).is_none());
+    }
+
+    #[test]
+    fn test_ambient_light_ignores_geometry() {
+        let ball = Sphere::new(Position::new(0., 0., 0.), 1., Material::default());
+        // NOTE: the blocker would shadow any directional light
+        let blocker = Sphere::new(Position::new(0., 0., 5.), 0.5, Material::default());
+        let scene: Scene<Sky> = Scene::default()
+            .add_object(ball)
+            .add_object(blocker)
+            .add_light(Light::ambient(0.2));
+
+        let eye = Position::new(0., 0., -10.);
+        for target in [Position::new(0., 0.9, 0.), Position::new(0., -0.9, 0.)] {
+            let ray = Ray::new(eye, Direction::a_to_b(&eye, &target));
+            assert_abs_diff_eq!(
+                *scene.cast_ray(&ray).as_ref(),
+                Vector3::new(0.2, 0.2, 0.2),
+                epsilon = EPSILON
+            );
+        }
+    }
+
+    #[test]
+    fn test_metal_ray_reflection() {
+        let sphere = Sphere::new(Position::new(0., 0., 0.), 1., Material::default());
+        let ray = Ray::new(Position::new(-1., 2., 0.), Direction::new(1., -1., 0.));
+        let hit = HitPoint::from_ray(&sphere, &ray, 2f64.sqrt(), true);
+        let monte_carlo = MonteCarlo::default();
+
+        // NOTE: without fuzz the scattered ray is the exact mirror reflection
+        let metal_ray = monte_carlo.metal_ray(&hit, 0., &mut rand::rng()).unwrap();
+        assert_abs_diff_eq!(metal_ray.dir, Direction::new(1., 1., 0.));
+        assert_abs_diff_eq!(metal_ray.position, hit.position);
+
+        // NOTE: with fuzz, the ray stays in a cone around the reflection and above the surface
+        let reflected = Direction::new(1., 1., 0.);
+        for _ in 0..1000 {
+            if let Some(fuzzy) = monte_carlo.metal_ray(&hit, 0.3, &mut rand::rng()) {
+                assert!(fuzzy.dir.dot(&hit.norm()) > 0.);
+                assert!(fuzzy.dir.dot(&reflected) >= (1. - 0.3f64.powi(2)).sqrt() - EPSILON);
+            }
+        }
+    }
+
+    #[test]
+    fn test_mirror_reflects_background() {
+        let mirror = Material {
+            diffuse_color: Color::new(1., 0.5, 0.5),
+            albedo: Albedo::new(0., 0., 1., 0.),
+            ..Material::default()
+        };
+        let scene: Scene<Sky, MonteCarlo> = Scene::default()
+            .add_background(Sky)
+            .add_object(Sphere::new(Position::new(0., 0., 0.), 1., mirror));
+
+        // NOTE: straight down on the top of the ball, the ray bounces straight up to the sky,
+        // the reflection isn't tinted by the surface
+        let ray = Ray::new(Position::new(0., 5., 0.), Direction::new(0., -1., 0.));
+        let sky = Sky.get_color(&Ray::new(
+            Position::new(0., 1., 0.),
+            Direction::new(0., 1., 0.),
+        ));
+
+        assert_abs_diff_eq!(
+            *scene.cast_ray(&ray).as_ref(),
+            *sky.as_ref(),
+            epsilon = EPSILON
+        );
+    }
+
+    #[test]
+    fn test_albedos_above_one_add_no_energy() {
+        let white = Material {
+            diffuse_color: Color::WHITE,
+            albedo: Albedo::new(1., 0., 1., 0.),
+            ..Material::default()
+        };
+        let scene: Scene<SolidColor, MonteCarlo> = Scene::default()
+            .add_background(SolidColor(Color::WHITE))
+            .add_object(Sphere::new(Position::new(0., 0., 0.), 1., white));
+
+        // NOTE: every bounce leaves the ball to the white background, whichever lobe is picked
+        let ray = Ray::new(Position::new(0., 5., 0.), Direction::new(0.1, -1., 0.));
+        for _ in 0..100 {
+            assert_abs_diff_eq!(
+                *scene.cast_ray(&ray).as_ref(),
+                *Color::WHITE.as_ref(),
+                epsilon = EPSILON
+            );
+        }
+    }
+
+    #[test]
+    fn test_mixed_mirror_reflects_background() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        // NOTE: the preset is black and mostly diffusive, only the reflection brings light
+        let scene: Scene<Sky, MonteCarlo> = Scene::default()
+            .add_background(Sky)
+            .add_object(Sphere::new(Position::new(0., 0., 0.), 1., Material::MIRROR));
+
+        let ray = Ray::new(Position::new(0., 5., 0.), Direction::new(0., -1., 0.));
+        let sky = Sky.get_color(&Ray::new(
+            Position::new(0., 1., 0.),
+            Direction::new(0., 1., 0.),
+        ));
+        let rng = &mut StdRng::seed_from_u64(5);
+        let samples = 4000;
+        let mut sum = Color::BLACK;
+        for _ in 0..samples {
+            sum += scene.cast_ray_with_rng(&ray, rng);
+        }
+
+        // NOTE: the albedos add up to 1.87, each lobe gets its share of 1
+        let albedo = &Material::MIRROR.albedo;
+        let reflective = albedo.reflective() / (albedo.diffusive() + albedo.reflective());
+        assert_abs_diff_eq!(
+            *(sum * (1. / samples as f64)).as_ref(),
+            *(sky * reflective).as_ref(),
+            epsilon = 0.05
+        );
+    }
+
+    #[test]
+    fn test_tiny_reflective_weight_adds_no_sky_tint() {
+        let red = |reflective: f64| Material {
+            diffuse_color: Color::new(1., 0., 0.),
+            albedo: Albedo::new(1., 0., reflective, 0.),
+            ..Material::default()
+        };
+        let scene = |reflective: f64| -> Scene<Sky> {
+            Scene::with_background(Sky)
+                .add_object(Sphere::new(Position::new(0., 0., 0.), 1., red(reflective)))
+                .add_light(Light::point(Position::new(0., 10., 10.), 1.))
+        };
+
+        let eye = Position::new(0., 0., 5.);
+        for target in [
+            Position::new(0., 0., 1.),
+            Position::new(0.5, 0.5, 0.5),
+            Position::new(-0.6, 0., 0.7),
+        ] {
+            let ray = Ray::new(eye, Direction::a_to_b(&eye, &target));
+            let without = scene(0.).cast_ray(&ray);
+            let tiny = scene(0.001).cast_ray(&ray);
+
+            // NOTE: nothing but the red diffuse light, the sky only comes in with its weight
+            assert_abs_diff_eq!(without.as_ref().y, 0.);
+            assert_abs_diff_eq!(without.as_ref().z, 0.);
+            assert_abs_diff_eq!(*tiny.as_ref(), *without.as_ref(), epsilon = 0.002);
+        }
+    }
+
+    #[test]
+    fn test_dielectric_ray_total_internal_reflection() {
+        let glass = Material {
+            albedo: Albedo::new(0., 0., 0., 1.),
+            refractive_index: 1.5,
+            ..Material::default()
+        };
+        let sphere = Sphere::new(Position::new(0., 0., 0.), 1., glass);
+        let monte_carlo = MonteCarlo::default();
+
+        // NOTE: hit the top from inside, 60 degrees from the normal, beyond the critical angle
+        let dir = Direction::new(60f64.to_radians().sin(), 60f64.to_radians().cos(), 0.);
+        let ray = Ray::new(Position::new(0., 1., 0.).move_forward(-1., &dir), dir);
+        let hit = HitPoint::from_ray(&sphere, &ray, 1., false);
+        for _ in 0..100 {
+            let next = monte_carlo.dielectric_ray(&ray, &hit, &mut rand::rng());
+            assert_abs_diff_eq!(next.dir, dir.reflection(&hit.norm()));
+        }
+
+        // NOTE: head-on from outside, schlick gives 4% reflectance, the rest refracts straight through
+        let ray = Ray::new(Position::new(0., 5., 0.), Direction::new(0., -1., 0.));
+        let hit = HitPoint::from_ray(&sphere, &ray, 4., true);
+        let refracted = (0..1000)
+            .filter(|_| {
+                monte_carlo
+                    .dielectric_ray(&ray, &hit, &mut rand::rng())
+                    .dir
+                    .as_ref()
+                    .y
+                    < 0.
+            })
+            .count();
+        assert!((900..1000).contains(&refracted), "{refracted}");
+    }
+
+    #[test]
+    fn test_debug_views_of_centered_sphere() {
+        use crate::raytracer::camera::{CameraBuilder, Progress};
+        use image::RgbImage;
+
+        let sphere = || Sphere::new(Position::new(0., 0., -5.), 1., Material::default());
+        let camera = CameraBuilder::new().progress(Progress::Quiet).build();
+        let center_of = |img: &RgbImage| img.get_pixel(4, 4).0;
+
+        // NOTE: the normal at the center faces the camera, (0, 0, 1) maps to (0.5, 0.5, 1)
+        let scene: Scene<Sky, NormalView> = Scene::new(NormalView).add_object(sphere());
+        let mut img = RgbImage::new(8, 8);
+        camera.render(&scene, &mut img);
+        let [r, g, b] = center_of(&img);
+        assert!(
+            r.abs_diff(127) <= 1 && g.abs_diff(127) <= 1 && b == 255,
+            "{r} {g} {b}"
+        );
+        assert_eq!(img.get_pixel(0, 0).0, [0, 0, 0]);
+
+        // NOTE: the center is hit at 4, halfway between near and far
+        let scene: Scene<Sky, DepthView> = Scene::new(DepthView::new(0., 8.)).add_object(sphere());
+        camera.render(&scene, &mut img);
+        let [r, g, b] = center_of(&img);
+        assert!(r.abs_diff(127) <= 1 && r == g && g == b, "{r} {g} {b}");
+        assert_eq!(img.get_pixel(0, 0).0, [0, 0, 0]);
+    }
+
+    #[test]
+    fn test_ambient_occlusion_under_sphere() {
+        let scene = |max_distance: f64| -> Scene<Sky, AmbientOcclusion> {
+            Scene::new(AmbientOcclusion::new(64, max_distance))
+                .add_object(Plane::new(
+                    Position::new(0., 0., 0.),
+                    Direction::new(0., 1., 0.),
+                    Material::default(),
+                ))
+                .add_object(Sphere::new(
+                    Position::new(0., 2.1, 0.),
+                    2.,
+                    Material::default(),
+                ))
+        };
+        // NOTE: low from the side, so the view ray passes under the sphere
+        let looking_at = |target: Position| {
+            let eye = Position::new(target.as_ref().x - 10., 1., 0.);
+            Ray::new(eye, Direction::a_to_b(&eye, &target))
+        };
+        let under = looking_at(Position::new(0., 0., 0.));
+        let open = looking_at(Position::new(30., 0., 0.));
+
+        let scene_near = scene(5.);
+        let occluded = average_radiance(&scene_near, &under);
+        let unoccluded = average_radiance(&scene_near, &open);
+        assert_abs_diff_eq!(unoccluded, 1.);
+        assert!(occluded < 0.5 * unoccluded, "{occluded} vs {unoccluded}");
+
+        // NOTE: the sphere is 0.1 above the plane, shorter rays all escape
+        assert_abs_diff_eq!(average_radiance(&scene(0.05), &under), 1.);
     }
 }
@@ -0,0 +1,87 @@
+use anyhow::Result;
+use image::{DynamicImage, Rgb32FImage};
+use nalgebra::Vector3;
+
+use std::path::Path;
+
+use crate::raytracer::Color;
+
+/// Image texture addressed by uv coordinates, sampled with bilinear filtering
+#[derive(Clone, Debug)]
+pub struct Texture(Rgb32FImage);
+
+impl Texture {
+    pub fn new(img: DynamicImage) -> Self {
+        Self(img.to_rgb32f())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(Self::new(image::open(path)?))
+    }
+
+    pub fn width(&self) -> u32 {
+        self.0.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.0.height()
+    }
+
+    fn texel(&self, x: i64, y: i64) -> Vector3<f64> {
+        // NOTE: u wraps around, v clamps at the border
+        let x = x.rem_euclid(self.width() as i64) as u32;
+        let y = y.clamp(0, self.height() as i64 - 1) as u32;
+        let p = self.0.get_pixel(x, y).0;
+
+        Vector3::new(p[0] as f64, p[1] as f64, p[2] as f64)
+    }
+
+    /// Sample the color at `(u, v)`, `u` goes from left to right, `v` from bottom to top
+    pub fn sample(&self, u: f64, v: f64) -> Color {
+        // NOTE: texel centers sit at half integers
+        let x = u * self.width() as f64 - 0.5;
+        let y = (1. - v) * self.height() as f64 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let top = (1. - fx) * self.texel(x0, y0) + fx * self.texel(x0 + 1, y0);
+        let bottom = (1. - fx) * self.texel(x0, y0 + 1) + fx * self.texel(x0 + 1, y0 + 1);
+
+        Color::from((1. - fy) * top + fy * bottom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use image::{Rgb, RgbImage};
+
+    #[test]
+    fn test_bilinear_sample() {
+        let mut img = RgbImage::new(2, 2);
+        img.put_pixel(0, 0, Rgb([255, 0, 0]));
+        img.put_pixel(1, 0, Rgb([0, 255, 0]));
+        img.put_pixel(0, 1, Rgb([0, 0, 255]));
+        img.put_pixel(1, 1, Rgb([255, 255, 255]));
+        let texture = Texture::new(DynamicImage::ImageRgb8(img));
+
+        // NOTE: texel centers return the texel itself
+        assert_abs_diff_eq!(
+            *texture.sample(0.25, 0.75).as_ref(),
+            Vector3::new(1., 0., 0.)
+        );
+        assert_abs_diff_eq!(
+            *texture.sample(0.75, 0.25).as_ref(),
+            Vector3::new(1., 1., 1.)
+        );
+
+        // NOTE: the center is the average of four texels
+        assert_abs_diff_eq!(
+            *texture.sample(0.5, 0.5).as_ref(),
+            Vector3::new(0.5, 0.5, 0.5),
+            epsilon = 1e-6
+        );
+    }
+}
@@ -1,10 +1,56 @@
+use anyhow::Result;
 use image::{imageops::flip_vertical_in_place, Rgb, RgbImage};
 use imageproc::drawing::draw_line_segment_mut;
-use nalgebra::{matrix, Matrix4, Vector3, Vector4};
+use indicatif::ParallelProgressIterator;
+use nalgebra::{matrix, Matrix3, Matrix4, Vector3, Vector4};
 use rand::Rng;
+use rayon::prelude::*;
 
-const FRAME: usize = 60;
+use std::f64::consts::PI;
+use std::path::PathBuf;
 
+use crate::raytracer::{animation::save_frames, progress_bar_style_of};
+use crate::tiny_render::{lesson_03_remove_hidden_faces::rasterize_3d, Canvas, Model};
+
+#[cfg(test)]
+use std::fs;
+
+/// Anything drawn as a set of 3D line segments
+pub trait Wireframe {
+    /// Both ends of every edge, in world coordinates
+    fn edges(&self) -> Vec<(Vector4<f64>, Vector4<f64>)>;
+
+    fn vertices_mut(&mut self) -> &mut [Vector4<f64>];
+}
+
+/// Apply `m` to every vertex of the wireframe
+pub fn transform<W: Wireframe + ?Sized>(wire: &mut W, m: &Matrix4<f64>) {
+    for v in wire.vertices_mut() {
+        *v = m * *v;
+    }
+}
+
+/// Rotation of `theta` around `axis` through the origin, counterclockwise looking from its tip
+///
+/// Rodrigues' formula, `R = I + sin(theta) K + (1 - cos(theta)) K^2` with `K` the cross product
+/// matrix of the normalized axis
+pub fn rotation_about_axis(axis: Vector3<f64>, theta: f64) -> Matrix4<f64> {
+    let k = axis.normalize().cross_matrix();
+    let rot = Matrix3::identity() + theta.sin() * k + (1. - theta.cos()) * k * k;
+
+    rot.to_homogeneous()
+}
+
+/// Rotation of `theta` around the line going through `center` along `axis`
+pub fn rotation_around_axis(theta: f64, axis: Vector3<f64>, center: Vector4<f64>) -> Matrix4<f64> {
+    let center = center.xyz();
+
+    Matrix4::new_translation(&center)
+        * rotation_about_axis(axis, theta)
+        * Matrix4::new_translation(&-center)
+}
+
+#[derive(Clone, Debug)]
 pub struct Box3D {
     // NOTE: world coordinates
     vertices: Vec<Vector4<f64>>,
@@ -36,42 +82,28 @@ impl Box3D {
         self.vertices[4]
     }
 
+    /// Rotate around the y axis through the origin
     pub fn rotate(&mut self, theta: f64) {
-        let rot = matrix![
-            theta.cos(), 0., theta.sin(), 0.;
-            0., 1., 0., 0.;
-            -theta.sin(), 0., theta.cos(), 0.;
-            0., 0., 0., 1.;
-        ];
-        for v in &mut self.vertices {
-            *v = rot * *v;
-        }
+        transform(self, &rotation_about_axis(Vector3::y(), theta));
     }
 
-    pub fn rotate_aroud_axis(&mut self, theta: f64, axis: Vector3<f64>) {
-        let w = axis.normalize();
-        let t = Vector3::new(w.x, w.y, w.z + 1.);
-        let u = t.cross(&w).normalize();
-        let v = w.cross(&u).normalize();
-        let w = Vector4::new(w.x, w.y, w.z, 0.);
-        let v = Vector4::new(v.x, v.y, v.z, 0.);
-        let u = Vector4::new(u.x, u.y, u.z, 0.);
-        let d_low_high = self.high() - self.low();
-        let cube_center = self.low() + d_low_high / 2.;
-        let world_to_local = Matrix4::from_columns(&[u, v, w, cube_center]);
-        let local_to_world = world_to_local.try_inverse().unwrap();
-        let rot = matrix![
-            theta.cos(), -theta.sin(), 0.0, 0.0;
-            theta.sin(), theta.cos(),  0.0, 0.0;
-            0.0,         0.0,          1.0, 0.0;
-            0.0,         0.0,          0.0, 1.0;
-        ];
+    pub fn center(&self) -> Vector4<f64> {
+        self.low() + (self.high() - self.low()) / 2.
+    }
 
-        for v in &mut self.vertices {
-            *v = world_to_local * rot * local_to_world * *v;
-        }
+    /// Rotate around `axis` through the center of the box
+    pub fn rotate_around_axis(&mut self, theta: f64, axis: Vector3<f64>) {
+        let m = rotation_around_axis(theta, axis, self.center());
+        transform(self, &m);
     }
 
+    #[deprecated(note = "use `rotate_around_axis`")]
+    pub fn rotate_aroud_axis(&mut self, theta: f64, axis: Vector3<f64>) {
+        self.rotate_around_axis(theta, axis);
+    }
+}
+
+impl Wireframe for Box3D {
     fn edges(&self) -> Vec<(Vector4<f64>, Vector4<f64>)> {
         let mut edges = Vec::new();
         let edges_indices = [
@@ -95,6 +127,94 @@ impl Box3D {
 
         edges
     }
+
+    fn vertices_mut(&mut self) -> &mut [Vector4<f64>] {
+        &mut self.vertices
+    }
+}
+
+/// Wireframe of a triangle mesh, every edge shared by several faces is drawn once
+pub struct WireMesh {
+    vertices: Vec<Vector4<f64>>,
+    edges: Vec<(usize, usize)>,
+}
+
+impl WireMesh {
+    pub fn from_model(model: &Model) -> Self {
+        let vertices = model
+            .vertices
+            .iter()
+            .map(|&(x, y, z)| Vector4::new(x, y, z, 1.))
+            .collect();
+
+        let mut edges = model
+            .faces
+            .iter()
+            .flat_map(|&(a, b, c)| [(a, b), (b, c), (c, a)])
+            .map(|(i, j)| (i.min(j), i.max(j)))
+            .collect::<Vec<_>>();
+        edges.sort_unstable();
+        edges.dedup();
+
+        Self { vertices, edges }
+    }
+
+    /// Center of the bounding box
+    pub fn center(&self) -> Vector4<f64> {
+        let (low, high) = self.vertices.iter().fold(
+            (Vector4::repeat(f64::MAX), Vector4::repeat(f64::MIN)),
+            |(low, high), v| (low.inf(v), high.sup(v)),
+        );
+
+        low + (high - low) / 2.
+    }
+}
+
+impl Wireframe for WireMesh {
+    fn edges(&self) -> Vec<(Vector4<f64>, Vector4<f64>)> {
+        self.edges
+            .iter()
+            .map(|&(i, j)| (self.vertices[i], self.vertices[j]))
+            .collect()
+    }
+
+    fn vertices_mut(&mut self) -> &mut [Vector4<f64>] {
+        &mut self.vertices
+    }
+}
+
+fn random_colors(n: usize) -> Vec<Rgb<u8>> {
+    let mut rng = rand::rng();
+
+    (0..n)
+        .map(|_| {
+            Rgb([
+                rng.random_range(0..255),
+                rng.random_range(0..255),
+                rng.random_range(0..255),
+            ])
+        })
+        .collect()
+}
+
+/// Where and how many frames of `Camera::save_rotation_box` are written
+#[derive(Clone, Debug)]
+pub struct RenderConfig {
+    /// Created if missing
+    pub dir: PathBuf,
+    /// Frames are named `{stem}_000.png`, `{stem}_001.png`, ...
+    pub stem: String,
+    pub frames: usize,
+}
+
+impl Default for RenderConfig {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("output/rotation"),
+            stem: "rotation_box".to_string(),
+            frames: 60,
+        }
+    }
 }
 
 pub struct Camera {
@@ -122,6 +242,21 @@ impl Default for Camera {
 }
 
 impl Camera {
+    /// Camera at `origin` looking at `target`, with +Y up
+    pub fn look_from(origin: Vector3<f64>, target: Vector3<f64>) -> Self {
+        let forward = (target - origin).normalize();
+        let right = forward.cross(&Vector3::y()).normalize();
+        let up = right.cross(&forward);
+
+        Self {
+            origin: origin.push(1.),
+            forward: forward.push(0.),
+            up: up.push(0.),
+            right: right.push(0.),
+            ..Self::default()
+        }
+    }
+
     fn world_to_cam_transform(&self) -> Matrix4<f64> {
         Matrix4::from_columns(&[self.right, self.up, -self.forward, self.origin])
     }
@@ -180,67 +315,172 @@ impl Camera {
         ]
     }
 
-    pub fn render(&self, width: usize, height: usize, bx: &Box3D) {
-        let mut img = image::RgbImage::new(width as u32, height as u32);
-        let mvp = self.view_port_transform(width as f64, height as f64);
+    /// Screen position of every vertex, y goes up and z is `-1/w`, the bigger the nearer
+    ///
+    /// `None` for the vertices behind the camera
+    pub fn project(
+        &self,
+        vertices: &[Vector4<f64>],
+        width: f64,
+        height: f64,
+    ) -> Vec<Option<Vector3<f64>>> {
+        let m = self.view_port_transform(width, height)
+            * self.orth_perspective_transform()
+            * self.cam_to_world_transform();
+
+        vertices
+            .iter()
+            .map(|v| {
+                let p = m * v;
+                // NOTE: w is the camera space z, negative in front of the camera
+                (p.w < 0.).then(|| Vector3::new(p.x / p.w, p.y / p.w, -1. / p.w))
+            })
+            .collect()
+    }
+
+    /// Fill the faces of `model`, lit from the camera, with the depth buffer of `canvas`
+    pub fn draw_model(&self, model: &Model, canvas: &mut Canvas) {
+        let vertices = model
+            .vertices
+            .iter()
+            .map(|&(x, y, z)| Vector4::new(x, y, z, 1.))
+            .collect::<Vec<_>>();
+        let projected = self.project(&vertices, canvas.width() as f64, canvas.height() as f64);
+
+        for &(a, b, c) in &model.faces {
+            let (Some(pa), Some(pb), Some(pc)) = (projected[a], projected[b], projected[c]) else {
+                continue;
+            };
+
+            // NOTE: same winding as the lessons, faces turned away get no light and are skipped
+            let [v0, v1, v2] = [a, b, c].map(|i| vertices[i].xyz());
+            let norm = (v2 - v0).cross(&(v1 - v0)).normalize();
+            let intensity = norm.dot(&self.forward.xyz());
+            if intensity <= 0. {
+                continue;
+            }
+
+            let gray = (255. * intensity).clamp(0., 255.) as u8;
+            rasterize_3d(&[pa, pb, pc], canvas, Rgb([gray; 3]));
+        }
+    }
+
+    /// Draw the edges of `wire` into `img`
+    pub fn render(&self, img: &mut RgbImage, wire: &dyn Wireframe) {
+        let (width, height) = (img.width() as f64, img.height() as f64);
+        let mvp = self.view_port_transform(width, height);
         let mper = self.orth_perspective_transform();
         let mcam = self.cam_to_world_transform();
 
-        for (p1, p2) in bx.edges() {
+        for (p1, p2) in wire.edges() {
             let p1_per = mvp * mper * mcam * p1;
             let p2_per = mvp * mper * mcam * p2;
             let p1_2d = ((p1_per.x / p1_per.w) as f32, (p1_per.y / p1_per.w) as f32);
             let p2_2d = ((p2_per.x / p2_per.w) as f32, (p2_per.y / p2_per.w) as f32);
 
-            draw_line_segment_mut(&mut img, p1_2d, p2_2d, Rgb([255, 255, 255]));
+            draw_line_segment_mut(img, p1_2d, p2_2d, Rgb([255, 255, 255]));
         }
 
-        flip_vertical_in_place(&mut img);
-        img.save("output/rotation_box.png").unwrap();
+        flip_vertical_in_place(img);
     }
 
-    fn draw_boxes(&self, img: &mut RgbImage, bx: &mut [Box3D], width: usize, height: usize) {
-        let mvp = self.view_port_transform(width as f64, height as f64);
+    /// Draw every wireframe into `img`, each with a random color
+    pub fn draw_wireframes(&self, img: &mut RgbImage, wires: &[&dyn Wireframe]) {
+        self.draw_wireframes_in(img, wires, &random_colors(wires.len()));
+    }
+
+    /// Draw every wireframe into `img`, `colors[i]` for the `i`th one
+    fn draw_wireframes_in(&self, img: &mut RgbImage, wires: &[&dyn Wireframe], colors: &[Rgb<u8>]) {
+        let mvp = self.view_port_transform(img.width() as f64, img.height() as f64);
         let mper = self.orth_perspective_transform();
         let mcam = self.cam_to_world_transform();
-        let mut rng = rand::rng();
 
-        for b in bx {
-            let random_color = Rgb([
-                rng.random_range(0..255),
-                rng.random_range(0..255),
-                rng.random_range(0..255),
-            ]);
-            for (p1, p2) in b.edges() {
+        for (w, &color) in wires.iter().zip(colors) {
+            for (p1, p2) in w.edges() {
                 let p1_per = mvp * mper * mcam * p1;
                 let p2_per = mvp * mper * mcam * p2;
                 let p1_2d = ((p1_per.x / p1_per.w) as f32, (p1_per.y / p1_per.w) as f32);
                 let p2_2d = ((p2_per.x / p2_per.w) as f32, (p2_per.y / p2_per.w) as f32);
 
-                draw_line_segment_mut(img, p1_2d, p2_2d, random_color);
+                draw_line_segment_mut(img, p1_2d, p2_2d, color);
             }
         }
     }
 
-    pub fn render_rotation_box(&self, width: usize, height: usize, bx: &mut [Box3D]) {
-        // frame is 60
-        for t in 0..=FRAME {
-            // bx.rotate(std::f64::consts::PI / 60.);
-            let mut img = image::RgbImage::new(width as u32, height as u32);
-            self.draw_boxes(&mut img, bx, width, height);
+    /// The boxes turned by `theta` around `axis` through their own centers
+    fn rotation_frame(
+        &self,
+        (width, height): (usize, usize),
+        bx: &[Box3D],
+        colors: &[Rgb<u8>],
+        axis: Vector3<f64>,
+        theta: f64,
+    ) -> RgbImage {
+        let turned = bx
+            .iter()
+            .map(|b| {
+                let mut b = b.clone();
+                b.rotate_around_axis(theta, axis);
+                b
+            })
+            .collect::<Vec<_>>();
+        let wires = turned
+            .iter()
+            .map(|b| b as &dyn Wireframe)
+            .collect::<Vec<_>>();
+
+        let mut img = RgbImage::new(width as u32, height as u32);
+        self.draw_wireframes_in(&mut img, &wires, colors);
+        flip_vertical_in_place(&mut img);
+        img
+    }
 
-            flip_vertical_in_place(&mut img);
-            img.save(format!("output/rotation/rotation_box_{t}.png"))
-                .unwrap();
+    /// Render `frames` frames of the boxes spinning a full turn around `axis`
+    ///
+    /// Frames are rendered in parallel, frame `k` turns its own copy of the boxes by `k` steps,
+    /// `bx` is left as it is. Each box keeps one random color in every frame
+    pub fn render_rotation_box(
+        &self,
+        width: usize,
+        height: usize,
+        bx: &[Box3D],
+        axis: Vector3<f64>,
+        frames: usize,
+    ) -> Vec<RgbImage> {
+        let colors = random_colors(bx.len());
+        self.rotation_frames((width, height), bx, &colors, axis, frames)
+    }
 
-            // bx.rotate(2. * std::f64::consts::PI / FRAME as f64);
-            for b in bx.iter_mut() {
-                b.rotate_aroud_axis(
-                    2. * std::f64::consts::PI / FRAME as f64,
-                    Vector3::new(1., 1., -1.),
-                );
-            }
-        }
+    /// `render_rotation_box` with the color of each box given
+    fn rotation_frames(
+        &self,
+        size: (usize, usize),
+        bx: &[Box3D],
+        colors: &[Rgb<u8>],
+        axis: Vector3<f64>,
+        frames: usize,
+    ) -> Vec<RgbImage> {
+        let step = 2. * PI / frames as f64;
+
+        (0..frames)
+            .into_par_iter()
+            .progress_with_style(progress_bar_style_of("frames"))
+            .map(|k| self.rotation_frame(size, bx, colors, axis, k as f64 * step))
+            .collect()
+    }
+
+    /// `render_rotation_box` saved as `config` says, return the paths of the frames
+    pub fn save_rotation_box(
+        &self,
+        width: usize,
+        height: usize,
+        bx: &[Box3D],
+        axis: Vector3<f64>,
+        config: &RenderConfig,
+    ) -> Result<Vec<PathBuf>> {
+        let frames = self.render_rotation_box(width, height, bx, axis, config.frames);
+
+        save_frames(&frames, &config.dir, &config.stem)
     }
 }
 
@@ -257,7 +497,10 @@ fn test_render() {
         Vector4::new(2., 2., -11., 1.),
     );
 
-    camera.render(800, 800, &bx);
+    let mut img = RgbImage::new(800, 800);
+    camera.render(&mut img, &bx);
+    fs::create_dir_all("output").unwrap();
+    img.save("output/rotation_box.png").unwrap();
 }
 
 #[test]
@@ -268,12 +511,37 @@ fn test_render_rotation_box() {
         forward: Vector4::new(0., -theta.sin(), -theta.cos(), 0.),
         ..Camera::default()
     };
-    let mut boxes = vec![Box3D::new(
+    let boxes = vec![Box3D::new(
+        Vector4::new(-2., -2., -2., 1.),
+        Vector4::new(2., 2., 2., 1.),
+    )];
+
+    let frames = camera.render_rotation_box(200, 200, &boxes, Vector3::new(1., 1., -1.), 60);
+    save_frames(&frames, "output/rotation", "rotation_box").unwrap();
+}
+
+#[test]
+fn test_save_rotation_box() {
+    let camera = Camera::look_from(Vector3::new(0., 0., 10.), Vector3::zeros());
+    let boxes = vec![Box3D::new(
         Vector4::new(-2., -2., -2., 1.),
         Vector4::new(2., 2., 2., 1.),
     )];
+    let dir = std::env::temp_dir().join("tiny_computer_graphics_save_rotation_box");
+    let _ = fs::remove_dir_all(&dir);
+    let config = RenderConfig {
+        dir: dir.clone(),
+        stem: "spin".to_string(),
+        frames: 2,
+    };
 
-    camera.render_rotation_box(200, 200, &mut boxes);
+    let paths = camera
+        .save_rotation_box(32, 32, &boxes, Vector3::y(), &config)
+        .unwrap();
+
+    assert_eq!(paths, [dir.join("spin_000.png"), dir.join("spin_001.png")]);
+    assert!(paths.iter().all(|p| p.exists()));
+    assert!(!dir.join("spin_002.png").exists());
 }
 
 #[test]
@@ -284,7 +552,7 @@ fn test_render_rotation_box_around_axis() {
         forward: Vector4::new(0., -theta.sin(), -theta.cos(), 0.),
         ..Camera::default()
     };
-    let mut boxes = vec![
+    let boxes = vec![
         Box3D::new(
             Vector4::new(-8., -12., -15., 1.),
             Vector4::new(-4., -8., -11., 1.),
@@ -303,7 +571,173 @@ fn test_render_rotation_box_around_axis() {
         ),
     ];
 
-    camera.render_rotation_box(200, 200, &mut boxes);
+    let frames = camera.render_rotation_box(200, 200, &boxes, Vector3::new(1., 1., -1.), 60);
+    save_frames(&frames, "output/rotation", "rotation_box").unwrap();
+}
+
+#[test]
+fn test_render_rotation_box_in_memory() {
+    let camera = Camera {
+        origin: Vector4::new(0., 0., 10., 1.),
+        ..Camera::default()
+    };
+    let boxes = vec![Box3D::new(
+        Vector4::new(-2., -2., -2., 1.),
+        Vector4::new(2., 2., 2., 1.),
+    )];
+
+    let frames = camera.render_rotation_box(64, 48, &boxes, Vector3::new(0., 1., 0.), 4);
+
+    assert_eq!(frames.len(), 4);
+    for frame in &frames {
+        assert_eq!(frame.dimensions(), (64, 48));
+        assert!(frame.pixels().any(|p| *p != Rgb([0, 0, 0])));
+    }
+    // NOTE: a quarter turn around y maps the cube onto itself, only the random color changes
+    let lit = |img: &RgbImage| {
+        img.pixels()
+            .map(|p| *p != Rgb([0, 0, 0]))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(lit(&frames[0]), lit(&frames[1]));
+}
+
+#[test]
+fn test_parallel_frames_match_sequential_rotation() {
+    let camera = Camera::look_from(Vector3::new(5., 3., 10.), Vector3::zeros());
+    let boxes = vec![
+        Box3D::new(
+            Vector4::new(-3., -1., -2., 1.),
+            Vector4::new(1., 2., 1., 1.),
+        ),
+        Box3D::new(Vector4::new(1., 1., 1., 1.), Vector4::new(2., 3., 2., 1.)),
+    ];
+    let axis = Vector3::new(1., 1., -1.);
+    let frames = 12;
+
+    let colors = random_colors(boxes.len());
+    let parallel = camera.rotation_frames((96, 96), &boxes, &colors, axis, frames);
+
+    // NOTE: the old way, one shared copy of the boxes turned a step after every frame
+    let mut turning = boxes.clone();
+    assert_eq!(parallel.len(), frames);
+    for (k, frame) in parallel.iter().enumerate() {
+        let mut img = RgbImage::new(96, 96);
+        let wires = turning
+            .iter()
+            .map(|b| b as &dyn Wireframe)
+            .collect::<Vec<_>>();
+        camera.draw_wireframes_in(&mut img, &wires, &colors);
+        flip_vertical_in_place(&mut img);
+        assert_eq!(*frame, img, "frame {k}");
+
+        for b in turning.iter_mut() {
+            b.rotate_around_axis(2. * PI / frames as f64, axis);
+        }
+    }
+    // NOTE: the boxes passed in are left as they are
+    let untouched = Box3D::new(
+        Vector4::new(-3., -1., -2., 1.),
+        Vector4::new(1., 2., 1., 1.),
+    );
+    assert_eq!(boxes[0].edges(), untouched.edges());
+}
+
+#[test]
+fn test_wire_mesh_dedup_edges() {
+    // NOTE: a quad split into two triangles shares its diagonal
+    let model = Model {
+        vertices: vec![(0., 0., 0.), (1., 0., 0.), (1., 1., 0.), (0., 1., 0.)],
+        faces: vec![(0, 1, 2), (0, 2, 3)],
+    };
+    let mesh = WireMesh::from_model(&model);
+
+    assert_eq!(mesh.edges().len(), 5);
+    assert_eq!(mesh.center(), Vector4::new(0.5, 0.5, 0., 1.));
+}
+
+#[test]
+fn test_render_rotating_head() {
+    let model = Model::load_model("obj/head.obj").unwrap();
+    let mut head = WireMesh::from_model(&model);
+    transform(
+        &mut head,
+        &Matrix4::new_translation(&Vector3::new(0., 0., -4.)),
+    );
+
+    let camera = Camera::default();
+    let center = head.center();
+    for _ in 0..4 {
+        let mut img = RgbImage::new(200, 200);
+        camera.draw_wireframes(&mut img, &[&head]);
+        assert!(img.pixels().any(|p| *p != Rgb([0, 0, 0])));
+
+        transform(
+            &mut head,
+            &rotation_around_axis(std::f64::consts::FRAC_PI_2, Vector3::y(), center),
+        );
+    }
+}
+
+#[test]
+fn test_draw_head_through_camera() {
+    let model = Model::load_model("obj/head.obj").unwrap();
+    let camera = Camera::look_from(Vector3::new(2., 1., 3.), Vector3::zeros());
+
+    // NOTE: the target lands in the middle of the screen, nearer vertices get bigger z
+    let projected = camera.project(
+        &[Vector4::new(0., 0., 0., 1.), Vector4::new(1., 0.5, 1.5, 1.)],
+        200.,
+        100.,
+    );
+    let (center, near) = (projected[0].unwrap(), projected[1].unwrap());
+    assert!((center.xy() - nalgebra::Vector2::new(99.5, 49.5)).norm() < 1e-9);
+    assert!(near.z > center.z);
+
+    let mut canvas = Canvas::new(400, 400);
+    camera.draw_model(&model, &mut canvas);
+
+    assert!(canvas.image().pixels().any(|p| *p != Rgb([0, 0, 0])));
+    fs::create_dir_all("output").unwrap();
+    canvas
+        .save_flipped("output/head_through_camera.png")
+        .unwrap();
+}
+
+#[test]
+fn test_rotation_about_axis() {
+    let quarter = rotation_about_axis(Vector3::z(), std::f64::consts::FRAC_PI_2);
+    let p = quarter * Vector4::new(1., 2., 3., 1.);
+    assert!((p - Vector4::new(-2., 1., 3., 1.)).norm() < 1e-12, "{p}");
+
+    // NOTE: the axis doesn't need to be normalized
+    let tilted = rotation_about_axis(Vector3::new(1., 1., 1.), 2. * std::f64::consts::PI / 3.);
+    let p = tilted * Vector4::new(1., 0., 0., 1.);
+    assert!((p - Vector4::new(0., 1., 0., 1.)).norm() < 1e-12, "{p}");
+
+    // NOTE: a full turn around any axis through the center puts every vertex back
+    let mut bx = Box3D::new(
+        Vector4::new(-1., 2., -7., 1.),
+        Vector4::new(3., 4., -5., 1.),
+    );
+    let before = bx.vertices.clone();
+    bx.rotate_around_axis(2. * std::f64::consts::PI, Vector3::new(1., -2., 0.5));
+    for (a, b) in before.iter().zip(&bx.vertices) {
+        assert!((a - b).norm() < 1e-9, "{a} vs {b}");
+    }
+
+    // NOTE: same as the matrix `rotate` used to build by hand
+    let theta = 0.3;
+    bx.rotate(theta);
+    let by_hand = matrix![
+        theta.cos(), 0., theta.sin(), 0.;
+        0., 1., 0., 0.;
+        -theta.sin(), 0., theta.cos(), 0.;
+        0., 0., 0., 1.;
+    ];
+    for (a, b) in before.iter().zip(&bx.vertices) {
+        assert!((by_hand * a - b).norm() < 1e-9);
+    }
 }
 
 #[test]
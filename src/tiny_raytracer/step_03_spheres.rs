@@ -92,15 +92,25 @@ pub fn scene_intersect<'a>(
     ret
 }
 
-pub fn cast_ray(orig: &Vector3<f64>, ray_dir: &Vector3<f64>, spheres: &[Sphere]) -> Vector3<f64> {
+pub fn cast_ray_with_background(
+    orig: &Vector3<f64>,
+    ray_dir: &Vector3<f64>,
+    spheres: &[Sphere],
+    background: &Vector3<f64>,
+) -> Vector3<f64> {
     let Some((sphere, _hit_point)) = scene_intersect(orig, ray_dir, spheres) else {
-        return BACKGROUND_COLOR;
+        return *background;
     };
 
     sphere.mat.diffuse_color
 }
 
-pub fn render<I>(img: &mut I, spheres: &[Sphere])
+/// Same as `cast_ray_with_background`, with the default `BACKGROUND_COLOR`
+pub fn cast_ray(orig: &Vector3<f64>, ray_dir: &Vector3<f64>, spheres: &[Sphere]) -> Vector3<f64> {
+    cast_ray_with_background(orig, ray_dir, spheres, &BACKGROUND_COLOR)
+}
+
+pub fn render_with_background<I>(img: &mut I, spheres: &[Sphere], background: &Vector3<f64>)
 where
     I: GenericImage<Pixel = Rgb<u8>>,
 {
@@ -113,12 +123,21 @@ where
         for j in 0..height {
             let (x, y) = pixel_to_world(i, j, width, height, FOV, Z);
             let ray_dir = Vector3::new(x, y, -1.).normalize();
-            let color = cast_ray(&Vector3::new(0., 0., 0.), &ray_dir, spheres);
+            let color =
+                cast_ray_with_background(&Vector3::new(0., 0., 0.), &ray_dir, spheres, background);
             img.put_pixel(i, j, v3_to_rgb(color));
         }
     }
 }
 
+/// Same as `render_with_background`, with the default `BACKGROUND_COLOR`
+pub fn render<I>(img: &mut I, spheres: &[Sphere])
+where
+    I: GenericImage<Pixel = Rgb<u8>>,
+{
+    render_with_background(img, spheres, &BACKGROUND_COLOR);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -9,39 +9,68 @@ use nalgebra::Vector3;
 pub struct Light {
     pub position: Vector3<f64>,
     pub intensity: f64,
+    // NOTE: 0 means no falloff with the distance
+    pub attenuation: f64,
 }
 
 impl Light {
     pub fn new(position: Vector3<f64>, intensity: f64) -> Self {
+        Self::with_attenuation(position, intensity, 0.)
+    }
+
+    /// Light fading as `1 / (1 + k * d²)` with the distance `d`
+    pub fn with_attenuation(position: Vector3<f64>, intensity: f64, k: f64) -> Self {
         Self {
             position,
             intensity,
+            attenuation: k,
         }
     }
+
+    /// The intensity reaching `point`
+    pub fn intensity_at(&self, point: &Vector3<f64>) -> f64 {
+        let dist = (self.position - point).magnitude();
+        self.intensity / (1. + self.attenuation * dist.powi(2))
+    }
 }
 
-pub fn cast_ray(
+pub fn cast_ray_with_background(
     orig: &Vector3<f64>,
     ray_dir: &Vector3<f64>,
     spheres: &[Sphere],
     lights: &[Light],
+    background: &Vector3<f64>,
 ) -> Vector3<f64> {
     let Some((sphere, hit_point)) = scene_intersect(orig, ray_dir, spheres) else {
-        return BACKGROUND_COLOR;
+        return *background;
     };
 
     let mut diffuse_light_intensity = 0.;
     for light in lights {
         let light_dir = (light.position - hit_point).normalize();
         let norm = (hit_point - sphere.center).normalize();
-        diffuse_light_intensity += light.intensity * light_dir.dot(&norm).max(0.);
+        diffuse_light_intensity += light.intensity_at(&hit_point) * light_dir.dot(&norm).max(0.);
     }
 
     sphere.mat.diffuse_color * diffuse_light_intensity
 }
 
-pub fn render<I>(img: &mut I, spheres: &[Sphere], lights: &[Light])
-where
+/// Same as `cast_ray_with_background`, with the default `BACKGROUND_COLOR`
+pub fn cast_ray(
+    orig: &Vector3<f64>,
+    ray_dir: &Vector3<f64>,
+    spheres: &[Sphere],
+    lights: &[Light],
+) -> Vector3<f64> {
+    cast_ray_with_background(orig, ray_dir, spheres, lights, &BACKGROUND_COLOR)
+}
+
+pub fn render_with_background<I>(
+    img: &mut I,
+    spheres: &[Sphere],
+    lights: &[Light],
+    background: &Vector3<f64>,
+) where
     I: GenericImage<Pixel = Rgb<u8>>,
 {
     let width = img.width();
@@ -55,12 +84,20 @@ where
         for j in 0..height {
             let (x, y) = pixel_to_world(i, j, width, height, FOV, Z);
             let ray_dir = Vector3::new(x, y, -1.).normalize();
-            let color = cast_ray(&orig, &ray_dir, spheres, lights);
+            let color = cast_ray_with_background(&orig, &ray_dir, spheres, lights, background);
             img.put_pixel(i, j, v3_to_rgb(color));
         }
     }
 }
 
+/// Same as `render_with_background`, with the default `BACKGROUND_COLOR`
+pub fn render<I>(img: &mut I, spheres: &[Sphere], lights: &[Light])
+where
+    I: GenericImage<Pixel = Rgb<u8>>,
+{
+    render_with_background(img, spheres, lights, &BACKGROUND_COLOR);
+}
+
 #[cfg(test)]
 mod tests {
     use super::super::step_03_spheres::Material;
@@ -118,14 +118,15 @@ pub fn scene_intersect<'a>(
     ret
 }
 
-pub fn cast_ray(
+pub fn cast_ray_with_background(
     orig: &Vector3<f64>,
     ray_dir: &Vector3<f64>,
     spheres: &[Sphere],
     lights: &[Light],
+    background: &Vector3<f64>,
 ) -> Vector3<f64> {
     let Some((sphere, hit_point)) = scene_intersect(orig, ray_dir, spheres) else {
-        return BACKGROUND_COLOR;
+        return *background;
     };
 
     let mut diffuse_light_intensity = 0.;
@@ -146,8 +147,9 @@ pub fn cast_ray(
             .dot(&reverse_reflect_light_dir)
             .max(0.)
             .powf(sphere.mat.specular_exponent);
-        diffuse_light_intensity += light.intensity * light_dir.dot(&norm).max(0.);
-        specular_light_intensity += light.intensity * to_expo;
+        let intensity = light.intensity_at(&hit_point);
+        diffuse_light_intensity += intensity * light_dir.dot(&norm).max(0.);
+        specular_light_intensity += intensity * to_expo;
     }
 
     let albedo = sphere.mat.albedo;
@@ -157,8 +159,22 @@ pub fn cast_ray(
         + white * specular_light_intensity * albedo.y
 }
 
-pub fn render<I>(img: &mut I, spheres: &[Sphere], lights: &[Light])
-where
+/// Same as `cast_ray_with_background`, with the default `BACKGROUND_COLOR`
+pub fn cast_ray(
+    orig: &Vector3<f64>,
+    ray_dir: &Vector3<f64>,
+    spheres: &[Sphere],
+    lights: &[Light],
+) -> Vector3<f64> {
+    cast_ray_with_background(orig, ray_dir, spheres, lights, &BACKGROUND_COLOR)
+}
+
+pub fn render_with_background<I>(
+    img: &mut I,
+    spheres: &[Sphere],
+    lights: &[Light],
+    background: &Vector3<f64>,
+) where
     I: GenericImage<Pixel = Rgb<u8>>,
 {
     let width = img.width();
@@ -189,14 +205,27 @@ where
         for j in 0..height {
             let (x, y) = pixel_to_world(i, j, width, height, FOV, Z);
             let ray_dir = Vector3::new(x, y, -1.).normalize();
-            let color = cast_ray(&orig, &ray_dir, spheres, lights);
+            let color = cast_ray_with_background(&orig, &ray_dir, spheres, lights, background);
 
             img.put_pixel(i, j, v3_to_rgb(color));
         }
     }
 }
 
-pub fn multi_thread_render(img: &mut RgbImage, spheres: &[Sphere], lights: &[Light]) {
+/// Same as `render_with_background`, with the default `BACKGROUND_COLOR`
+pub fn render<I>(img: &mut I, spheres: &[Sphere], lights: &[Light])
+where
+    I: GenericImage<Pixel = Rgb<u8>>,
+{
+    render_with_background(img, spheres, lights, &BACKGROUND_COLOR);
+}
+
+pub fn multi_thread_render_with_background(
+    img: &mut RgbImage,
+    spheres: &[Sphere],
+    lights: &[Light],
+    background: &Vector3<f64>,
+) {
     let width = img.width();
     let height = img.height();
     // NOTE:
@@ -226,12 +255,17 @@ pub fn multi_thread_render(img: &mut RgbImage, spheres: &[Sphere], lights: &[Lig
         let y = idx as u32 / width;
         let (x, y) = pixel_to_world(x, y, width, height, FOV, Z);
         let ray_dir = Vector3::new(x, y, -1.).normalize();
-        let color = cast_ray(&orig, &ray_dir, spheres, lights);
+        let color = cast_ray_with_background(&orig, &ray_dir, spheres, lights, background);
 
         *pixel = v3_to_rgb(color);
     });
 }
 
+/// Same as `multi_thread_render_with_background`, with the default `BACKGROUND_COLOR`
+pub fn multi_thread_render(img: &mut RgbImage, spheres: &[Sphere], lights: &[Light]) {
+    multi_thread_render_with_background(img, spheres, lights, &BACKGROUND_COLOR);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
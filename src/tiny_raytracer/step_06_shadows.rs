@@ -13,14 +13,15 @@ use super::step_05_specular_lighting::{reflection, scene_intersect, Sphere};
 use super::{pixel_to_world, BACKGROUND_COLOR, FOV, Z};
 
 #[allow(non_snake_case)]
-pub fn cast_ray(
+pub fn cast_ray_with_background(
     orig: &Vector3<f64>,
     ray_dir: &Vector3<f64>,
     spheres: &[Sphere],
     lights: &[Light],
+    background: &Vector3<f64>,
 ) -> Vector3<f64> {
     let Some((sphere, hit_point)) = scene_intersect(orig, ray_dir, spheres) else {
-        return BACKGROUND_COLOR;
+        return *background;
     };
 
     let mut diffuse_light_intensity = 0.;
@@ -58,8 +59,9 @@ pub fn cast_ray(
             .dot(&reverse_reflect_light_dir)
             .max(0.)
             .powf(sphere.mat.specular_exponent);
-        diffuse_light_intensity += light.intensity * light_dir.dot(&N).max(0.);
-        specular_light_intensity += light.intensity * to_expo;
+        let intensity = light.intensity_at(&hit_point);
+        diffuse_light_intensity += intensity * light_dir.dot(&N).max(0.);
+        specular_light_intensity += intensity * to_expo;
     }
 
     let albedo = sphere.mat.albedo;
@@ -69,7 +71,22 @@ pub fn cast_ray(
         + white * specular_light_intensity * albedo.y
 }
 
-pub fn multi_thread_render(img: &mut RgbImage, spheres: &[Sphere], lights: &[Light]) {
+/// Same as `cast_ray_with_background`, with the default `BACKGROUND_COLOR`
+pub fn cast_ray(
+    orig: &Vector3<f64>,
+    ray_dir: &Vector3<f64>,
+    spheres: &[Sphere],
+    lights: &[Light],
+) -> Vector3<f64> {
+    cast_ray_with_background(orig, ray_dir, spheres, lights, &BACKGROUND_COLOR)
+}
+
+pub fn multi_thread_render_with_background(
+    img: &mut RgbImage,
+    spheres: &[Sphere],
+    lights: &[Light],
+    background: &Vector3<f64>,
+) {
     let width = img.width();
     let height = img.height();
     // NOTE:
@@ -99,12 +116,17 @@ pub fn multi_thread_render(img: &mut RgbImage, spheres: &[Sphere], lights: &[Lig
         let y = idx as u32 / width;
         let (x, y) = pixel_to_world(x, y, width, height, FOV, Z);
         let ray_dir = Vector3::new(x, y, -1.).normalize();
-        let color = cast_ray(&orig, &ray_dir, spheres, lights);
+        let color = cast_ray_with_background(&orig, &ray_dir, spheres, lights, background);
 
         *pixel = v3_to_rgb(color);
     });
 }
 
+/// Same as `multi_thread_render_with_background`, with the default `BACKGROUND_COLOR`
+pub fn multi_thread_render(img: &mut RgbImage, spheres: &[Sphere], lights: &[Light]) {
+    multi_thread_render_with_background(img, spheres, lights, &BACKGROUND_COLOR);
+}
+
 #[cfg(test)]
 mod tests {
     use super::super::step_05_specular_lighting::Material;
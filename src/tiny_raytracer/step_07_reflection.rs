@@ -98,19 +98,20 @@ pub fn scene_intersect<'a>(
 }
 
 #[allow(non_snake_case)]
-pub fn cast_ray(
+pub fn cast_ray_with_background(
     orig: &Vector3<f64>,
     ray_dir: &Vector3<f64>,
     spheres: &[Sphere],
     lights: &[Light],
     depth: usize,
+    background: &Vector3<f64>,
 ) -> Vector3<f64> {
     if depth > REFLECT_DEPTH {
-        return BACKGROUND_COLOR;
+        return *background;
     }
 
     let Some((sphere, hit_point)) = scene_intersect(orig, ray_dir, spheres) else {
-        return BACKGROUND_COLOR;
+        return *background;
     };
 
     let N = (hit_point - sphere.center).normalize();
@@ -124,7 +125,14 @@ pub fn cast_ray(
         hit_point - N * 1e-3
     };
 
-    let reflect_color = cast_ray(&reflect_orig, &reflect_dir, spheres, lights, depth + 1);
+    let reflect_color = cast_ray_with_background(
+        &reflect_orig,
+        &reflect_dir,
+        spheres,
+        lights,
+        depth + 1,
+        background,
+    );
 
     let mut diffuse_light_intensity = 0.;
     let mut specular_light_intensity = 0.;
@@ -160,8 +168,9 @@ pub fn cast_ray(
             .dot(&reverse_reflect_light_dir)
             .max(0.)
             .powf(sphere.mat.specular_exponent);
-        diffuse_light_intensity += light.intensity * light_dir.dot(&N).max(0.);
-        specular_light_intensity += light.intensity * to_expo;
+        let intensity = light.intensity_at(&hit_point);
+        diffuse_light_intensity += intensity * light_dir.dot(&N).max(0.);
+        specular_light_intensity += intensity * to_expo;
     }
 
     let albedo = sphere.mat.albedo;
@@ -172,7 +181,23 @@ pub fn cast_ray(
         + reflect_color * albedo.z
 }
 
-pub fn multi_thread_render(img: &mut RgbImage, spheres: &[Sphere], lights: &[Light]) {
+/// Same as `cast_ray_with_background`, with the default `BACKGROUND_COLOR`
+pub fn cast_ray(
+    orig: &Vector3<f64>,
+    ray_dir: &Vector3<f64>,
+    spheres: &[Sphere],
+    lights: &[Light],
+    depth: usize,
+) -> Vector3<f64> {
+    cast_ray_with_background(orig, ray_dir, spheres, lights, depth, &BACKGROUND_COLOR)
+}
+
+pub fn multi_thread_render_with_background(
+    img: &mut RgbImage,
+    spheres: &[Sphere],
+    lights: &[Light],
+    background: &Vector3<f64>,
+) {
     let width = img.width();
     let height = img.height();
     // NOTE:
@@ -202,12 +227,17 @@ pub fn multi_thread_render(img: &mut RgbImage, spheres: &[Sphere], lights: &[Lig
         let y = idx as u32 / width;
         let (x, y) = pixel_to_world(x, y, width, height, FOV, Z);
         let ray_dir = Vector3::new(x, y, -1.).normalize();
-        let color = cast_ray(&orig, &ray_dir, spheres, lights, 0);
+        let color = cast_ray_with_background(&orig, &ray_dir, spheres, lights, 0, background);
 
         *pixel = v3_to_rgb(color);
     });
 }
 
+/// Same as `multi_thread_render_with_background`, with the default `BACKGROUND_COLOR`
+pub fn multi_thread_render(img: &mut RgbImage, spheres: &[Sphere], lights: &[Light]) {
+    multi_thread_render_with_background(img, spheres, lights, &BACKGROUND_COLOR);
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -253,4 +283,31 @@ mod test {
         multi_thread_render(&mut img, &spheres, &lights);
         img.save("output/ray_tracing_step_7_scene.tga").unwrap();
     }
+
+    #[test]
+    fn test_render_with_black_background() {
+        let ivory = Material {
+            diffuse_color: Vector3::new(0.4, 0.4, 0.3),
+            albedo: Vector3::new(0.6, 0.3, 0.1),
+            specular_exponent: 50.,
+        };
+        let spheres = [Sphere::new(Vector3::new(0., 0., -16.), 2., ivory)];
+        let lights = [Light::new(Vector3::new(-20., 20., 20.), 1.5)];
+        let black = Vector3::zeros();
+
+        // NOTE: a ray pointing away from every sphere sees the background
+        let orig = Vector3::zeros();
+        let away = Vector3::new(0., 0., 1.);
+        let color = cast_ray_with_background(&orig, &away, &spheres, &lights, 0, &black);
+        assert_eq!(color, black);
+        assert_eq!(
+            cast_ray(&orig, &away, &spheres, &lights, 0),
+            BACKGROUND_COLOR
+        );
+
+        let mut img = RgbImage::new(64, 48);
+        multi_thread_render_with_background(&mut img, &spheres, &lights, &black);
+        assert_eq!(*img.get_pixel(0, 0), Rgb([0, 0, 0]));
+        assert_ne!(*img.get_pixel(32, 24), Rgb([0, 0, 0]));
+    }
 }
@@ -2,6 +2,8 @@ use image::{Pixel, Rgb, RgbImage};
 use nalgebra::{Vector3, Vector4};
 use rayon::prelude::*;
 
+use crate::raytracer::fresnel;
+
 use super::{
     pixel_to_world, step_04_lighting::Light, step_05_specular_lighting::reflection,
     BACKGROUND_COLOR, FOV, REFLECT_DEPTH, Z,
@@ -147,20 +149,21 @@ pub fn scene_intersect<'a>(
 }
 
 #[allow(non_snake_case)]
-pub fn cast_ray(
+pub fn cast_ray_with_background(
     orig: &Vector3<f64>,
     ray_dir: &Vector3<f64>,
     spheres: &[Sphere],
     lights: &[Light],
     depth: usize,
+    background: &Vector3<f64>,
 ) -> Vector3<f64> {
     if depth > REFLECT_DEPTH {
-        return BACKGROUND_COLOR;
+        return *background;
         // return Vector3::from_element(0.);
     }
 
     let Some((sphere, hit_point)) = scene_intersect(orig, ray_dir, spheres) else {
-        return BACKGROUND_COLOR;
+        return *background;
     };
 
     let N = (hit_point - sphere.center).normalize();
@@ -183,13 +186,41 @@ pub fn cast_ray(
         hit_point - N * 1e-3
     };
 
-    let reflect_color = if sphere.mat.albedo.z > 0. {
-        cast_ray(&reflect_orig, &reflect_dir, spheres, lights, depth + 1)
+    // NOTE: for dielectrics, split reflection and refraction by the fresnel reflectance
+    let mut albedo = sphere.mat.albedo;
+    if sphere.mat.refractive_index != 1. && albedo.w > 0. {
+        let cos_theta = -ray_dir.dot(&N);
+        let kr = if cos_theta < 0. {
+            fresnel(-cos_theta, sphere.mat.refractive_index, 1.)
+        } else {
+            fresnel(cos_theta, 1., sphere.mat.refractive_index)
+        };
+        let total = albedo.z + albedo.w;
+        albedo.z = kr * total;
+        albedo.w = (1. - kr) * total;
+    }
+
+    let reflect_color = if albedo.z > 0. {
+        cast_ray_with_background(
+            &reflect_orig,
+            &reflect_dir,
+            spheres,
+            lights,
+            depth + 1,
+            background,
+        )
     } else {
         Vector3::from_element(0.)
     };
-    let refract_color = if sphere.mat.albedo.w > 0. {
-        cast_ray(&refract_orig, &refract_dir, spheres, lights, depth + 1)
+    let refract_color = if albedo.w > 0. {
+        cast_ray_with_background(
+            &refract_orig,
+            &refract_dir,
+            spheres,
+            lights,
+            depth + 1,
+            background,
+        )
     } else {
         Vector3::from_element(0.)
     };
@@ -228,11 +259,11 @@ pub fn cast_ray(
             .dot(&reverse_reflect_light_dir)
             .max(0.)
             .powf(sphere.mat.specular_exponent);
-        diffuse_light_intensity += light.intensity * light_dir.dot(&N).max(0.);
-        specular_light_intensity += light.intensity * to_expo;
+        let intensity = light.intensity_at(&hit_point);
+        diffuse_light_intensity += intensity * light_dir.dot(&N).max(0.);
+        specular_light_intensity += intensity * to_expo;
     }
 
-    let albedo = sphere.mat.albedo;
     let white = Vector3::new(1., 1., 1.);
 
     sphere.mat.diffuse_color * diffuse_light_intensity * albedo.x
@@ -241,7 +272,23 @@ pub fn cast_ray(
         + refract_color * albedo.w
 }
 
-pub fn multi_thread_render(img: &mut RgbImage, spheres: &[Sphere], lights: &[Light]) {
+/// Same as `cast_ray_with_background`, with the default `BACKGROUND_COLOR`
+pub fn cast_ray(
+    orig: &Vector3<f64>,
+    ray_dir: &Vector3<f64>,
+    spheres: &[Sphere],
+    lights: &[Light],
+    depth: usize,
+) -> Vector3<f64> {
+    cast_ray_with_background(orig, ray_dir, spheres, lights, depth, &BACKGROUND_COLOR)
+}
+
+pub fn multi_thread_render_with_background(
+    img: &mut RgbImage,
+    spheres: &[Sphere],
+    lights: &[Light],
+    background: &Vector3<f64>,
+) {
     let width = img.width();
     let height = img.height();
     // NOTE:
@@ -271,12 +318,17 @@ pub fn multi_thread_render(img: &mut RgbImage, spheres: &[Sphere], lights: &[Lig
         let y = idx as u32 / width;
         let (x, y) = pixel_to_world(x, y, width, height, FOV, Z);
         let ray_dir = Vector3::new(x, y, -1.).normalize();
-        let color = cast_ray(&orig, &ray_dir, spheres, lights, 0);
+        let color = cast_ray_with_background(&orig, &ray_dir, spheres, lights, 0, background);
 
         *pixel = v3_to_rgb(color);
     });
 }
 
+/// Same as `multi_thread_render_with_background`, with the default `BACKGROUND_COLOR`
+pub fn multi_thread_render(img: &mut RgbImage, spheres: &[Sphere], lights: &[Light]) {
+    multi_thread_render_with_background(img, spheres, lights, &BACKGROUND_COLOR);
+}
+
 #[cfg(test)]
 mod tests {
     use std::f64::consts::PI;
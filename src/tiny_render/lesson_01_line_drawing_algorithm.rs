@@ -1,7 +1,59 @@
 use anyhow::{bail, Result};
-use image::GenericImage;
+use image::{GenericImage, Rgb};
 
-use std::{fs::File, io::BufRead, path::Path};
+use std::{fmt, fs, path::Path};
+
+/// Put a pixel, doing nothing when it falls outside the image
+pub fn put_pixel_clipped<I: GenericImage>(img: &mut I, x: i64, y: i64, pixel: I::Pixel) {
+    if (0..img.width() as i64).contains(&x) && (0..img.height() as i64).contains(&y) {
+        img.put_pixel(x as u32, y as u32, pixel);
+    }
+}
+
+/// Clip the segment `p0` `p1` to the image rectangle with Liang–Barsky
+///
+/// Return `None` when the segment is fully outside
+pub fn clip_line(
+    p0: (f64, f64),
+    p1: (f64, f64),
+    width: u32,
+    height: u32,
+) -> Option<((f64, f64), (f64, f64))> {
+    let (dx, dy) = (p1.0 - p0.0, p1.1 - p0.1);
+    let (x_max, y_max) = (width as f64 - 1.0, height as f64 - 1.0);
+    let (mut t0, mut t1) = (0.0f64, 1.0f64);
+
+    // NOTE: p * t <= q for each of the four edges, left right bottom top
+    for (p, q) in [
+        (-dx, p0.0),
+        (dx, x_max - p0.0),
+        (-dy, p0.1),
+        (dy, y_max - p0.1),
+    ] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+            continue;
+        }
+
+        let t = q / p;
+        if p < 0.0 {
+            t0 = t0.max(t);
+        } else {
+            t1 = t1.min(t);
+        }
+    }
+
+    if t0 > t1 {
+        return None;
+    }
+
+    Some((
+        (p0.0 + t0 * dx, p0.1 + t0 * dy),
+        (p0.0 + t1 * dx, p0.1 + t1 * dy),
+    ))
+}
 
 pub fn naive_draw_line<I: GenericImage>(
     x0: u32,
@@ -22,7 +74,7 @@ pub fn naive_draw_line<I: GenericImage>(
         t += 0.01;
         let x_prime = (x0 + t * (x1 - x0)).round();
         let y_prime = (y0 + t * (y1 - y0)).round();
-        img.put_pixel(x_prime as u32, y_prime as u32, pixel);
+        put_pixel_clipped(img, x_prime as i64, y_prime as i64, pixel);
     }
 }
 
@@ -34,8 +86,18 @@ pub fn draw_line<I: GenericImage>(
     img: &mut I,
     pixel: I::Pixel,
 ) {
+    let Some((p0, p1)) = clip_line(
+        (x0 as f64, y0 as f64),
+        (x1 as f64, y1 as f64),
+        img.width(),
+        img.height(),
+    ) else {
+        return;
+    };
+
     let mut steep = false;
-    let (mut x0, mut y0, mut x1, mut y1) = (x0 as i64, y0 as i64, x1 as i64, y1 as i64);
+    let (mut x0, mut y0) = (p0.0.round() as i64, p0.1.round() as i64);
+    let (mut x1, mut y1) = (p1.0.round() as i64, p1.1.round() as i64);
 
     if (x1 - x0).abs() < (y1 - y0).abs() {
         std::mem::swap(&mut x0, &mut y0);
@@ -56,9 +118,9 @@ pub fn draw_line<I: GenericImage>(
 
     for x in x0..=x1 {
         if steep {
-            img.put_pixel(y as u32, x as u32, pixel);
+            put_pixel_clipped(img, y, x, pixel);
         } else {
-            img.put_pixel(x as u32, y as u32, pixel);
+            put_pixel_clipped(img, x, y, pixel);
         }
         err2 += derr2;
 
@@ -69,6 +131,114 @@ pub fn draw_line<I: GenericImage>(
     }
 }
 
+/// Mix `fg` over `bg`, `coverage` is how much of the pixel `fg` covers
+fn blend(bg: Rgb<u8>, fg: Rgb<u8>, coverage: f64) -> Rgb<u8> {
+    let coverage = coverage.clamp(0.0, 1.0);
+    let mut out = bg;
+    for i in 0..3 {
+        out[i] = (bg[i] as f64 * (1.0 - coverage) + fg[i] as f64 * coverage).round() as u8;
+    }
+
+    out
+}
+
+/// Xiaolin Wu's line algorithm, each pixel gets blended by how much the line covers it
+///
+/// Pixels outside the image are clipped
+pub fn draw_line_aa<I>(x0: f64, y0: f64, x1: f64, y1: f64, img: &mut I, pixel: Rgb<u8>)
+where
+    I: GenericImage<Pixel = Rgb<u8>>,
+{
+    let steep = (y1 - y0).abs() > (x1 - x0).abs();
+    let (mut x0, mut y0, mut x1, mut y1) = (x0, y0, x1, y1);
+
+    // NOTE: walk along the major axis, x after swapping
+    if steep {
+        std::mem::swap(&mut x0, &mut y0);
+        std::mem::swap(&mut x1, &mut y1);
+    }
+
+    if x0 > x1 {
+        std::mem::swap(&mut x0, &mut x1);
+        std::mem::swap(&mut y0, &mut y1);
+    }
+
+    let (width, height) = (img.width() as i64, img.height() as i64);
+    let mut plot = |x: i64, y: i64, coverage: f64| {
+        let (x, y) = if steep { (y, x) } else { (x, y) };
+        if (0..width).contains(&x) && (0..height).contains(&y) {
+            let (x, y) = (x as u32, y as u32);
+            img.put_pixel(x, y, blend(img.get_pixel(x, y), pixel, coverage));
+        }
+    };
+
+    let dx = x1 - x0;
+    let gradient = if dx == 0.0 { 1.0 } else { (y1 - y0) / dx };
+
+    // NOTE: the endpoints only cover part of their pixel along x
+    let mut endpoint = |x: f64, y: f64, xgap: f64| {
+        let xend = x.round();
+        let yend = y + gradient * (xend - x);
+        let (px, py) = (xend as i64, yend.floor() as i64);
+        let fract = yend - yend.floor();
+
+        plot(px, py, (1.0 - fract) * xgap);
+        plot(px, py + 1, fract * xgap);
+
+        (px, yend)
+    };
+
+    let (xpxl1, yend1) = endpoint(x0, y0, 1.0 - (x0 + 0.5).fract());
+    let (xpxl2, _) = endpoint(x1, y1, (x1 + 0.5).fract());
+
+    // NOTE: only walk the part of the line inside the image
+    let major = if steep { height } else { width };
+    let start = (xpxl1 + 1).max(0);
+    let end = xpxl2.min(major);
+
+    for x in start..end {
+        let intery = yend1 + gradient * (x - xpxl1) as f64;
+        let y = intery.floor();
+        let fract = intery - y;
+
+        plot(x, y as i64, 1.0 - fract);
+        plot(x, y as i64 + 1, fract);
+    }
+}
+
+/// Every line of an .obj file that failed to parse, with its 1-based line number
+#[derive(Debug, Default)]
+pub struct ParseErrors(pub Vec<(usize, String)>);
+
+impl ParseErrors {
+    /// Record the error of line `line`, numbered from 1
+    pub fn push(&mut self, line: usize, err: anyhow::Error) {
+        self.0.push((line, format!("{err:#}")));
+    }
+
+    /// `Ok(value)` if no line failed
+    pub fn or_ok<T>(self, value: T) -> Result<T> {
+        if self.0.is_empty() {
+            return Ok(value);
+        }
+
+        Err(self.into())
+    }
+}
+
+impl fmt::Display for ParseErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} line(s) failed to parse", self.0.len())?;
+        for (line, err) in &self.0 {
+            write!(f, "\n  line {line}: {err}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseErrors {}
+
 pub struct Model {
     pub vertices: Vec<(f64, f64, f64)>,
     // faces stores index, (a, b, c) means ath, bth, and cth vertices form one fase
@@ -89,44 +259,72 @@ impl Model {
         Ok((parts[0], parts[1], parts[2]))
     }
 
-    fn parse_face(text: &str) -> Result<(usize, usize, usize)> {
-        let parts = text
-            .split_whitespace()
-            .filter_map(|nums| nums.split('/').next())
-            .filter_map(|num| num.parse::<usize>().ok()) // NOTE: in .obj file, the index starts from 1
-            .map(|n| n - 1)
-            .collect::<Vec<_>>();
+    /// Parse a face line, polygons with more than 3 vertices are fan triangulated
+    fn parse_face(text: &str, vertex_count: usize) -> Result<Vec<(usize, usize, usize)>> {
+        let mut parts = Vec::with_capacity(3);
 
-        if parts.len() != 3 {
+        // NOTE: only the vertex slot of v/vt/vn matters here
+        for group in text.split_whitespace().skip(1) {
+            let num = match group.split('/').next() {
+                Some(num) if !num.is_empty() => num.parse::<i64>()?,
+                _ => bail!("Missing vertex index in face line: {text}"),
+            };
+
+            // NOTE: in .obj file, the index starts from 1, negative ones count back from the last vertex
+            let idx = if num > 0 {
+                num - 1
+            } else {
+                vertex_count as i64 + num
+            };
+            if num == 0 || idx < 0 {
+                bail!("Invalid vertex index {num} in face line: {text}");
+            }
+
+            parts.push(idx as usize);
+        }
+
+        if parts.len() < 3 {
             bail!("Failed to parse face line: {text}");
         }
 
-        Ok((parts[0], parts[1], parts[2]))
+        Ok((1..parts.len() - 1)
+            .map(|i| (parts[0], parts[i], parts[i + 1]))
+            .collect())
     }
 
     pub fn load_model<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::parse_model(&fs::read_to_string(path)?)
+    }
+
+    /// Parse the content of an .obj file
+    ///
+    /// Bad lines don't stop the parsing, they are all reported together as `ParseErrors`
+    pub fn parse_model(text: &str) -> Result<Self> {
         let mut m = Model {
             vertices: vec![],
             faces: vec![],
         };
+        let mut errors = ParseErrors::default();
 
-        let file = File::open(path)?;
-        let reader = std::io::BufReader::new(file);
-
-        for line in reader.lines() {
-            let line = line?; // Handle Result<String>
+        for (n, line) in text.lines().enumerate() {
             if line.starts_with("v ") {
-                m.vertices.push(Self::parse_vertex(&line)?);
+                match Self::parse_vertex(line) {
+                    Ok(vertex) => m.vertices.push(vertex),
+                    Err(err) => errors.push(n + 1, err),
+                }
                 continue;
             }
 
             if line.starts_with("f ") {
-                m.faces.push(Self::parse_face(&line)?);
+                match Self::parse_face(line, m.vertices.len()) {
+                    Ok(faces) => m.faces.extend(faces),
+                    Err(err) => errors.push(n + 1, err),
+                }
                 continue;
             }
         }
 
-        Ok(m)
+        errors.or_ok(m)
     }
 }
 
@@ -157,4 +355,171 @@ mod test {
         imageops::flip_vertical_in_place(&mut img);
         img.save("output/triangle.tga").unwrap();
     }
+
+    #[test]
+    fn test_parse_face() {
+        assert_eq!(
+            Model::parse_face("f -3/-2/-1 -2/-1/-3 -1/-3/-2", 10).unwrap(),
+            vec![(7, 8, 9)]
+        );
+        assert_eq!(Model::parse_face("f 1 2 3", 3).unwrap(), vec![(0, 1, 2)]);
+        assert_eq!(
+            Model::parse_face("f 4//1 5//2 6//3", 6).unwrap(),
+            vec![(3, 4, 5)]
+        );
+        assert_eq!(
+            Model::parse_face("f 1 2 3 4", 4).unwrap(),
+            vec![(0, 1, 2), (0, 2, 3)]
+        );
+
+        assert!(Model::parse_face("f -4 -2 -1", 3).is_err());
+        assert!(Model::parse_face("f /1/1 2 3", 3).is_err());
+        assert!(Model::parse_face("f 1 2", 3).is_err());
+    }
+
+    #[test]
+    fn test_parse_model() {
+        let obj = "\
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vn 0 0 1
+f 1//1 2//1 3//1 4//1
+f -4 -2 -1
+";
+        let model = Model::parse_model(obj).unwrap();
+        assert_eq!(model.faces, vec![(0, 1, 2), (0, 2, 3), (0, 2, 3)]);
+
+        // NOTE: every bad line is reported, the good ones in between still parse
+        let obj = "\
+v 0 0 0
+v 1 0
+f 1 2
+v 1 1 0
+f 1 2 -3
+";
+        let err = Model::parse_model(obj).err().unwrap();
+        let errors = &err.downcast_ref::<ParseErrors>().unwrap().0;
+        assert_eq!(
+            errors.iter().map(|(line, _)| *line).collect::<Vec<_>>(),
+            vec![2, 3, 5]
+        );
+        assert!(err
+            .to_string()
+            .contains("line 3: Failed to parse face line"));
+    }
+
+    #[test]
+    fn test_draw_line_aa_diagonal() {
+        let white = Rgb([255, 255, 255]);
+        let mut img = RgbImage::new(16, 16);
+        draw_line_aa(0.0, 0.0, 10.0, 10.0, &mut img, white);
+
+        // NOTE: a 45 degree line goes through pixel centers, no bleeding
+        for i in 1..10 {
+            assert_eq!(*img.get_pixel(i, i), white);
+            assert_eq!(*img.get_pixel(i, i + 1), Rgb([0, 0, 0]));
+        }
+    }
+
+    #[test]
+    fn test_draw_line_aa_shallow() {
+        let mut img = RgbImage::new(16, 16);
+        draw_line_aa(0.0, 0.0, 8.0, 2.0, &mut img, Rgb([255, 255, 255]));
+
+        // NOTE: gradient is 0.25, the coverage is split between two rows
+        assert_eq!(*img.get_pixel(1, 0), Rgb([191, 191, 191]));
+        assert_eq!(*img.get_pixel(1, 1), Rgb([64, 64, 64]));
+        assert_eq!(*img.get_pixel(2, 0), Rgb([128, 128, 128]));
+        assert_eq!(*img.get_pixel(2, 1), Rgb([128, 128, 128]));
+        assert_eq!(*img.get_pixel(4, 1), Rgb([255, 255, 255]));
+
+        // NOTE: it blends with what is already there
+        let mut img = RgbImage::from_pixel(16, 16, Rgb([0, 0, 255]));
+        draw_line_aa(0.0, 0.0, 8.0, 2.0, &mut img, Rgb([255, 0, 0]));
+        assert_eq!(*img.get_pixel(2, 0), Rgb([128, 0, 128]));
+    }
+
+    #[test]
+    fn test_draw_line_aa_clipped() {
+        let white = Rgb([255, 255, 255]);
+        let mut img = RgbImage::new(20, 20);
+        draw_line_aa(-10.0, -10.0, 50.0, 5.0, &mut img, white);
+        draw_line_aa(5.0, -30.0, 5.0, 40.0, &mut img, white);
+        draw_line_aa(-5.0, 7.0, 30.0, 7.0, &mut img, white);
+
+        assert_eq!(*img.get_pixel(5, 0), white);
+        assert_eq!(*img.get_pixel(5, 19), white);
+        assert_eq!(*img.get_pixel(0, 7), white);
+        assert_eq!(*img.get_pixel(19, 7), white);
+    }
+
+    #[test]
+    fn test_draw_line_fan() {
+        let size = 200;
+        let mut img = RgbImage::new(size * 2, size);
+        let center = size as f64 / 2.0;
+
+        // NOTE: Bresenham on the left, Wu on the right
+        for i in 0..24 {
+            let angle = i as f64 * std::f64::consts::PI / 12.0;
+            let (dx, dy) = (90.0 * angle.cos(), 90.0 * angle.sin());
+            draw_line(
+                center as u32,
+                center as u32,
+                (center + dx) as u32,
+                (center + dy) as u32,
+                &mut img,
+                Rgb([255, 255, 255]),
+            );
+            draw_line_aa(
+                center + size as f64,
+                center,
+                center + size as f64 + dx,
+                center + dy,
+                &mut img,
+                Rgb([255, 255, 255]),
+            );
+        }
+
+        imageops::flip_vertical_in_place(&mut img);
+        img.save("output/line_fan_bresenham_vs_wu.png").unwrap();
+    }
+
+    #[test]
+    fn test_clip_line() {
+        assert_eq!(
+            clip_line((-10.0, 5.0), (30.0, 5.0), 20, 20),
+            Some(((0.0, 5.0), (19.0, 5.0)))
+        );
+        assert_eq!(
+            clip_line((0.0, 0.0), (40.0, 40.0), 20, 20),
+            Some(((0.0, 0.0), (19.0, 19.0)))
+        );
+        assert_eq!(clip_line((25.0, 0.0), (40.0, 40.0), 20, 20), None);
+        assert_eq!(clip_line((-5.0, 30.0), (30.0, 25.0), 20, 20), None);
+    }
+
+    #[test]
+    fn test_draw_lines_outside_image() {
+        let white = Rgb([255, 255, 255]);
+        let mut img = RgbImage::new(20, 20);
+
+        // NOTE: partially outside, the visible part is drawn
+        draw_line(5, 5, 100, 5, &mut img, white);
+        draw_line(0, 0, 40, 40, &mut img, white);
+        naive_draw_line(3, 0, 3, 50, &mut img, white);
+        assert!((0..20).all(|i| *img.get_pixel(i, i) == white));
+        // NOTE: the naive version starts one step after the first endpoint
+        assert!((1..20).all(|y| *img.get_pixel(3, y) == white));
+        assert!((5..20).all(|x| *img.get_pixel(x, 5) == white));
+        assert_eq!(*img.get_pixel(4, 5), Rgb([0, 0, 0]));
+
+        // NOTE: fully outside, nothing is drawn
+        let mut img = RgbImage::new(20, 20);
+        draw_line(25, 0, 40, 40, &mut img, white);
+        naive_draw_line(30, 30, 40, 50, &mut img, white);
+        assert!(img.pixels().all(|p| *p == Rgb([0, 0, 0])));
+    }
 }
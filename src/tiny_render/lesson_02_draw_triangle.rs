@@ -1,14 +1,56 @@
 use image::{imageops, GenericImage};
 use nalgebra as na;
 
-#[derive(Clone)]
+/// A point in pixel space, pixel `(x, y)` covers `[x, x + 1) x [y, y + 1)`
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Point2D {
-    pub x: u32,
-    pub y: u32,
+    pub x: f64,
+    pub y: f64,
 }
 
-fn slope(pa: &Point2D, pb: &Point2D) -> f64 {
-    (pb.y as f64 - pa.y as f64) / (pb.x as f64 - pa.x as f64)
+impl From<(u32, u32)> for Point2D {
+    fn from((x, y): (u32, u32)) -> Self {
+        Self {
+            x: x as f64,
+            y: y as f64,
+        }
+    }
+}
+
+impl From<(f64, f64)> for Point2D {
+    fn from((x, y): (f64, f64)) -> Self {
+        Self { x, y }
+    }
+}
+
+/// Pixels whose centers lie in `[lo, hi]`, clipped to `[0, len)`
+pub(crate) fn pixel_span(lo: f64, hi: f64, len: u32) -> std::ops::RangeInclusive<u32> {
+    let first = (lo - 0.5).ceil().max(0.0);
+    let last = (hi - 0.5).floor().min(len as f64 - 1.0);
+
+    // NOTE: an empty range when nothing is covered
+    if last < first {
+        #[allow(clippy::reversed_empty_ranges)]
+        return 1..=0;
+    }
+
+    first as u32..=last as u32
+}
+
+/// Fill row `y` between `xa` and `xb`, sampling at pixel centers
+fn fill_span<I: GenericImage>(img: &mut I, y: u32, xa: f64, xb: f64, pixel: I::Pixel) {
+    for x in pixel_span(xa.min(xb), xa.max(xb), img.width()) {
+        img.put_pixel(x, y, pixel);
+    }
+}
+
+/// x of edge `pa` `pb` at height `y`
+fn edge_x(pa: &Point2D, pb: &Point2D, y: f64) -> f64 {
+    if pa.y == pb.y {
+        return pa.x;
+    }
+
+    pa.x + (pb.x - pa.x) * (y - pa.y) / (pb.y - pa.y)
 }
 
 pub fn naive_draw_triangle<I: GenericImage>(
@@ -19,7 +61,7 @@ pub fn naive_draw_triangle<I: GenericImage>(
     pixel: I::Pixel,
 ) {
     let mut vertices = [p0, p1, p2];
-    vertices.sort_by(|a, b| a.y.cmp(&b.y));
+    vertices.sort_by(|a, b| a.y.total_cmp(&b.y));
 
     // assume type I triangle
     // ---a------------------
@@ -30,38 +72,21 @@ pub fn naive_draw_triangle<I: GenericImage>(
     // --------*******-------
     // ---------****---------
     // ----------c-----------
-    let ya = vertices[2].y;
-    let yb = vertices[1].y;
-    let yc = vertices[0].y;
-
-    // left bound ac, right bound ab
-    // y from ya down to yb
-    let k_ac = slope(&vertices[2], &vertices[0]);
-    let k_ab = slope(&vertices[2], &vertices[1]);
-    let k_bc = slope(&vertices[1], &vertices[0]);
-    let mut left_bound_x = vertices[2].x as f64;
-    let mut right_bound_x = left_bound_x;
-    // when dy is 1, dx change
-    let dx_left = -1.0 / k_ac;
-    let dx_right = -1.0 / k_ab;
-    let dx_right2 = -1.0 / k_bc;
-
-    for y in (yb..=ya).rev() {
-        for x in left_bound_x as u32..=right_bound_x as u32 {
-            img.put_pixel(x, y, pixel);
-        }
-
-        left_bound_x += dx_left;
-        right_bound_x += dx_right;
-    }
-
-    for y in (yc..=yb).rev() {
-        for x in left_bound_x as u32..=right_bound_x as u32 {
-            img.put_pixel(x, y, pixel);
-        }
+    let (a, b, c) = (vertices[2], vertices[1], vertices[0]);
+
+    // left bound ac, right bound ab then bc
+    // y from ya down to yc, one row at a time, sampled at the row center
+    for y in pixel_span(c.y, a.y, img.height()).rev() {
+        let yc = y as f64 + 0.5;
+        let left_bound_x = edge_x(&a, &c, yc);
+        // NOTE: on the row of b, bc gives b itself, a horizontal ab would only give a
+        let right_bound_x = if yc > b.y {
+            edge_x(&a, &b, yc)
+        } else {
+            edge_x(&b, &c, yc)
+        };
 
-        left_bound_x += dx_left;
-        right_bound_x += dx_right2;
+        fill_span(img, y, left_bound_x, right_bound_x, pixel);
     }
 
     imageops::flip_vertical_in_place(img);
@@ -88,30 +113,18 @@ pub fn draw_triangle_upper_and_down<I: GenericImage>(
         std::mem::swap(&mut p1, &mut p2);
     }
 
-    let total_height = p2.y - p0.y;
-
-    for y in p0.y..=p1.y {
-        let segment_height = p1.y - p0.y + 1;
-        let alpha = (y as f64 - p0.y as f64) / total_height as f64;
-        let beta = (y as f64 - p0.y as f64) / segment_height as f64;
-        let a_x = (p0.x as f64 + (p2.x as f64 - p0.x as f64) * alpha) as u32;
-        let b_x = (p0.x as f64 + (p1.x as f64 - p0.x as f64) * beta) as u32;
-
-        for x in a_x.min(b_x)..=a_x.max(b_x) {
-            img.put_pixel(x, y, pixel);
-        }
-    }
-
-    for y in p1.y..=p2.y {
-        let segment_height = p2.y - p1.y + 1;
-        let alpha = (y as f64 - p0.y as f64) / total_height as f64;
-        let beta = (y as f64 - p1.y as f64) / segment_height as f64;
-        let a_x = (p0.x as f64 + (p2.x as f64 - p0.x as f64) * alpha) as u32;
-        let b_x = (p1.x as f64 + (p2.x as f64 - p1.x as f64) * beta) as u32;
+    // NOTE: only walk the rows inside the image, the lower part then the upper part
+    for y in pixel_span(p0.y, p2.y, img.height()) {
+        let yc = y as f64 + 0.5;
+        // NOTE: edge_x guards horizontal edges, a 0 / 0 there would drop the row
+        let a_x = edge_x(&p0, &p2, yc);
+        let b_x = if yc < p1.y {
+            edge_x(&p0, &p1, yc)
+        } else {
+            edge_x(&p1, &p2, yc)
+        };
 
-        for x in a_x.min(b_x)..=a_x.max(b_x) {
-            img.put_pixel(x, y, pixel);
-        }
+        fill_span(img, y, a_x, b_x, pixel);
     }
 }
 
@@ -120,9 +133,9 @@ fn is_in_triangle(p: &Point2D, t0: &Point2D, t1: &Point2D, t2: &Point2D) -> bool
     // -->    -->    -->
     // BP = u BA + v BC
     // Solve u v
-    let bp = na::Vector2::new(p.x as f64 - t1.x as f64, p.y as f64 - t1.y as f64);
-    let ba = na::Vector2::new(t0.x as f64 - t1.x as f64, t0.y as f64 - t1.y as f64);
-    let bc = na::Vector2::new(t2.x as f64 - t1.x as f64, t2.y as f64 - t1.y as f64);
+    let bp = na::Vector2::new(p.x - t1.x, p.y - t1.y);
+    let ba = na::Vector2::new(t0.x - t1.x, t0.y - t1.y);
+    let bc = na::Vector2::new(t2.x - t1.x, t2.y - t1.y);
 
     let mat = na::matrix![
         ba.x, bc.x;
@@ -145,14 +158,25 @@ pub fn draw_triangle_using_bounding_box<I: GenericImage>(
     img: &mut I,
     pixel: I::Pixel,
 ) {
-    let x_left = t0.x.min(t1.x).min(t2.x);
-    let x_right = t0.x.max(t1.x).max(t2.x).min(img.width() - 1);
-    let y_bottom = t0.y.min(t1.y).min(t2.y);
-    let y_top = t0.y.max(t1.y).max(t2.y).min(img.height() - 1);
-
-    for x in x_left..=x_right {
-        for y in y_bottom..=y_top {
-            if is_in_triangle(&Point2D { x, y }, &t0, &t1, &t2) {
+    let xs = pixel_span(
+        t0.x.min(t1.x).min(t2.x),
+        t0.x.max(t1.x).max(t2.x),
+        img.width(),
+    );
+    let ys = pixel_span(
+        t0.y.min(t1.y).min(t2.y),
+        t0.y.max(t1.y).max(t2.y),
+        img.height(),
+    );
+
+    for x in xs {
+        for y in ys.clone() {
+            // NOTE: sample at the pixel center, so thin triangles don't drop pixels
+            let center = Point2D {
+                x: x as f64 + 0.5,
+                y: y as f64 + 0.5,
+            };
+            if is_in_triangle(&center, &t0, &t1, &t2) {
                 img.put_pixel(x, y, pixel);
             }
         }
@@ -169,9 +193,9 @@ mod tests {
     #[test]
     fn test_triangle() {
         let mut img = RgbImage::new(800, 800);
-        let p0 = Point2D { x: 200, y: 600 };
-        let p1 = Point2D { x: 600, y: 400 };
-        let p2 = Point2D { x: 400, y: 100 };
+        let p0 = Point2D::from((200, 600));
+        let p1 = Point2D::from((600, 400));
+        let p2 = Point2D::from((400, 100));
         let pixel = Rgb([255, 0, 0]);
         naive_draw_triangle(p0, p1, p2, &mut img, pixel);
         img.save("output/triangle_filled.tga").unwrap();
@@ -180,15 +204,15 @@ mod tests {
     #[test]
     fn test_draw_triangle_upper_and_down() {
         let mut img = RgbImage::new(800, 800);
-        let p0 = Point2D { x: 200, y: 600 };
-        let p1 = Point2D { x: 600, y: 400 };
-        let p2 = Point2D { x: 400, y: 100 };
+        let p0 = Point2D::from((200, 600));
+        let p1 = Point2D::from((600, 400));
+        let p2 = Point2D::from((400, 100));
         let pixel = Rgb([255, 0, 0]);
         draw_triangle_upper_and_down(p0, p1, p2, &mut img, pixel);
 
-        let p0 = Point2D { x: 10, y: 90 };
-        let p1 = Point2D { x: 20, y: 20 };
-        let p2 = Point2D { x: 80, y: 150 };
+        let p0 = Point2D::from((10, 90));
+        let p1 = Point2D::from((20, 20));
+        let p2 = Point2D::from((80, 150));
         let pixel = Rgb([0, 255, 0]);
         draw_triangle_upper_and_down(p0, p1, p2, &mut img, pixel);
         imageops::flip_vertical_in_place(&mut img);
@@ -199,15 +223,15 @@ mod tests {
     #[test]
     fn test_draw_triangle_using_bounding_box() {
         let mut img = RgbImage::new(800, 800);
-        let p0 = Point2D { x: 200, y: 600 };
-        let p1 = Point2D { x: 600, y: 400 };
-        let p2 = Point2D { x: 400, y: 100 };
+        let p0 = Point2D::from((200, 600));
+        let p1 = Point2D::from((600, 400));
+        let p2 = Point2D::from((400, 100));
         let pixel = Rgb([255, 0, 0]);
         draw_triangle_using_bounding_box(p0, p1, p2, &mut img, pixel);
 
-        let p0 = Point2D { x: 10, y: 90 };
-        let p1 = Point2D { x: 20, y: 20 };
-        let p2 = Point2D { x: 80, y: 150 };
+        let p0 = Point2D::from((10, 90));
+        let p1 = Point2D::from((20, 20));
+        let p2 = Point2D::from((80, 150));
         let pixel = Rgb([0, 255, 0]);
         draw_triangle_using_bounding_box(p0, p1, p2, &mut img, pixel);
 
@@ -215,15 +239,141 @@ mod tests {
         img.save("output/triangle_bounding_box.tga").unwrap();
     }
 
+    #[test]
+    fn test_draw_triangles_outside_image() {
+        let white = Rgb([255, 255, 255]);
+        let (p0, p1, p2) = (
+            Point2D::from((10, 10)),
+            Point2D::from((60, 10)),
+            Point2D::from((10, 60)),
+        );
+
+        // NOTE: the right angle corner is inside, the rest is cut by the image border
+        let mut img = RgbImage::new(20, 20);
+        draw_triangle_upper_and_down(p0, p1, p2, &mut img, white);
+        for (x, y) in [(10, 10), (19, 10), (10, 19), (19, 19)] {
+            assert_eq!(*img.get_pixel(x, y), white, "({x}, {y})");
+        }
+        assert_eq!(*img.get_pixel(9, 10), Rgb([0, 0, 0]));
+
+        let mut bbox_img = RgbImage::new(20, 20);
+        draw_triangle_using_bounding_box(p0, p1, p2, &mut bbox_img, white);
+        assert_eq!(*bbox_img.get_pixel(19, 19), white);
+        assert_eq!(*bbox_img.get_pixel(9, 10), Rgb([0, 0, 0]));
+
+        let mut naive_img = RgbImage::new(20, 20);
+        naive_draw_triangle(p0, p1, p2, &mut naive_img, white);
+
+        // NOTE: vertex right on the border, as vertex_to_pixel gives for 1.0
+        let mut img = RgbImage::new(800, 800);
+        let (p0, p1, p2) = (
+            Point2D::from((0, 0)),
+            Point2D::from((800, 400)),
+            Point2D::from((400, 800)),
+        );
+        draw_triangle_upper_and_down(p0, p1, p2, &mut img, white);
+        draw_triangle_using_bounding_box(p0, p1, p2, &mut img, white);
+
+        // NOTE: fully outside, nothing is drawn
+        let mut img = RgbImage::new(20, 20);
+        let (p0, p1, p2) = (
+            Point2D::from((30, 30)),
+            Point2D::from((60, 30)),
+            Point2D::from((30, 60)),
+        );
+        draw_triangle_upper_and_down(p0, p1, p2, &mut img, white);
+        draw_triangle_using_bounding_box(p0, p1, p2, &mut img, white);
+        assert!(img.pixels().all(|p| *p == Rgb([0, 0, 0])));
+    }
+
+    #[test]
+    fn test_draw_triangle_negative_coordinates() {
+        let white = Rgb([255, 255, 255]);
+        let (p0, p1, p2) = (
+            Point2D::from((-10.0, -10.0)),
+            Point2D::from((14.0, -10.0)),
+            Point2D::from((-10.0, 14.0)),
+        );
+
+        let mut bbox_img = RgbImage::new(20, 20);
+        draw_triangle_using_bounding_box(p0, p1, p2, &mut bbox_img, white);
+        let mut scan_img = RgbImage::new(20, 20);
+        draw_triangle_upper_and_down(p0, p1, p2, &mut scan_img, white);
+        let mut naive_img = RgbImage::new(20, 20);
+        naive_draw_triangle(p0, p1, p2, &mut naive_img, white);
+        imageops::flip_vertical_in_place(&mut naive_img);
+
+        // NOTE: only the corner below x + y = 4 is inside the image
+        for img in [&bbox_img, &scan_img, &naive_img] {
+            assert_eq!(*img.get_pixel(0, 0), white);
+            assert_eq!(*img.get_pixel(1, 1), white);
+            assert_eq!(*img.get_pixel(2, 2), Rgb([0, 0, 0]));
+            assert_eq!(*img.get_pixel(5, 5), Rgb([0, 0, 0]));
+        }
+    }
+
+    #[test]
+    fn test_draw_triangle_flat_edge_on_pixel_center() {
+        let white = Rgb([255, 255, 255]);
+        // NOTE: the top edge is horizontal on the centers of row 10, one vertex is off the canvas
+        let (p0, p1, p2) = (
+            Point2D::from((4.0, 10.5)),
+            Point2D::from((30.0, 10.5)),
+            Point2D::from((8.0, -6.0)),
+        );
+
+        let mut scan_img = RgbImage::new(20, 20);
+        draw_triangle_upper_and_down(p0, p1, p2, &mut scan_img, white);
+        let mut bbox_img = RgbImage::new(20, 20);
+        draw_triangle_using_bounding_box(p0, p1, p2, &mut bbox_img, white);
+        let mut naive_img = RgbImage::new(20, 20);
+        naive_draw_triangle(p0, p1, p2, &mut naive_img, white);
+        imageops::flip_vertical_in_place(&mut naive_img);
+
+        for (name, img) in [
+            ("scan", &scan_img),
+            ("bbox", &bbox_img),
+            ("naive", &naive_img),
+        ] {
+            assert_eq!(*img.get_pixel(10, 5), white, "{name}");
+            assert_eq!(*img.get_pixel(10, 10), white, "{name}");
+            assert_eq!(*img.get_pixel(0, 10), Rgb([0, 0, 0]), "{name}");
+            assert_eq!(*img.get_pixel(10, 11), Rgb([0, 0, 0]), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_draw_thin_subpixel_triangle() {
+        let white = Rgb([255, 255, 255]);
+        // NOTE: thinner than a pixel, it still crosses the centers of row 0
+        let (p0, p1, p2) = (
+            Point2D::from((0.0, 0.4)),
+            Point2D::from((20.0, 0.6)),
+            Point2D::from((0.0, 0.6)),
+        );
+
+        let mut bbox_img = RgbImage::new(20, 4);
+        draw_triangle_using_bounding_box(p0, p1, p2, &mut bbox_img, white);
+        let mut scan_img = RgbImage::new(20, 4);
+        draw_triangle_upper_and_down(p0, p1, p2, &mut scan_img, white);
+        let mut naive_img = RgbImage::new(20, 4);
+        naive_draw_triangle(p0, p1, p2, &mut naive_img, white);
+        imageops::flip_vertical_in_place(&mut naive_img);
+
+        // NOTE: the long edge crosses y = 0.5 at x = 10
+        for img in [&bbox_img, &scan_img, &naive_img] {
+            assert!((0..10).all(|x| *img.get_pixel(x, 0) == white));
+            assert_eq!(*img.get_pixel(10, 0), Rgb([0, 0, 0]));
+            assert!((0..20).all(|x| *img.get_pixel(x, 1) == Rgb([0, 0, 0])));
+        }
+    }
+
     #[test]
     fn test_draw_illuminated_head() {
         let mut img = RgbImage::new(800, 800);
         let model = Model::load_model("obj/head.obj").unwrap();
 
-        let vertex_to_pixel = |n: f64, scale: u32| {
-            let scale = scale as f64;
-            ((n + 1.0) * scale / 2.0) as u32
-        };
+        let vertex_to_pixel = |n: f64, scale: u32| (n + 1.0) * scale as f64 / 2.0;
 
         let get_intensity = |tri: [(f64, f64, f64); 3]| {
             let t0 = na::Vector3::new(tri[0].0, tri[0].1, tri[0].2);
@@ -270,10 +420,7 @@ mod tests {
         let mut img = RgbImage::new(2048, 2048);
         let model = Model::load_model("obj/head.obj").unwrap();
 
-        let vertex_to_pixel = |n: f64, scale: u32| {
-            let scale = scale as f64;
-            ((n + 1.0) * scale / 2.0) as u32
-        };
+        let vertex_to_pixel = |n: f64, scale: u32| (n + 1.0) * scale as f64 / 2.0;
 
         let mut rng = rand::rng();
 
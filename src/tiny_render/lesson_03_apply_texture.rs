@@ -1,7 +1,9 @@
-use anyhow::{bail, Result};
+use anyhow::{bail, ensure, Result};
 use image::{imageops, DynamicImage, GenericImage, GenericImageView, Pixel, Rgb};
-use nalgebra::{matrix, Vector2, Vector3};
-use std::{fs::File, io::BufRead, path::Path};
+use nalgebra::{matrix, Matrix4, Vector2, Vector3, Vector4};
+use std::{fs, path::Path};
+
+use super::{clip_line, draw_line, Canvas, ParseErrors};
 
 // NOTE: We need to apply barycentric coordinates
 // this will helps us to determine the texture cordinates
@@ -53,17 +55,115 @@ fn barycentric_coordinates2(triangle: &[Vector3<f64>], p: Vector3<f64>) -> Vecto
         })
 }
 
+#[derive(Debug)]
 pub struct Face {
     vertex_idx: Vector3<usize>,
-    texture_idx: Vector3<usize>,
+    texture_idx: Option<Vector3<usize>>,
+    normal_idx: Option<Vector3<usize>>,
+    material_idx: Option<usize>,
+}
+
+impl Face {
+    pub fn vertex_idx(&self) -> Vector3<usize> {
+        self.vertex_idx
+    }
+
+    pub fn texture_idx(&self) -> Option<Vector3<usize>> {
+        self.texture_idx
+    }
+
+    pub fn normal_idx(&self) -> Option<Vector3<usize>> {
+        self.normal_idx
+    }
+
+    /// Index into `Model::materials`, set by the last `usemtl` before the face
+    pub fn material_idx(&self) -> Option<usize> {
+        self.material_idx
+    }
+}
+
+/// Material declared in a .mtl file
+#[derive(Clone, Debug)]
+pub struct Material {
+    pub name: String,
+    /// Kd
+    pub diffuse: Vector3<f64>,
+    /// Ks
+    pub specular: Vector3<f64>,
+    /// Ns
+    pub specular_exponent: f64,
+    /// map_Kd
+    pub diffuse_map: Option<DynamicImage>,
+}
+
+impl Material {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            diffuse: Vector3::new(1.0, 1.0, 1.0),
+            specular: Vector3::zeros(),
+            specular_exponent: 0.0,
+            diffuse_map: None,
+        }
+    }
+
+    /// Parse the content of a .mtl file, texture paths are relative to `dir`
+    ///
+    /// Return the materials, and a warning for each line with a key that isn't supported,
+    /// those lines are ignored
+    pub fn parse_library(text: &str, dir: &Path) -> Result<(Vec<Self>, Vec<String>)> {
+        let mut materials: Vec<Self> = vec![];
+        let mut warnings = vec![];
+
+        for (n, line) in text.lines().map(str::trim).enumerate() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let rest = rest.trim();
+
+            if key == "newmtl" {
+                materials.push(Self::new(rest));
+                continue;
+            }
+
+            if !matches!(key, "Kd" | "Ks" | "Ns" | "map_Kd") {
+                warnings.push(format!("line {}: unsupported key `{key}`, ignored", n + 1));
+                continue;
+            }
+
+            let Some(m) = materials.last_mut() else {
+                bail!("Material property before any newmtl: {line}");
+            };
+
+            match key {
+                "Kd" => m.diffuse = Model::parse_vertex(line)?,
+                "Ks" => m.specular = Model::parse_vertex(line)?,
+                "Ns" => m.specular_exponent = rest.parse()?,
+                _ => {
+                    let mut img = image::open(dir.join(rest))?;
+                    imageops::flip_vertical_in_place(&mut img);
+                    m.diffuse_map = Some(img);
+                }
+            }
+        }
+
+        Ok((materials, warnings))
+    }
 }
 
 #[derive(Default)]
 pub struct Model {
     pub vertices: Vec<Vector3<f64>>,
     pub textures: Vec<Vector2<f64>>,
+    pub normals: Vec<Vector3<f64>>,
     pub faces: Vec<Face>,
+    pub materials: Vec<Material>,
+    /// What was ignored while loading, e.g. keys of .mtl files that aren't supported
+    pub warnings: Vec<String>,
     pub texture_color_map: Option<DynamicImage>,
+    /// Tangent space normals, the color `(r, g, b)` is the normal `2 * (r, g, b) / 255 - 1`
+    pub normal_map: Option<DynamicImage>,
 }
 
 impl Model {
@@ -96,25 +196,69 @@ impl Model {
         Ok(Vector2::new(parts[0], parts[1]))
     }
 
-    fn parse_face(text: &str) -> Result<Face> {
-        let parts = text
-            .split_whitespace()
-            .flat_map(|nums| nums.split('/'))
-            .filter_map(|num| num.parse::<usize>().ok().map(|n| n - 1)) // NOTE: in .obj file, the index starts from 1
-            .collect::<Vec<_>>();
+    /// Resolve a 1-based index, negative ones count back from the last element read so far
+    fn parse_index(num: &str, count: usize) -> Result<usize> {
+        let idx = num.parse::<i64>()?;
+        let resolved = if idx > 0 { idx - 1 } else { count as i64 + idx };
 
-        if parts.len() != 9 {
-            bail!("Failed to parse face line: {text}");
+        if idx == 0 || resolved < 0 {
+            bail!("Invalid index {num}, only {count} elements read so far");
         }
 
-        // NOTE: parts format
-        // 0 1 2
-        // 3 4 5
-        // 6 7 8
-        Ok(Face {
-            vertex_idx: Vector3::new(parts[0], parts[3], parts[6]),
-            texture_idx: Vector3::new(parts[1], parts[4], parts[7]),
-        })
+        Ok(resolved as usize)
+    }
+
+    /// Parse an optional texture or normal slot, an empty slot means it is absent
+    fn parse_slot(num: Option<&str>, count: usize) -> Result<Option<usize>> {
+        match num {
+            None | Some("") => Ok(None),
+            Some(num) => Self::parse_index(num, count).map(Some),
+        }
+    }
+
+    /// Parse a face line, polygons with more than 3 vertices are fan triangulated
+    fn parse_face(&self, text: &str) -> Result<Vec<Face>> {
+        // NOTE: each group is one of v, v/vt, v//vn or v/vt/vn
+        let mut corners = Vec::with_capacity(3);
+
+        for group in text.split_whitespace().skip(1) {
+            let mut slots = group.split('/');
+            let vertex = match slots.next() {
+                Some(num) if !num.is_empty() => Self::parse_index(num, self.vertices.len())?,
+                _ => bail!("Missing vertex index in face line: {text}"),
+            };
+            let texture = Self::parse_slot(slots.next(), self.textures.len())?;
+            let normal = Self::parse_slot(slots.next(), self.normals.len())?;
+
+            corners.push((vertex, texture, normal));
+        }
+
+        if corners.len() < 3 {
+            bail!("Failed to parse face line, expect at least 3 vertices: {text}");
+        }
+
+        // NOTE: texture and normal indices only count when all three vertices have them
+        fn all(a: Option<usize>, b: Option<usize>, c: Option<usize>) -> Option<Vector3<usize>> {
+            Some(Vector3::new(a?, b?, c?))
+        }
+
+        let triangle = |a: usize, b: usize, c: usize| {
+            let (va, ta, na) = corners[a];
+            let (vb, tb, nb) = corners[b];
+            let (vc, tc, nc) = corners[c];
+
+            Face {
+                vertex_idx: Vector3::new(va, vb, vc),
+                texture_idx: all(ta, tb, tc),
+                normal_idx: all(na, nb, nc),
+                material_idx: None,
+            }
+        };
+
+        // NOTE: A B C D E becomes ABC ACD ADE
+        Ok((1..corners.len() - 1)
+            .map(|i| triangle(0, i, i + 1))
+            .collect())
     }
 
     pub fn load_texture<P: AsRef<Path>>(self, texture_path: P) -> Result<Self> {
@@ -126,40 +270,153 @@ impl Model {
         Ok(m)
     }
 
+    pub fn load_normal_map<P: AsRef<Path>>(self, normal_map_path: P) -> Result<Self> {
+        let mut m = self;
+        let mut img = image::open(normal_map_path)?;
+        imageops::flip_vertical_in_place(&mut img);
+        m.normal_map = Some(img);
+
+        Ok(m)
+    }
+
     pub fn load_model<P: AsRef<Path>>(self, obj_path: P) -> Result<Self> {
+        let obj_path = obj_path.as_ref();
+        let text = fs::read_to_string(obj_path)?;
+        let dir = obj_path.parent().unwrap_or(Path::new(""));
+
+        self.parse_model_in(&text, dir)
+    }
+
+    /// Parse the content of an .obj file
+    pub fn parse_model(self, text: &str) -> Result<Self> {
+        self.parse_model_in(text, Path::new(""))
+    }
+
+    /// Parse the content of an .obj file, `mtllib` paths are relative to `dir`
+    ///
+    /// Bad lines don't stop the parsing, they are all reported together as `ParseErrors`
+    fn parse_model_in(self, text: &str, dir: &Path) -> Result<Self> {
         let mut m = self;
-        let file = File::open(obj_path)?;
-        let reader = std::io::BufReader::new(file);
+        let mut material_idx = None;
+        let mut errors = ParseErrors::default();
 
-        for line in reader.lines() {
-            let line = line?; // Handle Result<String>
-            if line.starts_with("v ") {
-                m.vertices.push(Self::parse_vertex(&line)?);
-                continue;
+        for (n, line) in text.lines().enumerate() {
+            if let Err(err) = m.parse_line(line, dir, &mut material_idx) {
+                errors.push(n + 1, err);
             }
+        }
 
-            if line.starts_with("vt ") {
-                m.textures.push(Self::parse_texture(&line)?);
-                continue;
-            }
+        errors.or_ok(m)
+    }
 
-            if line.starts_with("f ") {
-                m.faces.push(Self::parse_face(&line)?);
-                continue;
+    /// Add what `line` describes to the model, `material_idx` is the material set by `usemtl`
+    fn parse_line(
+        &mut self,
+        line: &str,
+        dir: &Path,
+        material_idx: &mut Option<usize>,
+    ) -> Result<()> {
+        if let Some(name) = line.strip_prefix("mtllib ") {
+            let path = dir.join(name.trim());
+            let library = fs::read_to_string(&path)?;
+            let base = path.parent().unwrap_or(dir);
+            let (materials, warnings) = Material::parse_library(&library, base)?;
+            self.materials.extend(materials);
+            self.warnings.extend(
+                warnings
+                    .into_iter()
+                    .map(|warning| format!("{}: {warning}", path.display())),
+            );
+        } else if let Some(name) = line.strip_prefix("usemtl ") {
+            let name = name.trim();
+            match self.materials.iter().position(|mat| mat.name == name) {
+                Some(idx) => *material_idx = Some(idx),
+                None => bail!("Unknown material: {name}"),
             }
+        } else if line.starts_with("v ") {
+            self.vertices.push(Self::parse_vertex(line)?);
+        } else if line.starts_with("vt ") {
+            self.textures.push(Self::parse_texture(line)?);
+        } else if line.starts_with("vn ") {
+            self.normals.push(Self::parse_vertex(line)?);
+        } else if line.starts_with("f ") {
+            let faces = self.parse_face(line)?;
+            self.faces.extend(faces.into_iter().map(|face| Face {
+                material_idx: *material_idx,
+                ..face
+            }));
         }
 
-        Ok(m)
+        Ok(())
     }
 }
 
-fn get_light_intensity(tri: &[Vector3<f64>]) -> f64 {
+fn get_light_intensity(tri: &[Vector3<f64>], light_dir: &Vector3<f64>) -> f64 {
     let t0 = Vector3::new(tri[0].x, tri[0].y, tri[0].z);
     let t1 = Vector3::new(tri[1].x, tri[1].y, tri[1].z);
     let t2 = Vector3::new(tri[2].x, tri[2].y, tri[2].z);
     let orth = (t2 - t0).cross(&(t1 - t0)).normalize();
 
-    orth.dot(&Vector3::new(0.0, 0.0, -1.0).normalize())
+    orth.dot(&light_dir.normalize())
+}
+
+/// Intensity with the light shining along `light_dir`, `norm` points outward
+fn get_light_intensity_by_norm(norm: &Vector3<f64>, light_dir: &Vector3<f64>) -> f64 {
+    -norm.normalize().dot(&light_dir.normalize())
+}
+
+/// Interpolate the vertex normals with the barycentric coefficients, Phong shading
+fn interpolate_normal(normals: &[Vector3<f64>], coe: &Vector3<f64>) -> Vector3<f64> {
+    (coe.x * normals[0] + coe.y * normals[1] + coe.z * normals[2]).normalize()
+}
+
+/// Directions in which u and v grow on the triangle, `None` when the uv are degenerate
+fn tangent_frame(
+    pts: &[Vector3<f64>],
+    textures: &[Vector2<f64>],
+) -> Option<(Vector3<f64>, Vector3<f64>)> {
+    let (e1, e2) = (pts[1] - pts[0], pts[2] - pts[0]);
+    let (d1, d2) = (textures[1] - textures[0], textures[2] - textures[0]);
+
+    let det = d1.x * d2.y - d2.x * d1.y;
+    if det.abs() < f64::EPSILON {
+        return None;
+    }
+
+    let tangent = (e1 * d2.y - e2 * d1.y) / det;
+    let bitangent = (e2 * d1.x - e1 * d2.x) / det;
+    Some((tangent, bitangent))
+}
+
+/// Normal used for lighting, `None` to light the whole face with its own normal
+///
+/// With a normal map, the sampled tangent space normal is brought around the interpolated
+/// normal, or the face normal without vertex normals
+fn shading_normal(
+    coe: &Vector3<f64>,
+    pts: &[Vector3<f64>],
+    textures: &[Vector2<f64>],
+    normals: Option<&[Vector3<f64>]>,
+    tangents: Option<&(Vector3<f64>, Vector3<f64>)>,
+    options: &RenderOptions,
+    model: &Model,
+) -> Option<Vector3<f64>> {
+    let geometric = normals.map(|normals| interpolate_normal(normals, coe));
+    let (Some(normal_map), Some((tangent, bitangent))) = (model.normal_map.as_ref(), tangents)
+    else {
+        return geometric;
+    };
+
+    // NOTE: faces are counterclockwise seen from outside
+    let n = geometric.unwrap_or_else(|| (pts[1] - pts[0]).cross(&(pts[2] - pts[0])).normalize());
+    // NOTE: Gram-Schmidt, the tangents are in the plane of the face but n may not be its normal
+    let t = (tangent - n * n.dot(tangent)).normalize();
+    let b = (bitangent - n * n.dot(bitangent) - t * t.dot(bitangent)).normalize();
+
+    let uv = coe.x * textures[0] + coe.y * textures[1] + coe.z * textures[2];
+    let local = options.filter.sample(normal_map, &uv) / 255.0 * 2.0 - Vector3::new(1.0, 1.0, 1.0);
+
+    Some((t * local.x + b * local.y + n * local.z).normalize())
 }
 
 fn world_to_screen(v: &Vector3<f64>, width: u32, height: u32) -> Vector3<f64> {
@@ -173,6 +430,263 @@ fn world_to_screen(v: &Vector3<f64>, width: u32, height: u32) -> Vector3<f64> {
     )
 }
 
+/// How camera space is flattened onto the screen
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Projection {
+    /// Drop z, `[-1, 1]` on x and y fills the screen whatever the depth
+    #[default]
+    Orthographic,
+    /// Divide by depth, `fov` is the vertical field of view in degrees
+    Perspective { fov: f64 },
+}
+
+/// Where the model is looked at from
+///
+/// The default looks down -z without perspective, the same as `world_to_screen` alone
+#[derive(Clone, Debug)]
+pub struct Camera {
+    eye: Vector3<f64>,
+    target: Vector3<f64>,
+    up: Vector3<f64>,
+    // NOTE: world to camera space, computed once when the camera is built
+    view: Matrix4<f64>,
+    pub projection: Projection,
+    /// Skip triangles facing away from the camera
+    pub cull_back_faces: bool,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new(
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(0.0, 0.0, -1.0),
+            Vector3::new(0.0, 1.0, 0.0),
+        )
+        .unwrap()
+    }
+}
+
+impl Camera {
+    /// Orthographic camera at `eye` looking at `target`, `up` only needs to be roughly up
+    ///
+    /// Fail when `eye` and `target` are the same point or `up` is along the line of sight
+    pub fn new(eye: Vector3<f64>, target: Vector3<f64>, up: Vector3<f64>) -> Result<Self> {
+        let view = Self::view_transform(&eye, &target, &up)?;
+
+        Ok(Self {
+            eye,
+            target,
+            up,
+            view,
+            projection: Projection::Orthographic,
+            cull_back_faces: true,
+        })
+    }
+
+    /// Perspective camera with the vertical field of view `fov` in degrees, `+y` is up
+    pub fn look_at(eye: Vector3<f64>, target: Vector3<f64>, fov: f64) -> Result<Self> {
+        Ok(Self {
+            projection: Projection::Perspective { fov },
+            ..Self::new(eye, target, Vector3::y())?
+        })
+    }
+
+    pub fn eye(&self) -> &Vector3<f64> {
+        &self.eye
+    }
+
+    pub fn target(&self) -> &Vector3<f64> {
+        &self.target
+    }
+
+    pub fn up(&self) -> &Vector3<f64> {
+        &self.up
+    }
+
+    /// World to camera space, the camera sits at the origin looking down -z
+    fn view_transform(
+        eye: &Vector3<f64>,
+        target: &Vector3<f64>,
+        up: &Vector3<f64>,
+    ) -> Result<Matrix4<f64>> {
+        let forward = target - eye;
+        ensure!(
+            forward.norm() > f64::EPSILON,
+            "camera eye and target are the same point {eye:?}"
+        );
+        let forward = forward.normalize();
+
+        let right = forward.cross(up);
+        ensure!(
+            right.norm() > f64::EPSILON,
+            "camera up {up:?} is parallel to the line of sight"
+        );
+        let right = right.normalize();
+        let up = right.cross(&forward);
+
+        // NOTE: the camera axes are orthonormal, so the inverse rotation is the transpose,
+        // the rows are the axes and the eye is moved back to the origin
+        let back = -forward;
+        Ok(matrix![
+            right.x, right.y, right.z, -right.dot(eye);
+            up.x   , up.y   , up.z   , -up.dot(eye)   ;
+            back.x , back.y , back.z , -back.dot(eye) ;
+            0.     , 0.     , 0.     , 1.             ;
+        ])
+    }
+
+    /// Same as `orth_perspective_transform` in rotating_box, the near plane sits at z = -1
+    fn perspective_transform(fov: f64, aspect: f64) -> Matrix4<f64> {
+        let n = -1.0;
+        let f = -100.0;
+        let t = (fov.to_radians() / 2.0).tan();
+        let (b, r, l) = (-t, t * aspect, -t * aspect);
+
+        matrix![
+            2. * n / (r - l), 0.               , (l + r) / (l - r) , 0.                   ;
+            0.              , 2. * n / (t - b) , (b + t) / (b - t) , 0.                   ;
+            0.              , 0.               , (f + n) / (n - f) , -2. * f * n / (f - n);
+            0.              , 0.               , 1.                , 0.                   ;
+        ]
+    }
+
+    /// Project a world position to screen space, z keeps the depth, the bigger the nearer
+    ///
+    /// Return `None` when the position is behind the camera
+    fn project(&self, v: &Vector3<f64>, width: u32, height: u32) -> Option<Vector3<f64>> {
+        let v = self.view * v.push(1.0);
+
+        let Projection::Perspective { fov } = self.projection else {
+            return Some(world_to_screen(&v.xyz(), width, height));
+        };
+
+        // NOTE: w is the camera space z, negative in front of the camera
+        let clip: Vector4<f64> = Self::perspective_transform(fov, width as f64 / height as f64) * v;
+        if clip.w >= 0.0 {
+            return None;
+        }
+
+        // NOTE: 1/w is linear in screen space, so it interpolates correctly across the triangle
+        let ndc = clip.xyz() / clip.w;
+        let screen = world_to_screen(&ndc, width, height);
+
+        Some(Vector3::new(screen.x, screen.y, -1.0 / clip.w))
+    }
+
+    /// 1/w of a projected position, constant without perspective
+    fn inv_w(&self, projected: &Vector3<f64>) -> f64 {
+        match self.projection {
+            Projection::Perspective { .. } => projected.z,
+            Projection::Orthographic => 1.0,
+        }
+    }
+}
+
+/// Turn screen space barycentric coefficients into ones for the attributes of the vertices
+///
+/// Attributes divided by w are linear in screen space, interpolate them with 1/w and divide back
+fn perspective_correct(coe: &Vector3<f64>, inv_w: &Vector3<f64>) -> Vector3<f64> {
+    let weighted = coe.component_mul(inv_w);
+
+    weighted / weighted.sum()
+}
+
+/// How the faces of a model are filled
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum RenderMode {
+    /// Only the edges, as in lesson 01
+    Wireframe,
+    /// One color everywhere, without lighting
+    FlatColor(Rgb<u8>),
+    /// White shaded by the face normal, as in lesson 02
+    FlatShaded,
+    /// White shaded by intensities computed at the vertices, see `rasterize_gouraud`
+    Gouraud,
+    /// Textures, materials and vertex normals when the model has them
+    #[default]
+    Textured,
+}
+
+/// How a texture is sampled between the centers of its texels
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TextureFilter {
+    /// The texel the sample falls in, blocky when the texture is magnified
+    Nearest,
+    /// The four texels around the sample, weighted by how close their centers are
+    #[default]
+    Bilinear,
+}
+
+impl TextureFilter {
+    /// Color of `texture` at `uv`, both in `[0, 1]`, components in `[0, 255]`
+    pub fn sample(&self, texture: &DynamicImage, uv: &Vector2<f64>) -> Vector3<f64> {
+        let (w, h) = (texture.width(), texture.height());
+        let texel = |x: u32, y: u32| {
+            let rgb = texture.get_pixel(x, y).to_rgb();
+            Vector3::new(rgb[0] as f64, rgb[1] as f64, rgb[2] as f64)
+        };
+
+        match self {
+            // NOTE: u or v of exactly 1 would sample one texel past the border
+            Self::Nearest => texel(
+                ((w as f64 * uv.x) as u32).min(w - 1),
+                ((h as f64 * uv.y) as u32).min(h - 1),
+            ),
+            Self::Bilinear => {
+                // NOTE: texel centers are at half integers, past the outer ones the border is kept
+                let x = (w as f64 * uv.x - 0.5).clamp(0.0, (w - 1) as f64);
+                let y = (h as f64 * uv.y - 0.5).clamp(0.0, (h - 1) as f64);
+                let (x0, y0) = (x.floor() as u32, y.floor() as u32);
+                let (x1, y1) = ((x0 + 1).min(w - 1), (y0 + 1).min(h - 1));
+                let (fx, fy) = (x.fract(), y.fract());
+
+                let top = texel(x0, y0) * (1.0 - fx) + texel(x1, y0) * fx;
+                let bottom = texel(x0, y1) * (1.0 - fx) + texel(x1, y1) * fx;
+                top * (1.0 - fy) + bottom * fy
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RenderOptions {
+    pub camera: Camera,
+    pub mode: RenderMode,
+    pub filter: TextureFilter,
+    /// Direction the light travels, it doesn't need to be normalized
+    pub light_dir: Vector3<f64>,
+    /// Draw the edges in this color over the filled faces, hidden edges too
+    pub wireframe: Option<Rgb<u8>>,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            camera: Camera::default(),
+            mode: RenderMode::default(),
+            filter: TextureFilter::default(),
+            light_dir: Vector3::new(0.0, 0.0, -1.0),
+            wireframe: None,
+        }
+    }
+}
+
+/// Draw the edges of a screen space triangle, edges going off the image are skipped
+fn draw_edges<I>(pts: &[Vector3<f64>], img: &mut I, pixel: Rgb<u8>)
+where
+    I: GenericImage<Pixel = Rgb<u8>>,
+{
+    let (width, height) = (img.width(), img.height());
+
+    for (a, b) in [(0, 1), (1, 2), (2, 0)] {
+        let (a, b) = (pts[a], pts[b]);
+        // NOTE: draw the part of an edge crossing the boundary that is on the canvas
+        if let Some((a, b)) = clip_line((a.x, a.y), (b.x, b.y), width, height) {
+            draw_line(a.0 as u32, a.1 as u32, b.0 as u32, b.1 as u32, img, pixel);
+        }
+    }
+}
+
 fn bound_box(pts: &[Vector3<f64>], width: u32, height: u32) -> (Vector2<f64>, Vector2<f64>) {
     let w = width as f64;
     let h = height as f64;
@@ -193,89 +707,308 @@ fn bound_box(pts: &[Vector3<f64>], width: u32, height: u32) -> (Vector2<f64>, Ve
     (bboxmin, bboxmax)
 }
 
-pub fn rasterize_3d_triangle<I>(
-    pts: &[Vector3<f64>],
+/// Color of a pixel with textures, materials and the normal from `shading_normal`
+fn textured_color(
+    coe: &Vector3<f64>,
     textures: &[Vector2<f64>],
-    z_buffer: &mut [f64],
-    img: &mut I,
+    normal: Option<Vector3<f64>>,
+    intensity: f64,
+    options: &RenderOptions,
     model: &Model,
-) where
-    I: GenericImage<Pixel = Rgb<u8>>,
+    material: Option<&Material>,
+) -> [u8; 3] {
+    // TODO: write it using matrix multiplication
+    // NOTE: apply texture if can
+
+    // NOTE: the face material wins over the texture of the whole model
+    let color_map = material
+        .and_then(|m| m.diffuse_map.as_ref())
+        .or(model.texture_color_map.as_ref());
+    let diffuse = material.map_or(Vector3::new(1.0, 1.0, 1.0), |m| m.diffuse);
+
+    let pixel = if let Some(color_map) = color_map {
+        let p_texture = coe.x * textures[0] + coe.y * textures[1] + coe.z * textures[2];
+        options
+            .filter
+            .sample(color_map, &p_texture)
+            .component_mul(&diffuse)
+    } else {
+        diffuse * 255.0
+    };
+
+    // NOTE: step 4: get current color for current pixel
+
+    // NOTE: step 5: apply intensity to color, per pixel if there are vertex normals or a normal map
+    let intensity = normal.map_or(intensity.max(0.0), |normal| {
+        get_light_intensity_by_norm(&normal, &options.light_dir).max(0.0)
+    });
+
+    (pixel * intensity)
+        .map(|x| x.clamp(0.0, 255.0) as u8)
+        .into()
+}
+
+/// Project a triangle to screen, `None` when part of it is behind the camera
+fn project_triangle(
+    pts: &[Vector3<f64>],
+    camera: &Camera,
+    width: u32,
+    height: u32,
+) -> Option<Vec<Vector3<f64>>> {
+    pts.iter()
+        .map(|v| camera.project(v, width, height))
+        .collect()
+}
+
+/// Fill a screen space triangle with the z-buffer test
+///
+/// `shade` gets the perspective correct barycentric coefficients of the pixel
+fn fill_triangle<F>(pts: &[Vector3<f64>], camera: &Camera, canvas: &mut Canvas, mut shade: F)
+where
+    F: FnMut(&Vector3<f64>) -> [u8; 3],
 {
-    // NOTE: step 1: before scale world coordinates to screen, get intensity
-    let intensity = get_light_intensity(pts);
+    // NOTE: faces are counterclockwise seen from outside, clockwise on screen means facing away
+    let (ab, ac) = (pts[1] - pts[0], pts[2] - pts[0]);
+    if camera.cull_back_faces && ab.x * ac.y - ab.y * ac.x < 0.0 {
+        return;
+    }
 
-    // NOTE: step 2: world coordinates to screen
-    let pts = pts
-        .iter()
-        .map(|v| world_to_screen(v, img.width(), img.height()))
-        .collect::<Vec<_>>();
+    let inv_w = Vector3::new(
+        camera.inv_w(&pts[0]),
+        camera.inv_w(&pts[1]),
+        camera.inv_w(&pts[2]),
+    );
+    // NOTE: without perspective w is constant, screen space coefficients are already right
+    let affine = camera.projection == Projection::Orthographic;
 
     // NOTE: step 3: get bounding box
-    let (bboxmin, bboxmax) = bound_box(&pts, img.width(), img.height());
+    let (bboxmin, bboxmax) = bound_box(pts, canvas.width(), canvas.height());
 
     for x in bboxmin.x as u32..=bboxmax.x as u32 {
         for y in bboxmin.y as u32..=bboxmax.y as u32 {
             let p = Vector3::new(x as f64, y as f64, 0.0);
-            let coe = barycentric_coordinates2(&pts, p);
-            let z_idx = (p.x as u32 + p.y as u32 * img.width()) as usize;
+            let coe = barycentric_coordinates2(pts, p);
+            // NOTE: interpolate depth, so the nearest triangle wins whatever the drawing order
+            let p = Vector3::new(
+                p.x,
+                p.y,
+                coe.x * pts[0].z + coe.y * pts[1].z + coe.z * pts[2].z,
+            );
 
             // NOTE: test is in triangle
             // if not, don't draw
-            if coe.iter().any(|&x| x < 0.0) || intensity < 0.0 {
+            if coe.iter().any(|&x| x < 0.0) {
                 continue;
             }
 
-            // TODO: write it using matrix multiplication
-            // NOTE: apply texture if can
-
-            let pixel = if let Some(ref color_map) = model.texture_color_map {
-                let p_texture = coe.x * textures[0] + coe.y * textures[1] + coe.z * textures[2];
-                let texture_w = color_map.width() as f64 * p_texture.x;
-                let texture_h = color_map.height() as f64 * p_texture.y;
-                let rgb = color_map
-                    .get_pixel(texture_w as u32, texture_h as u32)
-                    .to_rgb();
-                Vector3::new(rgb[0] as f64, rgb[1] as f64, rgb[2] as f64)
-            } else {
-                Vector3::new(255.0, 255.0, 255.0)
-            };
+            // NOTE: shade only what passes the depth test
+            if canvas.test_depth(x, y, p.z) {
+                let color_bit = if affine {
+                    shade(&coe)
+                } else {
+                    shade(&perspective_correct(&coe, &inv_w))
+                };
+                canvas.image_mut().put_pixel(x, y, Rgb(color_bit));
+            }
+        }
+    }
+}
 
-            // NOTE: step 4: get current color for current pixel
+pub fn rasterize_3d_triangle(
+    pts: &[Vector3<f64>],
+    textures: &[Vector2<f64>],
+    normals: Option<&[Vector3<f64>]>,
+    options: &RenderOptions,
+    canvas: &mut Canvas,
+    model: &Model,
+    material: Option<&Material>,
+) {
+    // NOTE: step 1: before scale world coordinates to screen, get intensity
+    let intensity = get_light_intensity(pts, &options.light_dir);
 
-            // NOTE: step 5: apply intensity to color
-            let color_bit = (pixel * intensity)
-                .map(|x| x.clamp(0.0, 255.0) as u8)
-                .into();
+    if options.mode == RenderMode::Gouraud {
+        // NOTE: without vertex normals every vertex gets the face intensity
+        let intensities = normals.map_or([intensity; 3], |n| {
+            [0, 1, 2].map(|i| get_light_intensity_by_norm(&n[i], &options.light_dir))
+        });
+        rasterize_gouraud(pts, &intensities, &options.camera, canvas);
+        return;
+    }
 
-            if z_buffer[z_idx] < p.z {
-                z_buffer[z_idx] = p.z;
-                img.put_pixel(p.x as u32, p.y as u32, Rgb(color_bit));
-            }
-        }
+    let world = pts;
+    let tangents = model
+        .normal_map
+        .as_ref()
+        .and_then(|_| tangent_frame(world, textures));
+
+    // NOTE: step 2: world coordinates to screen, skip triangles crossing behind the camera
+    let Some(pts) = project_triangle(pts, &options.camera, canvas.width(), canvas.height()) else {
+        return;
+    };
+
+    if options.mode == RenderMode::Wireframe {
+        draw_edges(&pts, canvas.image_mut(), Rgb([255, 255, 255]));
+        return;
     }
+
+    fill_triangle(&pts, &options.camera, canvas, |coe| match options.mode {
+        RenderMode::FlatColor(color) => color.0,
+        RenderMode::FlatShaded => gray(intensity),
+        _ => {
+            let normal = shading_normal(
+                coe,
+                world,
+                textures,
+                normals,
+                tangents.as_ref(),
+                options,
+                model,
+            );
+            textured_color(coe, textures, normal, intensity, options, model, material)
+        }
+    });
 }
 
-pub fn draw_model<I>(model: Model, img: &mut I)
-where
-    I: GenericImage<Pixel = Rgb<u8>>,
-{
-    let mut z_buffer = vec![f64::MIN; (img.width() * img.height()) as usize];
+fn gray(intensity: f64) -> [u8; 3] {
+    [(255.0 * intensity).clamp(0.0, 255.0) as u8; 3]
+}
+
+/// Gouraud shading, the intensities of the three vertices are interpolated across the triangle
+pub fn rasterize_gouraud(
+    pts: &[Vector3<f64>],
+    intensities: &[f64],
+    camera: &Camera,
+    canvas: &mut Canvas,
+) {
+    let Some(pts) = project_triangle(pts, camera, canvas.width(), canvas.height()) else {
+        return;
+    };
+
+    fill_triangle(&pts, camera, canvas, |coe| {
+        gray(coe.x * intensities[0] + coe.y * intensities[1] + coe.z * intensities[2])
+    });
+}
 
+pub fn draw_model(model: Model, canvas: &mut Canvas) {
+    draw_model_with(model, &RenderOptions::default(), canvas);
+}
+
+/// `draw_model` looking down -z with `projection`
+///
+/// With perspective the eye backs off to z = 3, so a model within `[-1, 1]` stays in front of it
+pub fn draw_model_with_projection(model: Model, projection: Projection, canvas: &mut Canvas) {
+    let camera = match projection {
+        Projection::Orthographic => Camera::default(),
+        // NOTE: the eye is off the target and +y is across the line of sight, it's always valid
+        Projection::Perspective { fov } => {
+            Camera::look_at(Vector3::new(0.0, 0.0, 3.0), Vector3::zeros(), fov).unwrap()
+        }
+    };
+
+    draw_model_with_camera(model, &camera, canvas);
+}
+
+pub fn draw_model_with_camera(model: Model, camera: &Camera, canvas: &mut Canvas) {
+    let options = RenderOptions {
+        camera: camera.clone(),
+        ..Default::default()
+    };
+
+    draw_model_with(model, &options, canvas);
+}
+
+pub fn draw_model_with(model: Model, options: &RenderOptions, canvas: &mut Canvas) {
     model.faces.iter().for_each(|face| {
         let v0 = model.vertices[face.vertex_idx.x];
         let v1 = model.vertices[face.vertex_idx.y];
         let v2 = model.vertices[face.vertex_idx.z];
 
-        let t0 = model.textures[face.texture_idx.x];
-        let t1 = model.textures[face.texture_idx.y];
-        let t2 = model.textures[face.texture_idx.z];
-
         let pts = [v0, v1, v2];
-        let textures = [t0, t1, t2];
+        // NOTE: faces without texture coordinates sample the corner of the texture
+        let textures = face.texture_idx.map_or([Vector2::zeros(); 3], |idx| {
+            idx.map(|i| model.textures[i]).into()
+        });
 
-        rasterize_3d_triangle(&pts, &textures, &mut z_buffer, img, &model);
+        // NOTE: files without vn lines fall back to the face normal
+        let normals: Option<[Vector3<f64>; 3]> = face
+            .normal_idx
+            .map(|idx| idx.map(|i| model.normals[i]).into());
+
+        rasterize_3d_triangle(
+            &pts,
+            &textures,
+            normals.as_ref().map(|n| n.as_slice()),
+            options,
+            canvas,
+            &model,
+            face.material_idx.map(|i| &model.materials[i]),
+        );
     });
+
+    if let Some(pixel) = options.wireframe {
+        draw_wireframe(&model, &options.camera, canvas.image_mut(), pixel);
+    }
+}
+
+/// Draw the three edges of every face with `draw_line`, to check how faces are connected
+pub fn draw_model_wireframe<I>(model: &Model, img: &mut I, pixel: Rgb<u8>)
+where
+    I: GenericImage<Pixel = Rgb<u8>>,
+{
+    draw_wireframe(model, &Camera::default(), img, pixel);
+}
+
+fn draw_wireframe<I>(model: &Model, camera: &Camera, img: &mut I, pixel: Rgb<u8>)
+where
+    I: GenericImage<Pixel = Rgb<u8>>,
+{
+    let (width, height) = (img.width(), img.height());
+
+    for face in model.faces.iter() {
+        let pts =
+            [face.vertex_idx.x, face.vertex_idx.y, face.vertex_idx.z].map(|i| model.vertices[i]);
+        // NOTE: no depth test and no culling, edges behind the model show through
+        if let Some(pts) = project_triangle(&pts, camera, width, height) {
+            draw_edges(&pts, img, pixel);
+        }
+    }
+}
+
+/// `draw_model` with `samples` x `samples` supersampling, so edges are smooth
+///
+/// The model is drawn, with its z-buffer, into an image `samples` times larger in each dimension,
+/// then every block of `samples` x `samples` pixels is averaged into one pixel of `img`
+pub fn draw_model_aa<I>(model: Model, img: &mut I, samples: u32)
+where
+    I: GenericImage<Pixel = Rgb<u8>>,
+{
+    draw_model_with_aa(model, &RenderOptions::default(), img, samples);
+}
+
+/// `draw_model_with` and supersampling, see `draw_model_aa`
+pub fn draw_model_with_aa<I>(model: Model, options: &RenderOptions, img: &mut I, samples: u32)
+where
+    I: GenericImage<Pixel = Rgb<u8>>,
+{
+    let samples = samples.max(1);
+    let (width, height) = (img.width(), img.height());
+    let mut canvas = Canvas::new(width * samples, height * samples);
+    draw_model_with(model, options, &mut canvas);
+    let large = canvas.image();
+
+    let count = samples * samples;
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum = [0u32; 3];
+            for dy in 0..samples {
+                for dx in 0..samples {
+                    let p = large.get_pixel(x * samples + dx, y * samples + dy);
+                    sum.iter_mut().zip(p.0).for_each(|(s, c)| *s += c as u32);
+                }
+            }
+            img.put_pixel(x, y, Rgb(sum.map(|s| ((s + count / 2) / count) as u8)));
+        }
+    }
 }
 
 #[cfg(test)]
@@ -308,29 +1041,917 @@ mod tests {
         assert_eq!(b, Vector3::new(1.0, 0.0, 0.0));
     }
 
+    #[test]
+    fn test_load_model_with_normals() {
+        let model = Model::default().load_model("obj/head.obj").unwrap();
+
+        assert_eq!(model.normals.len(), 1258);
+        let face = &model.faces[0];
+        assert_eq!(face.vertex_idx(), Vector3::new(23, 24, 25));
+        assert_eq!(face.normal_idx(), Some(Vector3::new(23, 24, 25)));
+        assert!(model
+            .faces
+            .iter()
+            .filter_map(|f| f.normal_idx())
+            .all(|idx| idx.iter().all(|&i| i < model.normals.len())));
+    }
+
     #[test]
     fn test_draw_head_removing_hidden_faces() {
-        let mut img = RgbImage::new(800, 800);
+        let mut canvas = Canvas::new(800, 800);
         let model = Model::default().load_model("obj/head.obj").unwrap();
 
-        draw_model(model, &mut img);
+        draw_model(model, &mut canvas);
 
-        imageops::flip_vertical_in_place(&mut img);
-        img.save("output/head_using_barycentric_2.tga").unwrap();
+        canvas
+            .save_flipped("output/head_using_barycentric_2.tga")
+            .unwrap();
     }
 
     #[test]
     fn test_draw_head_with_texture() {
-        let mut img = RgbImage::new(800, 800);
+        let mut canvas = Canvas::new(800, 800);
         let model = Model::default()
             .load_model("obj/head.obj")
             .unwrap()
             .load_texture("obj/african_head_diffuse.tga")
             .unwrap();
 
-        draw_model(model, &mut img);
+        draw_model(model, &mut canvas);
+
+        canvas.save_flipped("output/head_with_texture.tga").unwrap();
+    }
+
+    #[test]
+    fn test_draw_head_wireframe() {
+        let mut img = RgbImage::new(800, 800);
+        let model = Model::default().load_model("obj/head.obj").unwrap();
+        let red = Rgb([255, 0, 0]);
+
+        draw_model_wireframe(&model, &mut img, red);
+
+        let lit = img.pixels().filter(|p| **p == red).count();
+        assert!(lit > 0);
+        assert!(img.pixels().all(|p| *p == red || *p == Rgb([0, 0, 0])));
+
+        imageops::flip_vertical_in_place(&mut img);
+        img.save("output/head_wireframe.tga").unwrap();
+    }
+
+    #[test]
+    fn test_wireframe_overlay() {
+        let obj = "\
+v -0.8 -0.8 0.0
+v 0.8 -0.8 0.0
+v 0.8 0.8 0.0
+v -0.8 0.8 0.0
+f 1 2 3
+f 1 3 4
+";
+        let model = Model::default().parse_model(obj).unwrap();
+        let options = RenderOptions {
+            mode: RenderMode::FlatColor(Rgb([0, 0, 255])),
+            wireframe: Some(Rgb([255, 0, 0])),
+            ..Default::default()
+        };
+        let mut canvas = Canvas::new(40, 40);
+        draw_model_with(model, &options, &mut canvas);
+        let img = canvas.image();
+
+        // NOTE: the shared diagonal is drawn over the fill, the rest of the face stays filled
+        assert_eq!(*img.get_pixel(20, 20), Rgb([255, 0, 0]));
+        assert_eq!(*img.get_pixel(30, 10), Rgb([0, 0, 255]));
+    }
+
+    #[test]
+    fn test_wireframe_clips_edges_crossing_the_canvas() {
+        let obj = "\
+v -2.0 -0.5 0.0
+v 2.0 -0.5 0.0
+v 0.0 0.5 0.0
+f 1 2 3
+";
+        let model = Model::default().parse_model(obj).unwrap();
+        let red = Rgb([255, 0, 0]);
+        let mut img = RgbImage::new(40, 40);
+
+        draw_model_wireframe(&model, &mut img, red);
+
+        // NOTE: the bottom edge runs off both sides, only its middle is on the canvas
+        assert_eq!(*img.get_pixel(0, 10), red);
+        assert_eq!(*img.get_pixel(20, 10), red);
+        assert_eq!(*img.get_pixel(39, 10), red);
+    }
+
+    #[test]
+    fn test_interpolate_normal_at_centroid() {
+        let obj = "\
+v 0 0 0
+v 1 0 0
+v 0 1 0
+vn 1 0 1
+vn 0 1 1
+vn -1 -1 1
+f 1//1 2//2 3//3
+";
+        let model = Model::default().parse_model(obj).unwrap();
+        let face = &model.faces[0];
+        let normals = face.normal_idx().unwrap().map(|i| model.normals[i]);
+        let normals = [normals.x, normals.y, normals.z];
+
+        let centroid = (model.vertices[0] + model.vertices[1] + model.vertices[2]) / 3.0;
+        let coe = barycentric_coordinates2(&model.vertices, centroid);
+        let n = interpolate_normal(&normals, &coe);
+
+        // NOTE: the tilts cancel out at the centroid
+        assert!((n - Vector3::new(0.0, 0.0, 1.0)).norm() < 1e-9, "{n}");
+        let light_dir = RenderOptions::default().light_dir;
+        assert!((get_light_intensity_by_norm(&n, &light_dir) - 1.0).abs() < 1e-9);
+
+        // NOTE: at a vertex, its own normal is used
+        let coe = barycentric_coordinates2(&model.vertices, model.vertices[1]);
+        let n = interpolate_normal(&normals, &coe);
+        assert!((n - normals[1].normalize()).norm() < 1e-9, "{n}");
+    }
+
+    #[test]
+    fn test_default_camera_is_orthographic() {
+        let camera = Camera::default();
+        let v = Vector3::new(0.5, -0.25, 0.3);
+
+        assert_eq!(
+            camera.project(&v, 800, 600),
+            Some(world_to_screen(&v, 800, 600))
+        );
+    }
+
+    #[test]
+    fn test_perspective_camera() {
+        let camera = Camera::look_at(Vector3::new(0.0, 0.0, 3.0), Vector3::zeros(), 60.0).unwrap();
+
+        // NOTE: the target lands in the middle of the screen
+        let center = camera.project(&Vector3::zeros(), 800, 800).unwrap();
+        assert!((center.xy() - Vector2::new(400.5, 400.5)).norm() < 1e-9);
+
+        // NOTE: nearer positions get bigger depth, positions behind the camera are dropped
+        let near = camera
+            .project(&Vector3::new(0.0, 0.0, 1.0), 800, 800)
+            .unwrap();
+        assert!(near.z > center.z);
+        assert!(camera
+            .project(&Vector3::new(0.0, 0.0, 4.0), 800, 800)
+            .is_none());
+    }
+
+    #[test]
+    fn test_view_transform() {
+        let eye = Vector3::new(1.0, 2.0, 3.0);
+        let camera = Camera::look_at(eye, Vector3::new(-1.0, 0.5, 0.0), 60.0).unwrap();
+
+        // NOTE: the camera axes in world space, the view matrix is their inverse
+        let forward = (camera.target - camera.eye).normalize();
+        let right = forward.cross(&camera.up).normalize();
+        let up = right.cross(&forward);
+        let to_world = Matrix4::from_columns(&[
+            right.push(0.0),
+            up.push(0.0),
+            (-forward).push(0.0),
+            eye.push(1.0),
+        ]);
+        assert!((camera.view * to_world - Matrix4::identity()).norm() < 1e-12);
+
+        let err = Camera::look_at(eye, eye, 60.0).unwrap_err().to_string();
+        assert!(err.contains("same point"), "{err}");
+        let err = Camera::new(eye, eye + Vector3::y(), Vector3::new(0.0, 2.0, 0.0))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("parallel"), "{err}");
+    }
+
+    #[test]
+    fn test_perspective_foreshortening() {
+        let perspective =
+            Camera::look_at(Vector3::new(0.0, 0.0, 3.0), Vector3::zeros(), 60.0).unwrap();
+        let orthographic = Camera {
+            projection: Projection::Orthographic,
+            ..perspective.clone()
+        };
+        let off_center = |camera: &Camera, z: f64| {
+            let p = camera
+                .project(&Vector3::new(0.5, 0.5, z), 800, 800)
+                .unwrap();
+            (p.xy() - Vector2::new(400.5, 400.5)).norm()
+        };
+
+        // NOTE: orthographic ignores the depth, perspective pulls farther vertices to the center
+        assert_eq!(
+            off_center(&orthographic, -1.0),
+            off_center(&orthographic, 1.0)
+        );
+        assert!(off_center(&perspective, -1.0) < off_center(&perspective, 1.0));
+        assert!(off_center(&perspective, -1.0) < off_center(&orthographic, -1.0));
+    }
+
+    #[test]
+    fn test_draw_head_with_perspective() {
+        let mut canvas = Canvas::new(800, 800);
+        let model = Model::default().load_model("obj/head.obj").unwrap();
+
+        draw_model_with_projection(model, Projection::Perspective { fov: 60.0 }, &mut canvas);
+
+        let lit = canvas.image().pixels().filter(|p| p.0 != [0; 3]).count();
+        assert!(lit > 0);
+        canvas
+            .save_flipped("output/head_with_perspective.tga")
+            .unwrap();
+    }
+
+    #[test]
+    fn test_draw_head_from_side() {
+        let mut canvas = Canvas::new(800, 800);
+        let model = Model::default().load_model("obj/head.obj").unwrap();
+        let theta = 30f64.to_radians();
+        let eye = Vector3::new(3.0 * theta.sin(), 0.0, 3.0 * theta.cos());
+        let camera = Camera::look_at(eye, Vector3::zeros(), 45.0).unwrap();
+
+        draw_model_with_camera(model, &camera, &mut canvas);
+
+        canvas.save_flipped("output/head_from_side.tga").unwrap();
+    }
+
+    #[test]
+    fn test_nearer_triangle_wins() {
+        let near = [
+            Vector3::new(-0.5, -0.5, 0.5),
+            Vector3::new(0.5, -0.5, 0.5),
+            Vector3::new(0.0, 0.5, 0.5),
+        ];
+        let far = [
+            Vector3::new(-0.8, -0.8, -0.5),
+            Vector3::new(0.8, -0.8, -0.5),
+            Vector3::new(0.0, 0.8, -0.5),
+        ];
+        let red = Material {
+            diffuse: Vector3::new(1.0, 0.0, 0.0),
+            ..Material::new("red")
+        };
+        let blue = Material {
+            diffuse: Vector3::new(0.0, 0.0, 1.0),
+            ..Material::new("blue")
+        };
+        let model = Model::default();
+        let options = RenderOptions::default();
+
+        for order in [
+            [(&near, &red), (&far, &blue)],
+            [(&far, &blue), (&near, &red)],
+        ] {
+            let mut canvas = Canvas::new(40, 40);
+            for (pts, material) in order {
+                rasterize_3d_triangle(
+                    pts,
+                    &[Vector2::zeros(); 3],
+                    None,
+                    &options,
+                    &mut canvas,
+                    &model,
+                    Some(material),
+                );
+            }
+
+            assert_eq!(*canvas.image().get_pixel(20, 18), Rgb([255, 0, 0]));
+            // NOTE: outside the near triangle, the far one is still visible
+            assert_eq!(*canvas.image().get_pixel(20, 32), Rgb([0, 0, 255]));
+        }
+    }
+
+    #[test]
+    fn test_nearer_textured_triangle_wins() {
+        let mut texture = RgbImage::new(2, 1);
+        texture.put_pixel(0, 0, Rgb([255, 0, 0]));
+        texture.put_pixel(1, 0, Rgb([0, 0, 255]));
+        let model = Model {
+            texture_color_map: Some(DynamicImage::ImageRgb8(texture)),
+            ..Default::default()
+        };
+
+        // NOTE: the near triangle samples the red texel, the far one the blue texel
+        let near = [
+            Vector3::new(-0.8, -0.8, 0.2),
+            Vector3::new(0.8, -0.8, 0.2),
+            Vector3::new(0.0, 0.8, 0.2),
+        ];
+        let far = [
+            Vector3::new(-0.8, -0.6, -0.2),
+            Vector3::new(0.8, -0.6, -0.2),
+            Vector3::new(0.0, 0.9, -0.2),
+        ];
+        let red_uv = [Vector2::new(0.25, 0.5); 3];
+        let blue_uv = [Vector2::new(0.75, 0.5); 3];
+
+        for order in [
+            [(&near, &red_uv), (&far, &blue_uv)],
+            [(&far, &blue_uv), (&near, &red_uv)],
+        ] {
+            let mut canvas = Canvas::new(40, 40);
+            for (pts, uv) in order {
+                rasterize_3d_triangle(
+                    pts,
+                    uv,
+                    None,
+                    &RenderOptions::default(),
+                    &mut canvas,
+                    &model,
+                    None,
+                );
+            }
+
+            assert_eq!(*canvas.image().get_pixel(20, 20), Rgb([255, 0, 0]));
+        }
+    }
+
+    #[test]
+    fn test_back_face_culling() {
+        // NOTE: clockwise seen from the camera
+        let pts = [
+            Vector3::new(-0.5, -0.5, 0.0),
+            Vector3::new(0.0, 0.5, 0.0),
+            Vector3::new(0.5, -0.5, 0.0),
+        ];
+        let model = Model::default();
+
+        for (cull, drawn) in [(true, false), (false, true)] {
+            let options = RenderOptions {
+                camera: Camera {
+                    cull_back_faces: cull,
+                    ..Camera::default()
+                },
+                ..Default::default()
+            };
+            let mut canvas = Canvas::new(40, 40);
+            rasterize_3d_triangle(
+                &pts,
+                &[Vector2::zeros(); 3],
+                None,
+                &options,
+                &mut canvas,
+                &model,
+                None,
+            );
+
+            assert_eq!(canvas.depth(20, 20) > f64::MIN, drawn);
+        }
+    }
+
+    #[test]
+    fn test_render_modes() {
+        let obj = "\
+v -0.8 -0.8 0.0
+v 0.8 -0.8 0.3
+v 0.8 0.8 0.3
+v -0.8 0.8 0.0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+f 1/1 2/2 3/3
+f 1/1 3/3 4/4
+";
+        let mut texture = RgbImage::new(2, 2);
+        texture.put_pixel(0, 0, Rgb([255, 0, 0]));
+        texture.put_pixel(1, 0, Rgb([0, 255, 0]));
+        texture.put_pixel(0, 1, Rgb([0, 0, 255]));
+        texture.put_pixel(1, 1, Rgb([255, 255, 0]));
+
+        let modes = [
+            RenderMode::Wireframe,
+            RenderMode::FlatColor(Rgb([255, 0, 255])),
+            RenderMode::FlatShaded,
+            RenderMode::Textured,
+        ];
+        let images = modes.map(|mode| {
+            let model = Model {
+                texture_color_map: Some(DynamicImage::ImageRgb8(texture.clone())),
+                ..Default::default()
+            }
+            .parse_model(obj)
+            .unwrap();
+            let options = RenderOptions {
+                mode,
+                ..Default::default()
+            };
+            let mut canvas = Canvas::new(40, 40);
+            draw_model_with(model, &options, &mut canvas);
+            canvas.into_image()
+        });
+
+        for i in 0..images.len() {
+            for j in i + 1..images.len() {
+                assert_ne!(images[i], images[j], "{:?} vs {:?}", modes[i], modes[j]);
+            }
+        }
+
+        let [wireframe, flat_color, flat_shaded, _] = &images;
+        // NOTE: wireframe only draws the edges, including the shared diagonal
+        assert_eq!(*wireframe.get_pixel(30, 10), Rgb([0, 0, 0]));
+        assert_eq!(*wireframe.get_pixel(20, 20), Rgb([255, 255, 255]));
+        assert_eq!(*flat_color.get_pixel(30, 10), Rgb([255, 0, 255]));
+        // NOTE: the faces are tilted away from the light
+        let shade = flat_shaded.get_pixel(30, 10)[0];
+        assert!(0 < shade && shade < 255, "{shade}");
+    }
+
+    #[test]
+    fn test_flat_normal_map_keeps_shading() {
+        let obj = "\
+v -0.8 -0.8 0.0
+v 0.8 -0.8 0.3
+v 0.8 0.8 0.3
+v -0.8 0.8 0.0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+f 1/1 2/2 3/3
+f 1/1 3/3 4/4
+";
+        let render = |normal_map: Option<Rgb<u8>>| {
+            let model = Model {
+                normal_map: normal_map
+                    .map(|n| DynamicImage::ImageRgb8(RgbImage::from_pixel(4, 4, n))),
+                ..Default::default()
+            }
+            .parse_model(obj)
+            .unwrap();
+            let mut canvas = Canvas::new(40, 40);
+            draw_model(model, &mut canvas);
+            canvas.into_image()
+        };
+
+        let plain = render(None);
+        // NOTE: (128, 128, 255) is the face normal itself, up to the rounding of 128
+        let flat = render(Some(Rgb([128, 128, 255])));
+        assert!(plain
+            .pixels()
+            .zip(flat.pixels())
+            .all(|(a, b)| (0..3).all(|i| a[i].abs_diff(b[i]) <= 1)));
+        let shade = plain.get_pixel(20, 20)[0];
+        assert!(0 < shade && shade < 255, "{shade}");
+
+        // NOTE: normals along +u are almost in the plane of the face, facing away from the light
+        let leaning = render(Some(Rgb([255, 128, 128])));
+        let dark = leaning.get_pixel(20, 20)[0];
+        assert!(dark < shade / 2, "{dark} vs {shade}");
+    }
+
+    #[test]
+    fn test_perspective_correct_uv() {
+        // NOTE: a floor going away from the camera, uv goes from (0, 0) near to (1, 1) far
+        let camera = Camera::look_at(Vector3::zeros(), Vector3::new(0.0, 0.0, -1.0), 90.0).unwrap();
+        let floor = [
+            Vector3::new(-1.0, -1.0, -2.0),
+            Vector3::new(1.0, -1.0, -2.0),
+            Vector3::new(1.0, -1.0, -20.0),
+        ];
+        let uv = [
+            Vector2::new(0.0, 0.0),
+            Vector2::new(1.0, 0.0),
+            Vector2::new(1.0, 1.0),
+        ];
+        let pts = floor.map(|v| camera.project(&v, 800, 800).unwrap());
+        let inv_w = Vector3::from(pts.map(|p| camera.inv_w(&p)));
+
+        // NOTE: the middle of the first and last vertices has the middle uv
+        let center = (floor[0] + floor[2]) / 2.0;
+        let coe = barycentric_coordinates2(&pts, camera.project(&center, 800, 800).unwrap());
+        let sample = |coe: Vector3<f64>| coe.x * uv[0] + coe.y * uv[1] + coe.z * uv[2];
+
+        let expected = Vector2::new(0.5, 0.5);
+        let correct = sample(perspective_correct(&coe, &inv_w));
+        assert!((correct - expected).norm() < 1e-6, "{correct}");
+        // NOTE: linear interpolation in screen space is way off
+        assert!((sample(coe) - expected).norm() > 0.1);
+    }
+
+    #[test]
+    fn test_bilinear_texture_filter() {
+        let mut texture = RgbImage::new(2, 2);
+        texture.put_pixel(0, 0, Rgb([200, 0, 0]));
+        texture.put_pixel(1, 0, Rgb([0, 100, 0]));
+        texture.put_pixel(0, 1, Rgb([0, 0, 40]));
+        texture.put_pixel(1, 1, Rgb([40, 20, 0]));
+        let texture = DynamicImage::ImageRgb8(texture);
+
+        // NOTE: the center is as far from all four texel centers
+        let center = Vector2::new(0.5, 0.5);
+        assert_eq!(
+            TextureFilter::Bilinear.sample(&texture, &center),
+            Vector3::new(60.0, 30.0, 10.0)
+        );
+        assert_eq!(
+            TextureFilter::Nearest.sample(&texture, &center),
+            Vector3::new(40.0, 20.0, 0.0)
+        );
+
+        // NOTE: on a texel center, or past it towards the border, only that texel is seen
+        for uv in [Vector2::new(0.25, 0.25), Vector2::new(0.0, 0.0)] {
+            assert_eq!(
+                TextureFilter::Bilinear.sample(&texture, &uv),
+                Vector3::new(200.0, 0.0, 0.0)
+            );
+        }
+        assert_eq!(
+            TextureFilter::Bilinear.sample(&texture, &Vector2::new(0.5, 0.25)),
+            Vector3::new(100.0, 50.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_checkerboard_wall_at_grazing_angle() {
+        // NOTE: 8 texels alternating white and black along u
+        let texture = RgbImage::from_fn(8, 1, |x, _| {
+            if x.is_multiple_of(2) {
+                Rgb([255, 255, 255])
+            } else {
+                Rgb([0, 0, 0])
+            }
+        });
+        let model = Model {
+            texture_color_map: Some(DynamicImage::ImageRgb8(texture)),
+            ..Default::default()
+        };
+
+        // NOTE: a wall on the left going away from the camera, u goes from 0 near to 1 far
+        let (near, far) = (-1.5, -30.0);
+        let wall = [
+            Vector3::new(-1.0, -1.0, near),
+            Vector3::new(-1.0, -1.0, far),
+            Vector3::new(-1.0, 1.0, far),
+            Vector3::new(-1.0, 1.0, near),
+        ];
+        let uv = [0.0, 1.0, 1.0, 0.0].map(|u| Vector2::new(u, 0.5));
+
+        let mut camera =
+            Camera::look_at(Vector3::zeros(), Vector3::new(0.0, 0.0, -1.0), 90.0).unwrap();
+        camera.cull_back_faces = false;
+        // NOTE: nearest keeps the texel borders sharp, they are where the uv is checked
+        let options = RenderOptions {
+            camera,
+            filter: TextureFilter::Nearest,
+            light_dir: Vector3::new(-1.0, 0.0, 0.0),
+            ..Default::default()
+        };
+
+        let (width, height) = (64, 64);
+        let mut canvas = Canvas::new(width, height);
+        for tri in [[0, 1, 2], [0, 2, 3]] {
+            rasterize_3d_triangle(
+                &tri.map(|i| wall[i]),
+                &tri.map(|i| uv[i]),
+                None,
+                &options,
+                &mut canvas,
+                &model,
+                None,
+            );
+        }
+
+        // NOTE: the wall point seen at screen x, farther points are closer to the center
+        let screen_x = |z: f64| {
+            let p = options
+                .camera
+                .project(&Vector3::new(-1.0, 0.0, z), width, height);
+            p.unwrap().x
+        };
+        let z_at = |x: f64| {
+            let (mut lo, mut hi) = (far, near);
+            for _ in 0..100 {
+                let mid = (lo + hi) / 2.0;
+                if screen_x(mid) > x {
+                    lo = mid;
+                } else {
+                    hi = mid;
+                }
+            }
+            lo
+        };
+        let (x_near, x_far) = (screen_x(near), screen_x(far));
+
+        let row = height / 2;
+        let mut differs_from_affine = 0;
+        for x in x_near.ceil() as u32..x_far as u32 {
+            let correct = (near - z_at(x as f64)) / (near - far);
+            let affine = (x as f64 - x_near) / (x_far - x_near);
+
+            // NOTE: skip pixels too close to a texel border
+            let texel = correct * 8.0;
+            if (texel - texel.round()).abs() < 0.05 {
+                continue;
+            }
+
+            let expected = if (texel as u32).is_multiple_of(2) {
+                255
+            } else {
+                0
+            };
+            assert_eq!(
+                canvas.image().get_pixel(x, row)[0],
+                expected,
+                "x = {x}, u = {correct}"
+            );
+            if (texel as u32) != (affine * 8.0) as u32 {
+                differs_from_affine += 1;
+            }
+        }
+
+        assert!(differs_from_affine > 5, "{differs_from_affine}");
+    }
+
+    #[test]
+    fn test_draw_head_with_gouraud() {
+        let mut canvas = Canvas::new(400, 400);
+        let model = Model::default().load_model("obj/head.obj").unwrap();
+        let options = RenderOptions {
+            mode: RenderMode::Gouraud,
+            ..Default::default()
+        };
+
+        draw_model_with(model, &options, &mut canvas);
+
+        // NOTE: intensities vary smoothly, the forehead is neither black nor white
+        let forehead = canvas.image().get_pixel(200, 330)[0];
+        assert!(0 < forehead && forehead < 255, "{forehead}");
+        let shades = canvas
+            .image()
+            .pixels()
+            .map(|p| p[0])
+            .collect::<std::collections::HashSet<_>>();
+        assert!(shades.len() > 100, "{}", shades.len());
+
+        canvas.save_flipped("output/head_gouraud.tga").unwrap();
+    }
+
+    #[test]
+    fn test_draw_head_lit_from_side() {
+        // NOTE: mean brightness of the left and right halves
+        let halves = |light_dir: Vector3<f64>| {
+            let mut canvas = Canvas::new(200, 200);
+            let model = Model::default().load_model("obj/head.obj").unwrap();
+            let options = RenderOptions {
+                light_dir,
+                ..Default::default()
+            };
+            draw_model_with(model, &options, &mut canvas);
+            let img = canvas.image();
+
+            let mean = |xs: std::ops::Range<u32>| {
+                let sum: f64 = xs
+                    .flat_map(|x| (0..200).map(move |y| (x, y)))
+                    .map(|(x, y)| img.get_pixel(x, y)[0] as f64)
+                    .sum();
+                sum / (100.0 * 200.0)
+            };
+            (mean(0..100), mean(100..200), canvas)
+        };
+
+        let (front_left, front_right, _) = halves(Vector3::new(0.0, 0.0, -1.0));
+        // NOTE: the light travels to -x, so it comes from the right
+        let (side_left, side_right, canvas) = halves(Vector3::new(-2.0, 0.0, -1.0));
+
+        assert!((front_left - front_right).abs() < 0.1 * front_left.max(front_right));
+        assert!(side_right > 2.0 * side_left, "{side_left} {side_right}");
+
+        canvas
+            .save_flipped("output/head_lit_from_side.tga")
+            .unwrap();
+    }
+
+    fn model_with(vertices: usize, textures: usize, normals: usize) -> Model {
+        Model {
+            vertices: vec![Vector3::zeros(); vertices],
+            textures: vec![Vector2::zeros(); textures],
+            normals: vec![Vector3::zeros(); normals],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_face_negative_indices() {
+        let model = model_with(5, 4, 3);
+        let face = &model.parse_face("f -3/-2/-1 -2/-1/-3 -1/-4/-2").unwrap()[0];
+
+        assert_eq!(face.vertex_idx(), Vector3::new(2, 3, 4));
+        assert_eq!(face.texture_idx(), Some(Vector3::new(2, 3, 0)));
+        assert_eq!(face.normal_idx(), Some(Vector3::new(2, 0, 1)));
+
+        assert!(model.parse_face("f -6 -2 -1").is_err());
+    }
+
+    #[test]
+    fn test_parse_face_optional_slots() {
+        let model = model_with(3, 0, 3);
+
+        let face = &model.parse_face("f 1 2 3").unwrap()[0];
+        assert_eq!(face.vertex_idx(), Vector3::new(0, 1, 2));
+        assert_eq!(face.texture_idx(), None);
+        assert_eq!(face.normal_idx(), None);
+
+        let face = &model.parse_face("f 1//3 2//2 3//1").unwrap()[0];
+        assert_eq!(face.texture_idx(), None);
+        assert_eq!(face.normal_idx(), Some(Vector3::new(2, 1, 0)));
+
+        let err = model.parse_face("f /1 2 3").unwrap_err().to_string();
+        assert!(err.contains("Missing vertex index"), "{err}");
+    }
+
+    #[test]
+    fn test_parse_quad_face() {
+        let obj = "\
+v 0 0 0
+v 2 0 0
+v 2 1 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+f 1/1 2/2 3/3 4/4
+";
+        let model = Model::default().parse_model(obj).unwrap();
+
+        assert_eq!(model.faces.len(), 2);
+        assert_eq!(model.faces[0].vertex_idx(), Vector3::new(0, 1, 2));
+        assert_eq!(model.faces[1].vertex_idx(), Vector3::new(0, 2, 3));
+        assert_eq!(model.faces[1].texture_idx(), Some(Vector3::new(0, 2, 3)));
+
+        // NOTE: the two triangles cover the 2x1 quad without overlapping
+        let area = |face: &Face| {
+            let [a, b, c] = [0, 1, 2].map(|i| model.vertices[face.vertex_idx()[i]]);
+            (b - a).cross(&(c - a)).norm() / 2.0
+        };
+        assert!((model.faces.iter().map(area).sum::<f64>() - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_parse_model_mixed_faces() {
+        let obj = "\
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vn 0 0 1
+vn 0 0 -1
+f 1//1 2//1 3//1 4//1
+f -4//-1 -2//-1 -1//-1
+";
+        let model = Model::default().parse_model(obj).unwrap();
+
+        let vertex_idx = model.faces.iter().map(Face::vertex_idx).collect::<Vec<_>>();
+        assert_eq!(
+            vertex_idx,
+            vec![
+                Vector3::new(0, 1, 2),
+                Vector3::new(0, 2, 3),
+                Vector3::new(0, 2, 3)
+            ]
+        );
+        assert!(model.faces.iter().all(|f| f.texture_idx().is_none()));
+        assert_eq!(model.faces[1].normal_idx(), Some(Vector3::new(0, 0, 0)));
+        assert_eq!(model.faces[2].normal_idx(), Some(Vector3::new(1, 1, 1)));
+    }
+
+    #[test]
+    fn test_parse_model_reports_every_bad_line() {
+        let obj = "\
+v 0 0 0
+v 1 0 0
+v 1 1
+v 0 1 0
+f 1 2 3 0
+usemtl missing
+f 1 2 3
+";
+        let err = Model::default().parse_model(obj).err().unwrap();
+        let errors = &err.downcast_ref::<ParseErrors>().unwrap().0;
+
+        assert_eq!(
+            errors.iter().map(|(line, _)| *line).collect::<Vec<_>>(),
+            vec![3, 5, 6]
+        );
+        assert!(errors[1].1.contains("Invalid index 0"), "{}", errors[1].1);
+        assert!(err.to_string().starts_with("3 line(s) failed to parse"));
+    }
+
+    #[test]
+    fn test_load_model_with_materials() {
+        let model = Model::default()
+            .load_model("obj/two_materials.obj")
+            .unwrap();
+
+        assert_eq!(model.materials.len(), 2);
+        let [left, right] =
+            [&model.faces[0], &model.faces[1]].map(|f| &model.materials[f.material_idx().unwrap()]);
+        assert_eq!(left.name, "red");
+        assert_eq!(left.diffuse, Vector3::new(1.0, 0.0, 0.0));
+        assert_eq!(left.specular_exponent, 10.0);
+        assert_eq!(right.name, "blue");
+        assert_eq!(right.diffuse, Vector3::new(0.0, 0.0, 1.0));
+        assert_eq!(
+            model.warnings,
+            [
+                "obj/two_materials.mtl: line 6: unsupported key `illum`, ignored",
+                "obj/two_materials.mtl: line 9: unsupported key `Ka`, ignored",
+            ]
+        );
+
+        // NOTE: each face is drawn with its own diffuse color
+        let mut canvas = Canvas::new(40, 20);
+        draw_model(model, &mut canvas);
+        let img = canvas.image();
+        assert_eq!(*img.get_pixel(8, 10), Rgb([255, 0, 0]));
+        assert_eq!(*img.get_pixel(31, 10), Rgb([0, 0, 255]));
+
+        let err = Model::default()
+            .parse_model("usemtl missing\n")
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(err.contains("missing"), "{err}");
+    }
+
+    #[test]
+    fn test_material_textures_switch_per_face() {
+        let dir = std::env::temp_dir().join("tiny_computer_graphics_material_textures");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        RgbImage::from_pixel(2, 2, Rgb([0, 255, 0]))
+            .save(dir.join("green.png"))
+            .unwrap();
+        fs::write(
+            dir.join("scene.mtl"),
+            "newmtl red\nKd 1 0 0\n\nnewmtl grass\nmap_Kd green.png\n",
+        )
+        .unwrap();
+        let obj = "\
+mtllib scene.mtl
+v -0.9 -0.9 0.0
+v -0.1 -0.9 0.0
+v -0.5 0.9 0.0
+v 0.1 -0.9 0.0
+v 0.9 -0.9 0.0
+v 0.5 0.9 0.0
+vt 0 0
+vt 1 0
+vt 0.5 1
+usemtl red
+f 1/1 2/2 3/3
+usemtl grass
+f 4/1 5/2 6/3
+";
+        fs::write(dir.join("scene.obj"), obj).unwrap();
+
+        let model = Model::default().load_model(dir.join("scene.obj")).unwrap();
+        assert!(model.materials[1].diffuse_map.is_some());
+        assert!(model.warnings.is_empty());
+
+        let mut canvas = Canvas::new(40, 20);
+        draw_model(model, &mut canvas);
+        let img = canvas.image();
+        assert_eq!(*img.get_pixel(8, 10), Rgb([255, 0, 0]));
+        assert_eq!(*img.get_pixel(31, 10), Rgb([0, 255, 0]));
+    }
+
+    #[test]
+    fn test_supersampled_edges_are_smooth() {
+        let obj = "\
+v -1.0 -1.0 0.0
+v 1.0 -1.0 0.0
+v -1.0 1.0 0.0
+f 1 2 3
+";
+        let model = || Model::default().parse_model(obj).unwrap();
+        let is_gray = |p: &Rgb<u8>| 0 < p[0] && p[0] < 255;
+
+        let mut aliased = Canvas::new(16, 16);
+        draw_model(model(), &mut aliased);
+        assert!(!aliased.image().pixels().any(is_gray));
+
+        // NOTE: pixels on the diagonal are partly covered, inside and outside stay as they were
+        let mut smooth = RgbImage::new(16, 16);
+        draw_model_aa(model(), &mut smooth, 2);
+        assert!(smooth.pixels().any(is_gray));
+        assert_eq!(*smooth.get_pixel(2, 2), Rgb([255, 255, 255]));
+        assert_eq!(*smooth.get_pixel(13, 13), Rgb([0, 0, 0]));
+    }
+
+    #[test]
+    fn test_draw_head_supersampled() {
+        let mut img = RgbImage::new(800, 800);
+        let model = Model::default().load_model("obj/head.obj").unwrap();
+
+        draw_model_aa(model, &mut img, 2);
 
         imageops::flip_vertical_in_place(&mut img);
-        img.save("output/head_with_texture.tga").unwrap();
+        img.save("output/head_supersampled.tga").unwrap();
     }
 }
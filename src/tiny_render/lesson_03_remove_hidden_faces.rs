@@ -1,8 +1,94 @@
-use super::Point2D;
-use image::GenericImage;
+use super::{lesson_02_draw_triangle::pixel_span, Point2D};
+use anyhow::Result;
+use image::{imageops, GenericImage, Rgb, RgbImage};
 use na::Vector3;
 use nalgebra::{self as na, Vector2};
 
+use std::path::Path;
+
+/// An image and its depth buffer, y goes up as in the lessons
+///
+/// Bigger z is nearer, a pixel is only drawn over a farther one
+pub struct Canvas {
+    image: RgbImage,
+    depth: Vec<f64>,
+}
+
+impl Canvas {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            image: RgbImage::new(width, height),
+            depth: vec![f64::MIN; (width * height) as usize],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.image.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.image.height()
+    }
+
+    /// Back to black, with nothing drawn in the depth buffer
+    pub fn clear(&mut self) {
+        self.image.pixels_mut().for_each(|p| *p = Rgb([0, 0, 0]));
+        self.depth.fill(f64::MIN);
+    }
+
+    /// Keep `z` at `(x, y)` if it is nearer than what is there, return whether it was kept
+    ///
+    /// Pixels outside the canvas are never kept
+    pub(crate) fn test_depth(&mut self, x: u32, y: u32, z: f64) -> bool {
+        if x >= self.width() || y >= self.height() {
+            return false;
+        }
+
+        let idx = (x + y * self.width()) as usize;
+        if self.depth[idx] < z {
+            self.depth[idx] = z;
+            return true;
+        }
+
+        false
+    }
+
+    /// Draw `pixel` at `(x, y)` if `z` passes the depth test, return whether it was drawn
+    pub fn put_with_depth(&mut self, x: u32, y: u32, z: f64, pixel: Rgb<u8>) -> bool {
+        let drawn = self.test_depth(x, y, z);
+        if drawn {
+            self.image.put_pixel(x, y, pixel);
+        }
+
+        drawn
+    }
+
+    /// Depth at `(x, y)`, `f64::MIN` where nothing is drawn
+    pub fn depth(&self, x: u32, y: u32) -> f64 {
+        self.depth[(x + y * self.width()) as usize]
+    }
+
+    pub fn image(&self) -> &RgbImage {
+        &self.image
+    }
+
+    /// The image, drawing on it directly skips the depth test
+    pub fn image_mut(&mut self) -> &mut RgbImage {
+        &mut self.image
+    }
+
+    pub fn into_image(self) -> RgbImage {
+        self.image
+    }
+
+    /// Save with y going down, as image files expect, the canvas itself is not flipped
+    pub fn save_flipped<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        imageops::flip_vertical(&self.image).save(path)?;
+
+        Ok(())
+    }
+}
+
 pub struct Model {
     pub vertices: Vec<(f64, f64, f64)>,
     // faces stores index, (a, b, c) means ath, bth, and cth vertices form one fase
@@ -21,12 +107,22 @@ pub fn rasterize_2d<I: GenericImage>(
         std::mem::swap(&mut p0, &mut p1);
     }
 
-    for x in p0.x..=p1.x {
+    // NOTE: columns past the image or the buffer are clipped
+    let width = img.width().min(y_buffer.len() as u32);
+    let columns = if p0.x == p1.x {
+        let x = p0.x.floor() + 0.5;
+        pixel_span(x, x, width)
+    } else {
+        pixel_span(p0.x, p1.x, width)
+    };
+
+    for x in columns {
+        // NOTE: sample at the column center
         let y = if p0.x == p1.x {
             p0.y.max(p1.y) as isize
         } else {
-            let t = (x - p0.x) as f64 / (p1.x - p0.x) as f64;
-            (p0.y as f64 + t * (p1.y as f64 - p0.y as f64)) as isize
+            let t = (x as f64 + 0.5 - p0.x) / (p1.x - p0.x);
+            (p0.y + t * (p1.y - p0.y)) as isize
         };
 
         // NOTE: y_buffer is like a infinity hole
@@ -74,15 +170,10 @@ fn is_in_triangle(
     })
 }
 
-pub fn rasterize_3d<I: GenericImage>(
-    pts: &[Vector3<f64>],
-    z_buffer: &mut [f64],
-    img: &mut I,
-    pixel: I::Pixel,
-) {
+pub fn rasterize_3d(pts: &[Vector3<f64>], canvas: &mut Canvas, pixel: Rgb<u8>) {
     let mut bboxmin = Vector2::new(f64::MAX, f64::MAX);
     let mut bboxmax = Vector2::new(f64::MIN, f64::MIN);
-    let clamp = Vector2::new(img.width() as f64 - 1.0, img.height() as f64 - 1.0);
+    let clamp = Vector2::new(canvas.width() as f64 - 1.0, canvas.height() as f64 - 1.0);
 
     for v in pts.iter() {
         // NOTE: iter from x to y
@@ -102,16 +193,12 @@ pub fn rasterize_3d<I: GenericImage>(
             let b = norm.y;
             let c = norm.z;
             p.z = (a * pts[0].x + b * pts[0].y + c * pts[0].z - a * p.x - b * p.y) / c;
-            let idx = (p.x as u32 + p.y as u32 * img.width()) as usize;
 
             if !is_in_triangle(&p, &pts[0], &pts[1], &pts[2]) {
                 continue;
             }
 
-            if z_buffer[idx] < p.z {
-                z_buffer[idx] = p.z;
-                img.put_pixel(p.x as u32, p.y as u32, pixel);
-            }
+            canvas.put_with_depth(x, y, p.z, pixel);
         }
     }
 }
@@ -120,12 +207,10 @@ pub fn rasterize_3d<I: GenericImage>(
 mod test {
     use super::*;
     use crate::tiny_render::Model;
-    use image::{imageops, Rgb, RgbImage};
 
     #[test]
     fn test_rasterized_3d() {
-        let mut img = RgbImage::new(400, 400);
-        let mut z_buffer = vec![f64::MIN; (img.width() * img.height()) as usize];
+        let mut canvas = Canvas::new(400, 400);
 
         let pts = [
             Vector3::new(50.0, 100.0, 0.0),
@@ -133,15 +218,15 @@ mod test {
             Vector3::new(300.0, 50.0, 0.0),
         ];
 
-        rasterize_3d(&pts, &mut z_buffer, &mut img, Rgb([255, 255, 255]));
-        imageops::flip_vertical_in_place(&mut img);
-        img.save("output/head_removing_hidden_faces.tga").unwrap();
+        rasterize_3d(&pts, &mut canvas, Rgb([255, 255, 255]));
+        canvas
+            .save_flipped("output/head_removing_hidden_faces.tga")
+            .unwrap();
     }
 
     #[test]
     fn test_draw_head_removing_hidden_faces() {
-        let mut img = RgbImage::new(800, 800);
-        let mut z_buffer = vec![f64::MIN; (img.width() * img.height()) as usize];
+        let mut canvas = Canvas::new(800, 800);
         let model = Model::load_model("obj/head.obj").unwrap();
         let scale = |p: f64, scl: u32| (p + 1.0) * (scl as f64) / 2.0 + 0.5;
 
@@ -169,19 +254,70 @@ mod test {
 
             let pts = pts_before_scale
                 .into_iter()
-                .map(|v| Vector3::new(scale(v.x, img.width()), scale(v.y, img.height()), v.z))
+                .map(|v| Vector3::new(scale(v.x, canvas.width()), scale(v.y, canvas.height()), v.z))
                 .collect::<Vec<_>>();
 
             if intensity > 0.0 {
                 let color_bit = (Vector3::new(255.0, 255.0, 255.0) * intensity)
                     .map(|x| x.clamp(0.0, 255.0) as u8)
                     .into();
-                rasterize_3d(&pts, &mut z_buffer, &mut img, Rgb(color_bit));
+                rasterize_3d(&pts, &mut canvas, Rgb(color_bit));
             }
         });
 
-        imageops::flip_vertical_in_place(&mut img);
-        img.save("output/head_removing_hidden_faces.tga").unwrap();
+        canvas
+            .save_flipped("output/head_removing_hidden_faces.tga")
+            .unwrap();
+    }
+
+    #[test]
+    fn test_canvas_depth_rejection() {
+        let mut canvas = Canvas::new(4, 4);
+        let (red, blue) = (Rgb([255, 0, 0]), Rgb([0, 0, 255]));
+
+        assert!(canvas.put_with_depth(1, 2, 0.5, red));
+        // NOTE: farther is rejected, nearer wins
+        assert!(!canvas.put_with_depth(1, 2, 0.2, blue));
+        assert_eq!(*canvas.image().get_pixel(1, 2), red);
+        assert!(canvas.put_with_depth(1, 2, 0.8, blue));
+        assert_eq!(*canvas.image().get_pixel(1, 2), blue);
+        assert_eq!(canvas.depth(1, 2), 0.8);
+        assert!(!canvas.put_with_depth(4, 0, 1.0, red));
+
+        // NOTE: the far triangle is drawn last, only where the near one isn't
+        let near = [
+            Vector3::new(0.0, 0.0, 1.0),
+            Vector3::new(4.0, 0.0, 1.0),
+            Vector3::new(0.0, 4.0, 1.0),
+        ];
+        let far = [
+            Vector3::new(0.0, 0.0, -1.0),
+            Vector3::new(4.0, 0.0, -1.0),
+            Vector3::new(4.0, 4.0, -1.0),
+        ];
+        canvas.clear();
+        assert_eq!(canvas.depth(1, 2), f64::MIN);
+        rasterize_3d(&near, &mut canvas, red);
+        rasterize_3d(&far, &mut canvas, blue);
+        assert_eq!(*canvas.image().get_pixel(1, 1), red);
+        assert_eq!(*canvas.image().get_pixel(3, 2), blue);
+    }
+
+    #[test]
+    fn test_render_2d_wider_than_image() {
+        let mut img = RgbImage::new(20, 4);
+        let mut y_buffer = vec![isize::MIN; img.width() as usize];
+
+        rasterize_2d(
+            Point2D::from((10, 0)),
+            Point2D::from((100, 10)),
+            &mut y_buffer,
+            &mut img,
+            Rgb([255, 0, 0]),
+        );
+
+        assert_eq!(*img.get_pixel(9, 0), Rgb([0, 0, 0]));
+        assert!((10..20).all(|x| *img.get_pixel(x, 0) == Rgb([255, 0, 0])));
     }
 
     #[test]
@@ -190,24 +326,24 @@ mod test {
         let mut y_buffer = vec![isize::MIN; img.width() as usize];
 
         rasterize_2d(
-            Point2D { x: 20, y: 32 },
-            Point2D { x: 744, y: 400 },
+            Point2D::from((20, 32)),
+            Point2D::from((744, 400)),
             &mut y_buffer,
             &mut img,
             Rgb([255, 0, 0]),
         );
 
         rasterize_2d(
-            Point2D { x: 120, y: 434 },
-            Point2D { x: 444, y: 400 },
+            Point2D::from((120, 434)),
+            Point2D::from((444, 400)),
             &mut y_buffer,
             &mut img,
             Rgb([0, 255, 0]),
         );
 
         rasterize_2d(
-            Point2D { x: 330, y: 463 },
-            Point2D { x: 594, y: 200 },
+            Point2D::from((330, 463)),
+            Point2D::from((594, 200)),
             &mut y_buffer,
             &mut img,
             Rgb([0, 0, 255]),
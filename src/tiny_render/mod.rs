@@ -4,5 +4,8 @@ pub mod lesson_02_draw_triangle;
 pub mod lesson_03_apply_texture;
 pub mod lesson_03_remove_hidden_faces;
 
-pub use lesson_01_line_drawing_algorithm::{draw_line, Model};
+pub use lesson_01_line_drawing_algorithm::{
+    clip_line, draw_line, draw_line_aa, Model, ParseErrors,
+};
 pub use lesson_02_draw_triangle::{draw_triangle_using_bounding_box, Point2D};
+pub use lesson_03_remove_hidden_faces::Canvas;
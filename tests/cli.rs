@@ -0,0 +1,50 @@
+use tiny_computer_graphics::raytracer::{
+    cli::{self, Options, Strategy},
+    scenes::BUILTIN,
+};
+
+#[test]
+fn test_render_builtin_scenes() {
+    for scene in BUILTIN {
+        for strategy in [Strategy::Lambertian, Strategy::MonteCarlo] {
+            let options = Options {
+                scene: scene.to_string(),
+                width: 8,
+                height: 6,
+                samples: Some(1),
+                strategy,
+                seed: Some(1),
+                ..Options::default()
+            };
+
+            let img = cli::render(&options).unwrap();
+            assert_eq!(img.dimensions(), (8, 6));
+            assert!(img.pixels().any(|p| p.0 != [0, 0, 0]), "{scene} is black");
+        }
+    }
+}
+
+#[test]
+fn test_run_scene_file() {
+    let output = std::env::temp_dir().join("tiny_computer_graphics_cli/first.ppm");
+    let _ = std::fs::remove_file(&output);
+    let options = Options::parse(
+        [
+            "--scene",
+            "scenes/first.toml",
+            "--width",
+            "8",
+            "--height",
+            "6",
+            "--output",
+            output.to_str().unwrap(),
+        ]
+        .map(String::from),
+    )
+    .unwrap();
+
+    cli::run(&options).unwrap();
+
+    let img = image::open(&output).unwrap();
+    assert_eq!((img.width(), img.height()), (8, 6));
+}